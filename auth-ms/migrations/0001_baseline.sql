@@ -0,0 +1,1228 @@
+-- Esquema base de auth-ms. Es idempotente: en bases creadas con el antiguo init.sql
+-- añade las tablas y columnas que faltan sin perder datos (tests/baseline_upgrade.sql). Los cambios posteriores van en migraciones
+-- nuevas; una migración ya aplicada no se modifica (sqlx verifica su checksum).
+-- ==========================
+-- HABILITAR EXTENSION UUID
+-- ==========================
+CREATE EXTENSION IF NOT EXISTS "uuid-ossp";
+CREATE EXTENSION IF NOT EXISTS pgcrypto;
+
+-- ==========================
+-- CONFIGURACIÓN INICIAL DE VARIABLES DE APLICACIÓN
+-- ==========================
+SELECT set_config('app.current_user_id', '', false);
+SELECT set_config('app.current_email', '', false);
+SELECT set_config('app.current_password', '', false);
+
+-- ==========================
+-- TABLA DE USUARIOS (MICROSERVICIO DE AUTENTICACIÓN)
+-- ==========================
+CREATE TABLE IF NOT EXISTS users (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    email VARCHAR(255) UNIQUE NOT NULL,
+    password_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    updated_at TIMESTAMPTZ DEFAULT NOW(),
+    last_login TIMESTAMPTZ,
+    email_verified_at TIMESTAMPTZ,
+    -- Secreto TOTP (base32); pendiente de confirmar mientras totp_enabled_at sea NULL
+    totp_secret TEXT,
+    totp_enabled_at TIMESTAMPTZ,
+    -- Último paso de 30 s aceptado, para impedir reutilizar un código
+    totp_last_used_step BIGINT,
+    CONSTRAINT valid_email CHECK (email ~* '^[A-Za-z0-9._+%-]+@[A-Za-z0-9.-]+[.][A-Za-z]+$')
+);
+
+-- En bases del antiguo init.sql la tabla ya existe sin las columnas nuevas. Las cuentas
+-- anteriores se dan por verificadas para que la política `required` no las bloquee.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema()
+          AND table_name = 'users'
+          AND column_name = 'email_verified_at'
+    ) THEN
+        ALTER TABLE users ADD COLUMN email_verified_at TIMESTAMPTZ;
+        UPDATE users SET email_verified_at = COALESCE(created_at, NOW());
+    END IF;
+END
+$$;
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_secret TEXT;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_enabled_at TIMESTAMPTZ;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS totp_last_used_step BIGINT;
+
+-- ==========================
+-- TABLA DE SESIONES (UNA POR LOGIN; AGRUPA LA FAMILIA DE REFRESH TOKENS)
+-- ==========================
+CREATE TABLE IF NOT EXISTS sessions (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+-- ==========================
+-- TABLA DE REFRESH TOKENS (ROTATIVOS, GUARDADOS COMO HASH)
+-- ==========================
+CREATE TABLE IF NOT EXISTS refresh_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    family_id UUID NOT NULL REFERENCES sessions(id) ON DELETE CASCADE,
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
+
+-- ==========================
+-- TABLA DE ACCESS TOKENS REVOCADOS (POR jti, HASTA QUE EXPIRAN)
+-- ==========================
+CREATE TABLE IF NOT EXISTS revoked_tokens (
+    jti UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMPTZ NOT NULL,
+    revoked_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- ==========================
+-- TABLA DE TOKENS DE RESTABLECIMIENTO DE CONTRASEÑA (UN SOLO USO, GUARDADOS COMO HASH)
+-- ==========================
+CREATE TABLE IF NOT EXISTS password_reset_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    used_at TIMESTAMPTZ
+);
+
+-- ==========================
+-- TABLA DE TOKENS DE VERIFICACIÓN DE EMAIL (UN SOLO USO, GUARDADOS COMO HASH)
+-- ==========================
+CREATE TABLE IF NOT EXISTS email_verification_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    token_hash TEXT UNIQUE NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    used_at TIMESTAMPTZ
+);
+
+-- ==========================
+-- TABLA DE TOKENS DE ACCESO PERSONAL (SCRIPTS E INTEGRACIONES, GUARDADOS COMO HASH)
+-- ==========================
+CREATE TABLE IF NOT EXISTS personal_access_tokens (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    -- Inicio del token en claro, para que el usuario lo reconozca en el listado
+    token_prefix VARCHAR(16) NOT NULL,
+    token_hash TEXT UNIQUE NOT NULL,
+    scopes TEXT[] NOT NULL,
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    revoked_at TIMESTAMPTZ
+);
+
+-- ==========================
+-- TABLA DE CÓDIGOS DE RECUPERACIÓN 2FA (UN SOLO USO, GUARDADOS COMO HASH)
+-- ==========================
+CREATE TABLE IF NOT EXISTS totp_recovery_codes (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    code_hash TEXT NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW(),
+    used_at TIMESTAMPTZ,
+    UNIQUE (user_id, code_hash)
+);
+
+-- ==========================
+-- TABLA DE INTENTOS DE LOGIN (AUDITORÍA)
+-- ==========================
+-- outcome: 'success' | 'failure' | 'locked' (rechazado sin verificar la contraseña)
+CREATE TABLE IF NOT EXISTS login_attempts (
+    id BIGSERIAL PRIMARY KEY,
+    email VARCHAR(255) NOT NULL,
+    ip_address TEXT,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    outcome TEXT NOT NULL CHECK (outcome IN ('success', 'failure', 'locked')),
+    attempted_at TIMESTAMPTZ DEFAULT NOW()
+);
+
+-- ==========================
+-- TABLA DE BLOQUEOS DE LOGIN (POR EMAIL Y POR DIRECCIÓN DEL CLIENTE)
+-- ==========================
+CREATE TABLE IF NOT EXISTS login_lockouts (
+    scope TEXT NOT NULL CHECK (scope IN ('email', 'ip')),
+    key TEXT NOT NULL,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    last_failed_at TIMESTAMPTZ,
+    locked_until TIMESTAMPTZ,
+    PRIMARY KEY (scope, key)
+);
+
+-- ==========================
+-- ÍNDICES PARA USUARIOS
+-- ==========================
+CREATE INDEX IF NOT EXISTS idx_users_email ON users(email);
+CREATE INDEX IF NOT EXISTS idx_users_created_at ON users(created_at);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_user_id ON refresh_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_refresh_tokens_family_id ON refresh_tokens(family_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id);
+CREATE INDEX IF NOT EXISTS idx_sessions_revoked_at ON sessions(revoked_at);
+CREATE INDEX IF NOT EXISTS idx_revoked_tokens_revoked_at ON revoked_tokens(revoked_at);
+CREATE INDEX IF NOT EXISTS idx_password_reset_tokens_user_id ON password_reset_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_email_verification_tokens_user_id ON email_verification_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_user_id ON personal_access_tokens(user_id);
+CREATE INDEX IF NOT EXISTS idx_personal_access_tokens_revoked_at ON personal_access_tokens(revoked_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_email ON login_attempts(email, attempted_at);
+CREATE INDEX IF NOT EXISTS idx_login_attempts_ip_address ON login_attempts(ip_address, attempted_at);
+
+-- ==========================
+-- FUNCIÓN PARA ACTUALIZAR updated_at
+-- ==========================
+CREATE OR REPLACE FUNCTION update_updated_at_column()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.updated_at = NOW();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trigger_update_users_updated_at ON users;
+CREATE TRIGGER trigger_update_users_updated_at
+    BEFORE UPDATE ON users
+    FOR EACH ROW
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- ==========================
+-- ROW LEVEL SECURITY (RLS)
+-- ==========================
+ALTER TABLE users ENABLE ROW LEVEL SECURITY;
+-- Sin políticas: solo accesibles a través de las funciones SECURITY DEFINER
+ALTER TABLE sessions ENABLE ROW LEVEL SECURITY;
+ALTER TABLE refresh_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE revoked_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE password_reset_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE email_verification_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE login_attempts ENABLE ROW LEVEL SECURITY;
+ALTER TABLE totp_recovery_codes ENABLE ROW LEVEL SECURITY;
+ALTER TABLE personal_access_tokens ENABLE ROW LEVEL SECURITY;
+ALTER TABLE login_lockouts ENABLE ROW LEVEL SECURITY;
+
+-- ==========================
+-- FUNCIÓN PARA OBTENER USER_ID ACTUAL
+-- ==========================
+CREATE OR REPLACE FUNCTION current_user_id()
+RETURNS UUID AS $$
+BEGIN
+    RETURN current_setting('app.current_user_id', true)::UUID;
+EXCEPTION WHEN OTHERS THEN
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER;
+
+-- ==========================
+-- FUNCIÓN PARA OBTENER USER
+-- ==========================
+DROP FUNCTION IF EXISTS get_user_by_id(UUID);
+CREATE OR REPLACE FUNCTION get_user_by_id(user_uuid UUID)
+RETURNS TABLE (
+    user_id UUID,
+    user_email VARCHAR,
+    user_created_at TIMESTAMPTZ,
+    user_email_verified_at TIMESTAMPTZ
+) AS $$
+BEGIN
+    -- Establecer el current_user_id para RLS (si es necesario)
+    EXECUTE format('SET LOCAL app.current_user_id = %L', user_uuid::text);
+    
+    -- Retornar los datos del usuario
+    RETURN QUERY
+    SELECT id, email, created_at, email_verified_at
+    FROM users
+    WHERE id = user_uuid;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER;
+
+-- ==========================
+-- FUNCIÓN PARA ESTABLECER CREDENCIALES TEMPORALES
+-- ==========================
+CREATE OR REPLACE FUNCTION set_app_current_credentials(p_email VARCHAR, p_password TEXT)
+RETURNS VOID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    PERFORM set_config('app.current_email', p_email, false);
+    PERFORM set_config('app.current_password', p_password, false);
+END;
+$$;
+
+-- ==========================
+-- CREAR ROL APP_USER
+-- ==========================
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'app_user') THEN
+        CREATE ROLE app_user LOGIN PASSWORD 'apppass';
+    END IF;
+END
+$$;
+
+-- ==========================
+-- POLÍTICAS RLS PARA USUARIOS
+-- ==========================
+-- Política para verificación de login
+DROP POLICY IF EXISTS "Allow login verification" ON users;
+CREATE POLICY "Allow login verification"
+ON users FOR SELECT
+TO app_user
+USING (
+    email = current_setting('app.current_email', true)::VARCHAR
+    AND password_hash = crypt(
+        current_setting('app.current_password', true)::TEXT, 
+        password_hash
+    )
+);
+
+-- Política para que usuarios vean sus propios datos
+DROP POLICY IF EXISTS "Users can view own data" ON users;
+CREATE POLICY "Users can view own data"
+ON users FOR SELECT
+USING (id = current_user_id());
+
+-- Política para que usuarios actualicen sus propios datos
+DROP POLICY IF EXISTS "Users can update own data" ON users;
+CREATE POLICY "Users can update own data"
+ON users FOR UPDATE
+USING (id = current_user_id())
+WITH CHECK (id = current_user_id());
+
+-- Política para permitir registro de usuarios
+DROP POLICY IF EXISTS "Allow user registration" ON users;
+CREATE POLICY "Allow user registration"
+ON users FOR INSERT
+TO app_user
+WITH CHECK (email IS NOT NULL AND password_hash IS NOT NULL);
+
+-- ==========================
+-- FUNCIÓN DE REGISTRO DE USUARIO
+-- ==========================
+CREATE OR REPLACE FUNCTION register_user(
+    p_email VARCHAR(255),
+    p_password TEXT
+)
+RETURNS TABLE (
+    user_id UUID,
+    user_email VARCHAR(255),
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+SECURITY DEFINER
+AS $$
+DECLARE
+    new_user_id UUID;
+BEGIN
+    -- Validar formato de email
+    IF p_email !~* '^[A-Za-z0-9._+%-]+@[A-Za-z0-9.-]+[.][A-Za-z]+$' THEN
+        RAISE EXCEPTION 'Invalid email format';
+    END IF;
+
+    -- Verificar que el email no exista
+    IF EXISTS (SELECT 1 FROM users WHERE email = p_email) THEN
+        RAISE EXCEPTION 'Email already registered';
+    END IF;
+
+    -- Insertar nuevo usuario con password hasheado
+    INSERT INTO users (
+        email,
+        password_hash
+    ) VALUES (
+        p_email,
+        crypt(p_password, gen_salt('bf'))
+    )
+    RETURNING id INTO new_user_id;
+
+    -- Retornar los datos del usuario creado
+    RETURN QUERY
+    SELECT 
+        u.id,
+        u.email AS user_email,
+        u.created_at
+    FROM users u
+    WHERE u.id = new_user_id;
+END;
+$$;
+
+-- ==========================
+-- FUNCIÓN DE VERIFICACIÓN DE LOGIN
+-- ==========================
+DROP FUNCTION IF EXISTS verify_login_credentials(VARCHAR, TEXT);
+CREATE OR REPLACE FUNCTION verify_login_credentials(p_email VARCHAR, p_password TEXT)
+RETURNS TABLE (
+    id UUID,
+    email VARCHAR(255),
+    password_hash TEXT,
+    created_at TIMESTAMPTZ,
+    email_verified_at TIMESTAMPTZ,
+    totp_enabled BOOLEAN
+)
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    -- Establecer credenciales temporales
+    PERFORM set_app_current_credentials(p_email, p_password);
+    
+    -- Obtener el ID del usuario
+    SELECT u.id INTO v_user_id
+    FROM users u
+    WHERE u.email = p_email
+    AND u.password_hash = crypt(p_password, u.password_hash);
+    
+    -- Establecer el user_id en la configuración para RLS
+    IF v_user_id IS NOT NULL THEN
+        PERFORM set_config('app.current_user_id', v_user_id::text, false);
+        
+        -- Actualizar last_login
+        UPDATE users u
+        SET last_login = NOW(),
+            updated_at = NOW()
+        WHERE u.id = v_user_id;
+        
+        -- Retornar los datos del usuario
+        RETURN QUERY
+        SELECT 
+            u.id,
+            u.email,
+            u.password_hash,
+            u.created_at,
+            u.email_verified_at,
+            u.totp_enabled_at IS NOT NULL
+        FROM users u
+        WHERE u.id = v_user_id;
+    END IF;
+    
+    -- Limpiar credenciales temporales
+    PERFORM set_config('app.current_email', '', true);
+    PERFORM set_config('app.current_password', '', true);
+    PERFORM set_config('app.current_user_id', '', true);
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIÓN PARA EMITIR REFRESH TOKEN
+-- ==========================
+-- Guarda solo el hash SHA-256 del token. Si no se indica familia se abre una nueva
+-- sesión (un login = una familia); la rotación reutiliza la familia del token anterior.
+CREATE OR REPLACE FUNCTION create_refresh_token(
+    p_user_id UUID,
+    p_token TEXT,
+    p_expires_at TIMESTAMPTZ,
+    p_family_id UUID DEFAULT NULL
+)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_family_id UUID := p_family_id;
+BEGIN
+    IF v_family_id IS NULL THEN
+        INSERT INTO sessions (user_id)
+        VALUES (p_user_id)
+        RETURNING id INTO v_family_id;
+    END IF;
+
+    INSERT INTO refresh_tokens (user_id, family_id, token_hash, expires_at)
+    VALUES (p_user_id, v_family_id, encode(digest(p_token, 'sha256'), 'hex'), p_expires_at);
+
+    RETURN v_family_id;
+END;
+$$;
+
+-- ==========================
+-- FUNCIONES DE REVOCACIÓN (LOGOUT)
+-- ==========================
+-- Revoca una sesión (y con ella su familia de refresh tokens) y, si se indica,
+-- el access token concreto por su jti.
+CREATE OR REPLACE FUNCTION revoke_session(
+    p_user_id UUID,
+    p_session_id UUID,
+    p_jti UUID DEFAULT NULL,
+    p_token_expires_at TIMESTAMPTZ DEFAULT NULL
+)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    IF p_jti IS NOT NULL AND p_token_expires_at IS NOT NULL THEN
+        INSERT INTO revoked_tokens (jti, user_id, expires_at)
+        VALUES (p_jti, p_user_id, p_token_expires_at)
+        ON CONFLICT (jti) DO NOTHING;
+    END IF;
+
+    UPDATE refresh_tokens rt
+    SET revoked_at = NOW()
+    WHERE rt.family_id = p_session_id
+      AND rt.user_id = p_user_id
+      AND rt.revoked_at IS NULL;
+
+    UPDATE sessions s
+    SET revoked_at = NOW()
+    WHERE s.id = p_session_id
+      AND s.user_id = p_user_id
+      AND s.revoked_at IS NULL;
+
+    RETURN FOUND;
+END;
+$$;
+
+-- Revoca todas las sesiones activas del usuario; retorna cuántas se cerraron
+CREATE OR REPLACE FUNCTION revoke_all_sessions(p_user_id UUID)
+RETURNS INTEGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_count INTEGER;
+BEGIN
+    UPDATE refresh_tokens rt
+    SET revoked_at = NOW()
+    WHERE rt.user_id = p_user_id
+      AND rt.revoked_at IS NULL;
+
+    UPDATE sessions s
+    SET revoked_at = NOW()
+    WHERE s.user_id = p_user_id
+      AND s.revoked_at IS NULL;
+
+    GET DIAGNOSTICS v_count = ROW_COUNT;
+    RETURN v_count;
+END;
+$$;
+
+-- Indica si un access token fue revocado por su jti o por su sesión
+CREATE OR REPLACE FUNCTION is_token_revoked(p_jti UUID, p_session_id UUID)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT EXISTS (SELECT 1 FROM revoked_tokens rt WHERE rt.jti = p_jti)
+        OR EXISTS (SELECT 1 FROM sessions s WHERE s.id = p_session_id AND s.revoked_at IS NOT NULL);
+$$;
+
+-- Revocaciones desde p_since que aún afectan a access tokens vigentes.
+-- Una sesión revocada deja de importar cuando expira el último access token
+-- que pudo emitirse antes de revocarla (p_token_ttl después).
+CREATE OR REPLACE FUNCTION get_revocations(p_since TIMESTAMPTZ, p_token_ttl INTERVAL)
+RETURNS TABLE (
+    kind TEXT,
+    id UUID,
+    expires_at TIMESTAMPTZ
+)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT 'token'::TEXT, rt.jti, rt.expires_at
+    FROM revoked_tokens rt
+    WHERE rt.revoked_at >= p_since
+      AND rt.expires_at > NOW()
+    UNION ALL
+    SELECT 'session'::TEXT, s.id, s.revoked_at + p_token_ttl
+    FROM sessions s
+    WHERE s.revoked_at >= p_since
+      AND s.revoked_at + p_token_ttl > NOW()
+    UNION ALL
+    SELECT 'personal_access_token'::TEXT, pat.id, pat.revoked_at + p_token_ttl
+    FROM personal_access_tokens pat
+    WHERE pat.revoked_at >= p_since
+      AND pat.revoked_at + p_token_ttl > NOW();
+$$;
+
+-- ==========================
+-- FUNCIÓN PARA ROTAR REFRESH TOKEN
+-- ==========================
+-- status: 'ok' | 'invalid' | 'expired' | 'revoked' | 'reused'
+-- Presentar un token ya rotado se considera robo: se revoca toda la familia.
+CREATE OR REPLACE FUNCTION rotate_refresh_token(
+    p_token TEXT,
+    p_new_token TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS TABLE (
+    status TEXT,
+    user_id UUID,
+    user_email VARCHAR(255),
+    session_id UUID
+)
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_token refresh_tokens%ROWTYPE;
+BEGIN
+    SELECT rt.* INTO v_token
+    FROM refresh_tokens rt
+    WHERE rt.token_hash = encode(digest(p_token, 'sha256'), 'hex')
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN QUERY SELECT 'invalid'::TEXT, NULL::UUID, NULL::VARCHAR(255), NULL::UUID;
+        RETURN;
+    END IF;
+
+    IF v_token.revoked_at IS NOT NULL
+       OR EXISTS (SELECT 1 FROM sessions s WHERE s.id = v_token.family_id AND s.revoked_at IS NOT NULL) THEN
+        RETURN QUERY SELECT 'revoked'::TEXT, v_token.user_id, NULL::VARCHAR(255), v_token.family_id;
+        RETURN;
+    END IF;
+
+    IF v_token.used_at IS NOT NULL THEN
+        PERFORM revoke_session(v_token.user_id, v_token.family_id);
+
+        RETURN QUERY SELECT 'reused'::TEXT, v_token.user_id, NULL::VARCHAR(255), v_token.family_id;
+        RETURN;
+    END IF;
+
+    IF v_token.expires_at <= NOW() THEN
+        RETURN QUERY SELECT 'expired'::TEXT, v_token.user_id, NULL::VARCHAR(255), v_token.family_id;
+        RETURN;
+    END IF;
+
+    UPDATE refresh_tokens rt
+    SET used_at = NOW()
+    WHERE rt.id = v_token.id;
+
+    PERFORM create_refresh_token(v_token.user_id, p_new_token, p_expires_at, v_token.family_id);
+
+    RETURN QUERY
+    SELECT 'ok'::TEXT, u.id, u.email, v_token.family_id
+    FROM users u
+    WHERE u.id = v_token.user_id;
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIONES DE CAMBIO Y RESTABLECIMIENTO DE CONTRASEÑA
+-- ==========================
+-- Cambia la contraseña verificando la actual. Retorna cuántas sesiones se revocaron,
+-- o NULL si la contraseña actual no coincide.
+CREATE OR REPLACE FUNCTION change_password(
+    p_user_id UUID,
+    p_current_password TEXT,
+    p_new_password TEXT
+)
+RETURNS INTEGER
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE users u
+    SET password_hash = crypt(p_new_password, gen_salt('bf')),
+        updated_at = NOW()
+    WHERE u.id = p_user_id
+      AND u.password_hash = crypt(p_current_password, u.password_hash);
+
+    IF NOT FOUND THEN
+        RETURN NULL;
+    END IF;
+
+    DELETE FROM password_reset_tokens prt WHERE prt.user_id = p_user_id;
+
+    RETURN revoke_all_sessions(p_user_id);
+END;
+$$;
+
+-- Registra un token de restablecimiento para el email indicado, invalidando los anteriores.
+-- Retorna el id del usuario, o NULL si el email no está registrado.
+CREATE OR REPLACE FUNCTION create_password_reset_token(
+    p_email VARCHAR,
+    p_token TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    SELECT u.id INTO v_user_id
+    FROM users u
+    WHERE u.email = p_email;
+
+    IF v_user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    DELETE FROM password_reset_tokens prt
+    WHERE prt.user_id = v_user_id
+      AND prt.used_at IS NULL;
+
+    INSERT INTO password_reset_tokens (user_id, token_hash, expires_at)
+    VALUES (v_user_id, encode(digest(p_token, 'sha256'), 'hex'), p_expires_at);
+
+    RETURN v_user_id;
+END;
+$$;
+
+-- Consume un token de restablecimiento vigente y fija la nueva contraseña,
+-- revocando todas las sesiones. Retorna el id del usuario, o NULL si el token
+-- no existe, ya fue usado o expiró.
+CREATE OR REPLACE FUNCTION reset_password(p_token TEXT, p_new_password TEXT)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    UPDATE password_reset_tokens prt
+    SET used_at = NOW()
+    WHERE prt.token_hash = encode(digest(p_token, 'sha256'), 'hex')
+      AND prt.used_at IS NULL
+      AND prt.expires_at > NOW()
+    RETURNING prt.user_id INTO v_user_id;
+
+    IF v_user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    -- Recibir el enlace por correo también demuestra la propiedad del email
+    UPDATE users u
+    SET password_hash = crypt(p_new_password, gen_salt('bf')),
+        email_verified_at = COALESCE(u.email_verified_at, NOW()),
+        updated_at = NOW()
+    WHERE u.id = v_user_id;
+
+    DELETE FROM password_reset_tokens prt
+    WHERE prt.user_id = v_user_id
+      AND prt.used_at IS NULL;
+
+    PERFORM revoke_all_sessions(v_user_id);
+
+    RETURN v_user_id;
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIONES DE VERIFICACIÓN DE EMAIL
+-- ==========================
+-- Registra un token de verificación para el email indicado, invalidando los anteriores.
+-- Retorna el id del usuario, o NULL si el email no está registrado o ya fue verificado.
+CREATE OR REPLACE FUNCTION create_email_verification_token(
+    p_email VARCHAR,
+    p_token TEXT,
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    SELECT u.id INTO v_user_id
+    FROM users u
+    WHERE u.email = p_email
+      AND u.email_verified_at IS NULL;
+
+    IF v_user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    DELETE FROM email_verification_tokens evt
+    WHERE evt.user_id = v_user_id
+      AND evt.used_at IS NULL;
+
+    INSERT INTO email_verification_tokens (user_id, token_hash, expires_at)
+    VALUES (v_user_id, encode(digest(p_token, 'sha256'), 'hex'), p_expires_at);
+
+    RETURN v_user_id;
+END;
+$$;
+
+-- Consume un token de verificación vigente y marca el email como verificado.
+-- Retorna el id del usuario, o NULL si el token no existe, ya fue usado o expiró.
+CREATE OR REPLACE FUNCTION verify_email(p_token TEXT)
+RETURNS UUID
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_user_id UUID;
+BEGIN
+    UPDATE email_verification_tokens evt
+    SET used_at = NOW()
+    WHERE evt.token_hash = encode(digest(p_token, 'sha256'), 'hex')
+      AND evt.used_at IS NULL
+      AND evt.expires_at > NOW()
+    RETURNING evt.user_id INTO v_user_id;
+
+    IF v_user_id IS NULL THEN
+        RETURN NULL;
+    END IF;
+
+    UPDATE users u
+    SET email_verified_at = COALESCE(u.email_verified_at, NOW()),
+        updated_at = NOW()
+    WHERE u.id = v_user_id;
+
+    DELETE FROM email_verification_tokens evt
+    WHERE evt.user_id = v_user_id
+      AND evt.used_at IS NULL;
+
+    RETURN v_user_id;
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIONES DE PROTECCIÓN CONTRA FUERZA BRUTA
+-- ==========================
+-- Retorna hasta cuándo está bloqueado el login para el email o la dirección indicados
+-- (el mayor de ambos), o NULL si ninguno está bloqueado. Registra el intento rechazado.
+CREATE OR REPLACE FUNCTION check_login_lockout(p_email VARCHAR, p_ip_address TEXT)
+RETURNS TIMESTAMPTZ
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_locked_until TIMESTAMPTZ;
+BEGIN
+    SELECT MAX(ll.locked_until) INTO v_locked_until
+    FROM login_lockouts ll
+    WHERE ((ll.scope = 'email' AND ll.key = lower(p_email))
+        OR (ll.scope = 'ip' AND ll.key = p_ip_address))
+      AND ll.locked_until > NOW();
+
+    IF v_locked_until IS NOT NULL THEN
+        INSERT INTO login_attempts (email, ip_address, outcome)
+        VALUES (lower(p_email), p_ip_address, 'locked');
+    END IF;
+
+    RETURN v_locked_until;
+END;
+$$;
+
+-- Suma un fallo al contador de (p_scope, p_key). Al alcanzar p_max_attempts se bloquea
+-- durante p_base_lockout, duplicando la duración con cada fallo adicional hasta p_max_lockout.
+-- Los fallos más antiguos que p_window no cuentan.
+CREATE OR REPLACE FUNCTION register_login_failure(
+    p_scope TEXT,
+    p_key TEXT,
+    p_max_attempts INTEGER,
+    p_base_lockout INTERVAL,
+    p_max_lockout INTERVAL,
+    p_window INTERVAL
+)
+RETURNS TIMESTAMPTZ
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_failed_count INTEGER;
+    v_locked_until TIMESTAMPTZ;
+BEGIN
+    INSERT INTO login_lockouts AS ll (scope, key, failed_count, last_failed_at)
+    VALUES (p_scope, p_key, 1, NOW())
+    ON CONFLICT (scope, key) DO UPDATE
+    SET failed_count = CASE
+            WHEN ll.last_failed_at < NOW() - p_window THEN 1
+            ELSE ll.failed_count + 1
+        END,
+        last_failed_at = NOW()
+    RETURNING ll.failed_count INTO v_failed_count;
+
+    IF v_failed_count < p_max_attempts THEN
+        RETURN NULL;
+    END IF;
+
+    v_locked_until := NOW() + LEAST(
+        p_base_lockout * power(2, LEAST(v_failed_count - p_max_attempts, 30)),
+        p_max_lockout
+    );
+
+    UPDATE login_lockouts ll
+    SET locked_until = v_locked_until
+    WHERE ll.scope = p_scope
+      AND ll.key = p_key;
+
+    RETURN v_locked_until;
+END;
+$$;
+
+-- Registra el resultado de un intento de login para auditoría y actualiza los contadores.
+-- Un login correcto reinicia el contador del email (no el de la dirección, que puede
+-- estar probando varias cuentas). Retorna el bloqueo resultante, o NULL si no hay.
+CREATE OR REPLACE FUNCTION record_login_attempt(
+    p_email VARCHAR,
+    p_ip_address TEXT,
+    p_user_id UUID,
+    p_succeeded BOOLEAN,
+    p_max_attempts_per_email INTEGER,
+    p_max_attempts_per_ip INTEGER,
+    p_base_lockout INTERVAL,
+    p_max_lockout INTERVAL,
+    p_window INTERVAL
+)
+RETURNS TIMESTAMPTZ
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+DECLARE
+    v_email_locked_until TIMESTAMPTZ;
+    v_ip_locked_until TIMESTAMPTZ;
+BEGIN
+    INSERT INTO login_attempts (email, ip_address, user_id, outcome)
+    VALUES (
+        lower(p_email),
+        p_ip_address,
+        p_user_id,
+        CASE WHEN p_succeeded THEN 'success' ELSE 'failure' END
+    );
+
+    IF p_succeeded THEN
+        DELETE FROM login_lockouts ll
+        WHERE ll.scope = 'email'
+          AND ll.key = lower(p_email);
+
+        RETURN NULL;
+    END IF;
+
+    v_email_locked_until := register_login_failure(
+        'email', lower(p_email), p_max_attempts_per_email, p_base_lockout, p_max_lockout, p_window
+    );
+
+    IF p_ip_address IS NOT NULL THEN
+        v_ip_locked_until := register_login_failure(
+            'ip', p_ip_address, p_max_attempts_per_ip, p_base_lockout, p_max_lockout, p_window
+        );
+    END IF;
+
+    RETURN GREATEST(v_email_locked_until, v_ip_locked_until);
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIONES DE AUTENTICACIÓN EN DOS PASOS (TOTP)
+-- ==========================
+-- Verifica la contraseña de un usuario ya autenticado (re-autenticación)
+CREATE OR REPLACE FUNCTION verify_password(p_user_id UUID, p_password TEXT)
+RETURNS BOOLEAN
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT EXISTS (
+        SELECT 1 FROM users u
+        WHERE u.id = p_user_id
+          AND u.password_hash = crypt(p_password, u.password_hash)
+    );
+$$;
+
+-- Estado 2FA del usuario
+CREATE OR REPLACE FUNCTION get_totp_state(p_user_id UUID)
+RETURNS TABLE (
+    email VARCHAR(255),
+    secret TEXT,
+    enabled BOOLEAN
+)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT u.email, u.totp_secret, u.totp_enabled_at IS NOT NULL
+    FROM users u
+    WHERE u.id = p_user_id;
+$$;
+
+-- Guarda un secreto pendiente de confirmación. Retorna FALSE si 2FA ya está activo.
+CREATE OR REPLACE FUNCTION begin_totp_setup(p_user_id UUID, p_secret TEXT)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE users u
+    SET totp_secret = p_secret,
+        totp_last_used_step = NULL,
+        updated_at = NOW()
+    WHERE u.id = p_user_id
+      AND u.totp_enabled_at IS NULL;
+
+    RETURN FOUND;
+END;
+$$;
+
+-- Acepta el paso TOTP indicado solo si es posterior al último usado (evita repetir un código)
+CREATE OR REPLACE FUNCTION consume_totp_step(p_user_id UUID, p_step BIGINT)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE users u
+    SET totp_last_used_step = p_step
+    WHERE u.id = p_user_id
+      AND (u.totp_last_used_step IS NULL OR u.totp_last_used_step < p_step);
+
+    RETURN FOUND;
+END;
+$$;
+
+-- Activa 2FA con el secreto pendiente y reemplaza los códigos de recuperación
+CREATE OR REPLACE FUNCTION enable_totp(p_user_id UUID, p_recovery_codes TEXT[])
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE users u
+    SET totp_enabled_at = NOW(),
+        updated_at = NOW()
+    WHERE u.id = p_user_id
+      AND u.totp_secret IS NOT NULL
+      AND u.totp_enabled_at IS NULL;
+
+    IF NOT FOUND THEN
+        RETURN FALSE;
+    END IF;
+
+    DELETE FROM totp_recovery_codes trc WHERE trc.user_id = p_user_id;
+
+    INSERT INTO totp_recovery_codes (user_id, code_hash)
+    SELECT p_user_id, encode(digest(code, 'sha256'), 'hex')
+    FROM unnest(p_recovery_codes) AS code;
+
+    RETURN TRUE;
+END;
+$$;
+
+-- Consume un código de recuperación no usado
+CREATE OR REPLACE FUNCTION use_totp_recovery_code(p_user_id UUID, p_code TEXT)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE totp_recovery_codes trc
+    SET used_at = NOW()
+    WHERE trc.user_id = p_user_id
+      AND trc.code_hash = encode(digest(p_code, 'sha256'), 'hex')
+      AND trc.used_at IS NULL;
+
+    RETURN FOUND;
+END;
+$$;
+
+-- Desactiva 2FA y elimina el secreto y los códigos de recuperación
+CREATE OR REPLACE FUNCTION disable_totp(p_user_id UUID)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE users u
+    SET totp_secret = NULL,
+        totp_enabled_at = NULL,
+        totp_last_used_step = NULL,
+        updated_at = NOW()
+    WHERE u.id = p_user_id
+      AND u.totp_enabled_at IS NOT NULL;
+
+    IF NOT FOUND THEN
+        RETURN FALSE;
+    END IF;
+
+    DELETE FROM totp_recovery_codes trc WHERE trc.user_id = p_user_id;
+
+    RETURN TRUE;
+END;
+$$;
+
+
+-- ==========================
+-- FUNCIONES DE TOKENS DE ACCESO PERSONAL
+-- ==========================
+CREATE OR REPLACE FUNCTION create_personal_access_token(
+    p_user_id UUID,
+    p_name VARCHAR,
+    p_token TEXT,
+    p_scopes TEXT[],
+    p_expires_at TIMESTAMPTZ
+)
+RETURNS TABLE (
+    id UUID,
+    name VARCHAR(100),
+    token_prefix VARCHAR(16),
+    scopes TEXT[],
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    INSERT INTO personal_access_tokens AS pat (user_id, name, token_prefix, token_hash, scopes, expires_at)
+    VALUES (
+        p_user_id,
+        p_name,
+        left(p_token, 12),
+        encode(digest(p_token, 'sha256'), 'hex'),
+        p_scopes,
+        p_expires_at
+    )
+    RETURNING pat.id, pat.name, pat.token_prefix, pat.scopes, pat.expires_at, pat.last_used_at, pat.created_at;
+END;
+$$;
+
+-- Tokens no revocados del usuario (incluye los expirados para que puedan eliminarse)
+CREATE OR REPLACE FUNCTION list_personal_access_tokens(p_user_id UUID)
+RETURNS TABLE (
+    id UUID,
+    name VARCHAR(100),
+    token_prefix VARCHAR(16),
+    scopes TEXT[],
+    expires_at TIMESTAMPTZ,
+    last_used_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ
+)
+LANGUAGE sql
+STABLE
+SECURITY DEFINER
+SET search_path = public
+AS $$
+    SELECT pat.id, pat.name, pat.token_prefix, pat.scopes, pat.expires_at, pat.last_used_at, pat.created_at
+    FROM personal_access_tokens pat
+    WHERE pat.user_id = p_user_id
+      AND pat.revoked_at IS NULL
+    ORDER BY pat.created_at DESC;
+$$;
+
+CREATE OR REPLACE FUNCTION revoke_personal_access_token(p_user_id UUID, p_token_id UUID)
+RETURNS BOOLEAN
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    UPDATE personal_access_tokens pat
+    SET revoked_at = NOW()
+    WHERE pat.id = p_token_id
+      AND pat.user_id = p_user_id
+      AND pat.revoked_at IS NULL;
+
+    RETURN FOUND;
+END;
+$$;
+
+-- Valida un token (no revocado ni expirado) y registra su uso.
+-- No retorna filas si el token no es válido.
+CREATE OR REPLACE FUNCTION verify_personal_access_token(p_token TEXT)
+RETURNS TABLE (
+    id UUID,
+    user_id UUID,
+    scopes TEXT[],
+    expires_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+SECURITY DEFINER
+SET search_path = public
+AS $$
+BEGIN
+    RETURN QUERY
+    UPDATE personal_access_tokens pat
+    SET last_used_at = NOW()
+    WHERE pat.token_hash = encode(digest(p_token, 'sha256'), 'hex')
+      AND pat.revoked_at IS NULL
+      AND (pat.expires_at IS NULL OR pat.expires_at > NOW())
+    RETURNING pat.id, pat.user_id, pat.scopes, pat.expires_at;
+END;
+$$;
+
+
+-- ==========================
+-- CONCEDER PERMISOS
+-- ==========================
+GRANT CONNECT ON DATABASE auth_db TO app_user;
+GRANT USAGE ON SCHEMA public TO app_user;
+
+GRANT EXECUTE ON FUNCTION verify_login_credentials TO app_user;
+GRANT EXECUTE ON FUNCTION set_app_current_credentials TO app_user;
+GRANT EXECUTE ON FUNCTION register_user TO app_user;
+GRANT EXECUTE ON FUNCTION current_user_id TO app_user;
+GRANT EXECUTE ON FUNCTION get_user_by_id TO app_user;
+GRANT EXECUTE ON FUNCTION create_refresh_token TO app_user;
+GRANT EXECUTE ON FUNCTION rotate_refresh_token TO app_user;
+GRANT EXECUTE ON FUNCTION revoke_session TO app_user;
+GRANT EXECUTE ON FUNCTION revoke_all_sessions TO app_user;
+GRANT EXECUTE ON FUNCTION is_token_revoked TO app_user;
+GRANT EXECUTE ON FUNCTION get_revocations TO app_user;
+GRANT EXECUTE ON FUNCTION change_password TO app_user;
+GRANT EXECUTE ON FUNCTION create_password_reset_token TO app_user;
+GRANT EXECUTE ON FUNCTION reset_password TO app_user;
+GRANT EXECUTE ON FUNCTION create_email_verification_token TO app_user;
+GRANT EXECUTE ON FUNCTION verify_email TO app_user;
+GRANT EXECUTE ON FUNCTION check_login_lockout TO app_user;
+GRANT EXECUTE ON FUNCTION register_login_failure TO app_user;
+GRANT EXECUTE ON FUNCTION record_login_attempt TO app_user;
+GRANT EXECUTE ON FUNCTION verify_password TO app_user;
+GRANT EXECUTE ON FUNCTION get_totp_state TO app_user;
+GRANT EXECUTE ON FUNCTION begin_totp_setup TO app_user;
+GRANT EXECUTE ON FUNCTION consume_totp_step TO app_user;
+GRANT EXECUTE ON FUNCTION enable_totp TO app_user;
+GRANT EXECUTE ON FUNCTION use_totp_recovery_code TO app_user;
+GRANT EXECUTE ON FUNCTION disable_totp TO app_user;
+GRANT EXECUTE ON FUNCTION create_personal_access_token TO app_user;
+GRANT EXECUTE ON FUNCTION list_personal_access_tokens TO app_user;
+GRANT EXECUTE ON FUNCTION revoke_personal_access_token TO app_user;
+GRANT EXECUTE ON FUNCTION verify_personal_access_token TO app_user;
+GRANT EXECUTE ON ALL FUNCTIONS IN SCHEMA public TO app_user;
+
+GRANT SELECT, INSERT, UPDATE ON users TO app_user;
+
+-- Permisos por defecto en las tablas
+ALTER DEFAULT PRIVILEGES IN SCHEMA public
+GRANT SELECT, INSERT, UPDATE ON TABLES TO app_user;
\ No newline at end of file
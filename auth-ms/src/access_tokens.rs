@@ -0,0 +1,218 @@
+// access_tokens.rs
+use axum::{
+    extract::{Json, Path, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgRow, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{authenticate, require_internal_token, tokens, AppError, AppState};
+
+/// Scopes que puede tener un token de acceso personal; main-ms exige el que corresponde a cada ruta
+pub const SCOPES: &[&str] = &[
+    "tasks:read",
+    "tasks:write",
+    "projects:read",
+    "projects:write",
+    "tags:read",
+    "tags:write",
+];
+
+/// Prefijo que permite a main-ms distinguir estos tokens de un JWT
+const TOKEN_PREFIX: &str = "pat_";
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateAccessTokenRequest {
+    #[validate(length(min = 1, max = 100))]
+    name: String,
+    #[validate(length(min = 1))]
+    scopes: Vec<String>,
+    /// Sin valor el token no expira
+    #[validate(range(min = 1, max = 365))]
+    expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IntrospectRequest {
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccessTokenResponse {
+    id: Uuid,
+    name: String,
+    token_prefix: String,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+    last_used_at: Option<DateTime<Utc>>,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedAccessTokenResponse {
+    #[serde(flatten)]
+    access_token: AccessTokenResponse,
+    /// Solo se muestra al crearlo; en la base de datos se guarda su hash
+    token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IntrospectResponse {
+    id: Uuid,
+    user_id: Uuid,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    message: String,
+}
+
+/// Crea un token de acceso personal. Requiere una sesión (JWT): un token personal no puede crear otros.
+pub async fn create(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateAccessTokenRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let mut scopes = payload.scopes;
+    if let Some(unknown) = scopes.iter().find(|scope| !SCOPES.contains(&scope.as_str())) {
+        return Err(AppError::Validation(format!(
+            "Unknown scope '{}'. Valid scopes: {}",
+            unknown,
+            SCOPES.join(", ")
+        )));
+    }
+    scopes.sort();
+    scopes.dedup();
+
+    let token = format!("{}{}", TOKEN_PREFIX, tokens::generate_token());
+    let expires_at = payload
+        .expires_in_days
+        .map(|days| Utc::now() + chrono::Duration::days(days));
+
+    let row = sqlx::query(
+        r#"
+        SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+        FROM create_personal_access_token($1, $2, $3, $4, $5)
+        "#,
+    )
+    .bind(claims.id)
+    .bind(payload.name.trim())
+    .bind(&token)
+    .bind(&scopes)
+    .bind(expires_at)
+    .fetch_one(&state.pool)
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedAccessTokenResponse {
+            access_token: access_token_from_row(&row),
+            token,
+        }),
+    ))
+}
+
+/// Lista los tokens activos del usuario (sin el valor del token)
+pub async fn list(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let rows = sqlx::query(
+        r#"
+        SELECT id, name, token_prefix, scopes, expires_at, last_used_at, created_at
+        FROM list_personal_access_tokens($1)
+        "#,
+    )
+    .bind(claims.id)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let access_tokens: Vec<AccessTokenResponse> = rows.iter().map(access_token_from_row).collect();
+
+    Ok((StatusCode::OK, Json(access_tokens)))
+}
+
+/// Revoca un token; main-ms deja de aceptarlo en la siguiente sincronización de revocaciones
+pub async fn revoke(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(token_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let revoked: bool = sqlx::query_scalar("SELECT revoke_personal_access_token($1, $2)")
+        .bind(claims.id)
+        .bind(token_id)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if !revoked {
+        return Err(AppError::NotFound("Access token not found".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(MessageResponse {
+            message: "Access token revoked".to_string(),
+        }),
+    ))
+}
+
+/// Valida un token para main-ms y registra su último uso. Si `INTERNAL_API_TOKEN` está
+/// definido se exige en el encabezado `X-Internal-Token`.
+pub async fn introspect(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<IntrospectRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    require_internal_token(&headers, &state)?;
+
+    if !payload.token.starts_with(TOKEN_PREFIX) {
+        return Err(AppError::Auth("Invalid or expired access token".to_string()));
+    }
+
+    let row = sqlx::query(
+        r#"
+        SELECT id, user_id, scopes, expires_at
+        FROM verify_personal_access_token($1)
+        "#,
+    )
+    .bind(&payload.token)
+    .fetch_optional(&state.pool)
+    .await?
+    .ok_or_else(|| AppError::Auth("Invalid or expired access token".to_string()))?;
+
+    Ok((
+        StatusCode::OK,
+        Json(IntrospectResponse {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            scopes: row.get("scopes"),
+            expires_at: row.get("expires_at"),
+        }),
+    ))
+}
+
+fn access_token_from_row(row: &PgRow) -> AccessTokenResponse {
+    AccessTokenResponse {
+        id: row.get("id"),
+        name: row.get("name"),
+        token_prefix: row.get("token_prefix"),
+        scopes: row.get("scopes"),
+        expires_at: row.get("expires_at"),
+        last_used_at: row.get("last_used_at"),
+        created_at: row.get("created_at"),
+    }
+}
@@ -0,0 +1,43 @@
+// db.rs
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    Connection, PgConnection,
+};
+use std::collections::HashSet;
+
+use crate::config::DatabaseConfig;
+
+/// Migraciones de `migrations/`, embebidas en el binario al compilar
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Aplica las migraciones pendientes con `database.migration_url`. sqlx toma un lock
+/// consultivo, así que varias réplicas arrancando a la vez no las aplican dos veces, y
+/// rechaza arrancar si una migración ya aplicada cambió (checksum distinto).
+pub async fn migrate(config: &DatabaseConfig) -> Result<(), MigrateError> {
+    let mut conn = PgConnection::connect(config.migration_url()).await?;
+
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    MIGRATOR.run(&mut conn).await?;
+
+    let mut pending = MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .peekable();
+
+    if pending.peek().is_none() {
+        println!("Schema is up to date, no pending migrations");
+    }
+    for migration in pending {
+        println!("Applied migration {} ({})", migration.version, migration.description);
+    }
+
+    conn.close().await?;
+    Ok(())
+}
@@ -0,0 +1,85 @@
+// mailer.rs
+use async_trait::async_trait;
+use chrono::Utc;
+use std::{fmt::Debug, path::PathBuf, sync::Arc};
+use uuid::Uuid;
+
+use crate::config::{MailConfig, MailDriver};
+
+/// Correo saliente en texto plano
+#[derive(Debug)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Mecanismo de envío de correos. Para agregar un proveedor real (SMTP, API HTTP)
+/// basta con implementar este trait y registrarlo en `from_config`.
+#[async_trait]
+pub trait Mailer: Debug + Send + Sync {
+    async fn send(&self, email: &Email) -> Result<(), MailError>;
+}
+
+/// Selecciona el mailer según `mail.driver` (`log` por defecto, o `file`)
+pub fn from_config(config: &MailConfig) -> Arc<dyn Mailer> {
+    let from = config.from.clone();
+
+    match config.driver {
+        MailDriver::File => Arc::new(FileMailer {
+            from,
+            dir: PathBuf::from(&config.outbox_dir),
+        }),
+        MailDriver::Log => Arc::new(LogMailer { from }),
+    }
+}
+
+/// Imprime los correos en la salida estándar (desarrollo local)
+#[derive(Debug)]
+pub struct LogMailer {
+    from: String,
+}
+
+#[async_trait]
+impl Mailer for LogMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        println!("📧 {}", render(&self.from, email).replace('\n', "\n   "));
+        Ok(())
+    }
+}
+
+/// Guarda cada correo como un archivo `.eml` en un directorio (desarrollo y pruebas)
+#[derive(Debug)]
+pub struct FileMailer {
+    from: String,
+    dir: PathBuf,
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: &Email) -> Result<(), MailError> {
+        tokio::fs::create_dir_all(&self.dir).await?;
+
+        let file_name = format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S"), Uuid::new_v4());
+        tokio::fs::write(self.dir.join(file_name), render(&self.from, email)).await?;
+
+        Ok(())
+    }
+}
+
+fn render(from: &str, email: &Email) -> String {
+    format!(
+        "From: {}\nTo: {}\nSubject: {}\nDate: {}\n\n{}\n",
+        from,
+        email.to,
+        email.subject,
+        Utc::now().to_rfc2822(),
+        email.body
+    )
+}
@@ -1,24 +1,48 @@
 use axum::{
-    extract::{Json, State},
-    http::{HeaderMap, StatusCode},
+    extract::{ConnectInfo, Json, State},
+    http::{header, HeaderMap, StatusCode},
     response::IntoResponse,
-    routing::{get, post},
+    routing::{delete, get, post},
     Router,
 };
 use chrono::{DateTime, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use sqlx::{postgres::PgPoolOptions, Pool, Postgres, Row};
-use tower_http::cors::CorsLayer;
-use std::{sync::Arc};
+use std::{net::SocketAddr, sync::Arc};
 use uuid::Uuid;
 use validator::Validate;
 use log::{error};
 
+mod access_tokens;
+mod config;
+mod db;
+mod mailer;
+mod password;
+mod sessions;
+mod throttle;
+mod tokens;
+mod two_factor;
+mod verification;
+
 #[derive(Debug, Clone)]
 struct AppState {
     pool: Pool<Postgres>,
     jwt_secret: String,
+    jwt_issuer: String,
+    jwt_audience: String,
+    access_token_ttl: chrono::Duration,
+    refresh_token_ttl: chrono::Duration,
+    internal_api_token: Option<String>,
+    mailer: Arc<dyn mailer::Mailer>,
+    password_reset_ttl: chrono::Duration,
+    password_reset_url: String,
+    email_verification_policy: verification::EmailVerificationPolicy,
+    email_verification_ttl: chrono::Duration,
+    email_verification_url: String,
+    login_throttle: throttle::LoginThrottle,
+    totp_issuer: String,
+    two_factor_challenge_ttl: chrono::Duration,
 }
 
 #[derive(Debug, Deserialize, Validate)]
@@ -41,12 +65,15 @@ struct UserResponse {
     id: Uuid,
     email: String,
     created_at: DateTime<Utc>,
+    email_verified: bool,
 }
 
 #[derive(Debug, Serialize)]
 struct AuthResponse {
     user: UserResponse,
-    token: String,
+    /// Ausentes al registrarse con la política `required`: hay que verificar el email antes de iniciar sesión
+    #[serde(flatten)]
+    tokens: Option<tokens::TokenPair>,
 }
 
 #[derive(Debug, Serialize)]
@@ -61,6 +88,22 @@ struct JwtPayload {
     email: String,
 }
 
+/// Claims completos de un access token emitido por este servicio.
+/// `jti` identifica el token y `sid` la sesión (familia de refresh tokens) que lo emitió.
+#[derive(Debug, Serialize, Deserialize)]
+struct JwtClaims {
+    id: Uuid,
+    email: String,
+    iss: String,
+    aud: String,
+    iat: usize,
+    exp: usize,
+    #[serde(default)]
+    jti: Option<Uuid>,
+    #[serde(default)]
+    sid: Option<Uuid>,
+}
+
 #[derive(Debug, thiserror::Error)]
 enum AppError {
     #[error("Database error: {0}")]
@@ -69,8 +112,17 @@ enum AppError {
     Validation(String),
     #[error("Authentication error: {0}")]
     Auth(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
     #[error("JWT error: {0}")]
     Jwt(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+    /// Login bloqueado temporalmente; contiene los segundos para `Retry-After`
+    #[error("Too many login attempts, retry after {0} seconds")]
+    TooManyRequests(u64),
 }
 
 impl IntoResponse for AppError {
@@ -79,7 +131,22 @@ impl IntoResponse for AppError {
             AppError::Database(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
             AppError::Validation(e) => (StatusCode::BAD_REQUEST, e),
             AppError::Auth(e) => (StatusCode::UNAUTHORIZED, e),
+            AppError::Forbidden(e) => (StatusCode::FORBIDDEN, e),
+            AppError::NotFound(e) => (StatusCode::NOT_FOUND, e),
             AppError::Jwt(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
+            AppError::Internal(e) => (StatusCode::INTERNAL_SERVER_ERROR, e),
+            AppError::TooManyRequests(retry_after) => {
+                let body = Json(ErrorResponse {
+                    error: "Too many login attempts, try again later".to_string(),
+                });
+
+                return (
+                    StatusCode::TOO_MANY_REQUESTS,
+                    [(header::RETRY_AFTER, retry_after.to_string())],
+                    body,
+                )
+                    .into_response();
+            }
         };
 
         let body = Json(ErrorResponse {
@@ -92,15 +159,34 @@ impl IntoResponse for AppError {
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     dotenvy::dotenv().ok();
-    
-    let database_url = std::env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
-    let jwt_secret = std::env::var("JWT_SECRET")
-        .expect("JWT_SECRET must be set");
+
+    let args = config::Args::parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    let config = config::Config::load(args.config_file.as_deref()).unwrap_or_else(|e| {
+        eprint!("{}", e);
+        std::process::exit(1);
+    });
+
+    if args.print_config {
+        print!("{}", config.to_redacted_toml());
+        return Ok(());
+    }
+
+    if args.command == config::Command::Migrate {
+        db::migrate(&config.database).await?;
+        return Ok(());
+    }
+
+    if config.database.migrate_on_startup {
+        db::migrate(&config.database).await?;
+    }
 
     let pool = PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+        .max_connections(config.database.max_connections)
+        .connect(&config.database.url)
         .await?;
 
     sqlx::query("SELECT 1")
@@ -109,21 +195,52 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let state = Arc::new(AppState {
         pool,
-        jwt_secret: jwt_secret.clone(),
+        jwt_secret: config.jwt.secret.clone(),
+        jwt_issuer: config.jwt.issuer.clone(),
+        jwt_audience: config.jwt.audience.clone(),
+        access_token_ttl: chrono::Duration::minutes(config.jwt.access_ttl_minutes),
+        refresh_token_ttl: chrono::Duration::days(config.jwt.refresh_ttl_days),
+        internal_api_token: Some(config.internal_api.token.clone()).filter(|token| !token.is_empty()),
+        mailer: mailer::from_config(&config.mail),
+        password_reset_ttl: chrono::Duration::minutes(config.password_reset.ttl_minutes),
+        password_reset_url: config.password_reset.url.clone(),
+        email_verification_policy: config.email_verification.policy,
+        email_verification_ttl: chrono::Duration::hours(config.email_verification.ttl_hours),
+        email_verification_url: config.email_verification.url.clone(),
+        login_throttle: throttle::LoginThrottle::from_config(&config.login),
+        totp_issuer: config.two_factor.totp_issuer.clone(),
+        two_factor_challenge_ttl: chrono::Duration::seconds(config.two_factor.challenge_ttl_seconds),
     });
 
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/api/register", post(register))
         .route("/api/login", post(login))
+        .route("/api/login/2fa", post(two_factor::login))
+        .route("/api/2fa/setup", post(two_factor::setup))
+        .route("/api/2fa/enable", post(two_factor::enable))
+        .route("/api/2fa/disable", post(two_factor::disable))
+        .route("/api/token/refresh", post(tokens::refresh))
+        .route("/api/logout", post(sessions::logout))
+        .route("/api/logout-all", post(sessions::logout_all))
+        .route("/api/revocations", get(sessions::revocations))
+        .route("/api/tokens", post(access_tokens::create).get(access_tokens::list))
+        .route("/api/tokens/{token_id}", delete(access_tokens::revoke))
+        .route("/api/tokens/introspect", post(access_tokens::introspect))
+        .route("/api/password/change", post(password::change_password))
+        .route("/api/password/forgot", post(password::forgot_password))
+        .route("/api/password/reset", post(password::reset_password))
+        .route("/api/email/verify", post(verification::verify_email))
+        .route("/api/email/resend", post(verification::resend_verification))
         .route("/api/me", get(get_current_user))
         .with_state(state)
-        .layer(CorsLayer::permissive());
+        .layer(config.cors.layer());
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await?;
-    println!("Server running on http://localhost:3000");
+    let addr = config.bind_addr();
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    println!("Server running on http://{}", addr);
     
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await?;
     
     Ok(())
 }
@@ -166,34 +283,53 @@ async fn register(
         id: user_id,
         email: user_email.clone(), // Clone here for the response
         created_at,
+        email_verified: false,
     };
 
-    // Use cloned values for JWT payload
-    let jwt_payload = JwtPayload {
-        id: user_id,
-        email: user_email,        // This moves user_email
-    };
+    // La cuenta ya está creada: un fallo al emitir o enviar la verificación no se expone al
+    // cliente (reintentar el registro chocaría con el email); puede pedir un reenvío
+    if let Err(e) = verification::send_verification_email(&state, &user_email).await {
+        error!("Error al enviar el correo de verificación: {}", e);
+    }
 
-    let token = create_jwt(&jwt_payload, &state.jwt_secret)?;
+    // Con la política `required` no se abre sesión hasta verificar el email
+    let tokens = match state.email_verification_policy {
+        verification::EmailVerificationPolicy::Required => None,
+        verification::EmailVerificationPolicy::Flag => {
+            // Use cloned values for JWT payload
+            let jwt_payload = JwtPayload {
+                id: user_id,
+                email: user_email,        // This moves user_email
+            };
+
+            Some(tokens::issue_token_pair(&state, &jwt_payload).await?)
+        }
+    };
 
     Ok((
         StatusCode::CREATED,
         Json(AuthResponse {
             user: user_response,
-            token,
+            tokens,
         }),
     ))
 }
 
 async fn login(
     State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
 ) -> Result<impl IntoResponse, AppError> {
     payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
 
+    // Rechazar antes de verificar la contraseña si el email o la dirección están bloqueados
+    let ip_address = state.login_throttle.client_ip(&headers, addr);
+    throttle::check(&state, &payload.email, &ip_address).await?;
+
     let row = sqlx::query(
         r#"
-        SELECT id, email, created_at
+        SELECT id, email, created_at, email_verified_at, totp_enabled
         FROM verify_login_credentials($1, $2)
         "#,
     )
@@ -203,16 +339,33 @@ async fn login(
     .await
     .map_err(AppError::Database)?;
 
+    let user_id: Option<Uuid> = row.as_ref().map(|row| row.get("id"));
+    let totp_enabled = row.as_ref().is_some_and(|row| row.get::<bool, _>("totp_enabled"));
+
+    // Con 2FA activo la contraseña correcta todavía no es un login correcto: el contador del
+    // email solo se reinicia cuando /api/login/2fa acepta el código
+    if !totp_enabled {
+        throttle::record(&state, &payload.email, &ip_address, user_id).await?;
+    }
+
     let row = row.ok_or_else(|| AppError::Auth("Invalid credentials".to_string()))?;
 
     let id: Uuid = row.get("id");
     let email: String = row.get("email");
     let created_at: DateTime<Utc> = row.get("created_at");
+    let email_verified_at: Option<DateTime<Utc>> = row.get("email_verified_at");
+
+    if email_verified_at.is_none()
+        && state.email_verification_policy == verification::EmailVerificationPolicy::Required
+    {
+        return Err(AppError::Forbidden("Email not verified".to_string()));
+    }
 
     let user_response = UserResponse {
         id,
         email: email.clone(),
         created_at,
+        email_verified: email_verified_at.is_some(),
     };
 
     // Usar valores por defecto temporalmente
@@ -221,15 +374,22 @@ async fn login(
         email,
     };
 
-    let token = create_jwt(&jwt_payload, &state.jwt_secret)?;
+    // Con 2FA activo solo se entrega un desafío; los tokens se emiten en /api/login/2fa
+    if totp_enabled {
+        let challenge = two_factor::challenge(&state, &jwt_payload)?;
+        return Ok((StatusCode::OK, Json(challenge)).into_response());
+    }
+
+    let tokens = Some(tokens::issue_token_pair(&state, &jwt_payload).await?);
 
     Ok((
         StatusCode::OK,
         Json(AuthResponse {
             user: user_response,
-            token,
+            tokens,
         }),
-    ))
+    )
+        .into_response())
 }
 
 async fn get_current_user(
@@ -239,28 +399,15 @@ async fn get_current_user(
     // Log de inicio de la función
     println!("Iniciando la obtención del usuario actual");
 
-    // Extraer el token del encabezado
-    let token = headers
-        .get("Authorization")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|s| s.strip_prefix("Bearer "))
-        .ok_or_else(|| {
-            error!("Token ausente o inválido en los encabezados");
-            AppError::Auth("Missing or invalid token".to_string())
-        })?;
-
-    // Log de token extraído
-    println!("Token extraído exitosamente del encabezado");
-
-    // Extraer el user_id del token
-    let user_id = match extract_user_id(token, &state.jwt_secret) {
-        Ok(id) => {
+    // Validar el token del encabezado (firma, expiración y revocación)
+    let user_id = match authenticate(&headers, &state).await {
+        Ok(claims) => {
             println!("ID de usuario extraído correctamente del token");
-            id
+            claims.id
         }
         Err(e) => {
-            error!("Error al extraer el ID de usuario del token: {}", e);
-            return Err(AppError::Auth(format!("Invalid token: {}", e)));
+            error!("Error al autenticar el token: {}", e);
+            return Err(e);
         }
     };
 
@@ -283,7 +430,7 @@ async fn get_current_user(
         Ok(r) => r,
         Err(e) => {
             println!("Error al ejecutar la consulta en la base de datos: {}", e);
-            return Err(AppError::Database(e.into()));
+            return Err(AppError::Database(e));
         }
     };
 
@@ -294,6 +441,7 @@ async fn get_current_user(
     let id: Uuid = row.get("user_id");
     let email: String = row.get("user_email");
     let created_at: DateTime<Utc> = row.get("user_created_at");
+    let email_verified_at: Option<DateTime<Utc>> = row.get("user_email_verified_at");
 
     // Log de la respuesta del usuario
     println!("Usuario encontrado: ID = {}, Email = {}", id, email);
@@ -302,6 +450,7 @@ async fn get_current_user(
         id,
         email,
         created_at,
+        email_verified: email_verified_at.is_some(),
     };
 
     // Log de respuesta exitosa
@@ -311,44 +460,92 @@ async fn get_current_user(
 }
 
 
-/// Función que recibe un token JWT y retorna el Uuid del usuario
-fn extract_user_id(token: &str, secret: &str) -> Result<Uuid, AppError> {
-    let token_data = decode::<JwtPayload>(
+/// Extrae el token Bearer del encabezado Authorization
+fn bearer_token(headers: &HeaderMap) -> Result<&str, AppError> {
+    headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|s| s.strip_prefix("Bearer "))
+        .ok_or_else(|| AppError::Auth("Missing or invalid token".to_string()))
+}
+
+/// Protege los endpoints que solo consume main-ms: si `INTERNAL_API_TOKEN` está
+/// definido debe llegar en el encabezado `X-Internal-Token`
+fn require_internal_token(headers: &HeaderMap, state: &AppState) -> Result<(), AppError> {
+    if let Some(ref expected) = state.internal_api_token {
+        let provided = headers
+            .get("X-Internal-Token")
+            .and_then(|h| h.to_str().ok());
+
+        if provided != Some(expected.as_str()) {
+            return Err(AppError::Auth("Invalid internal token".to_string()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Valida el access token de la petición y verifica que no haya sido revocado
+async fn authenticate(headers: &HeaderMap, state: &AppState) -> Result<JwtClaims, AppError> {
+    let token = bearer_token(headers)?;
+
+    let claims = decode::<JwtClaims>(
         token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    ).map_err(|e| AppError::Jwt(format!("Error decodificando token: {}", e)))?;
+        &DecodingKey::from_secret(state.jwt_secret.as_bytes()),
+        &jwt_validation(state),
+    )
+    .map_err(|e| AppError::Auth(format!("Invalid token: {}", e)))?
+    .claims;
+
+    let revoked: bool = sqlx::query_scalar("SELECT is_token_revoked($1, $2)")
+        .bind(claims.jti)
+        .bind(claims.sid)
+        .fetch_one(&state.pool)
+        .await?;
 
-    Ok(token_data.claims.id)
+    if revoked {
+        return Err(AppError::Auth("Token has been revoked".to_string()));
+    }
+
+    Ok(claims)
+}
+
+/// Reglas de validación compartidas con main-ms: firma HS256, expiración, issuer y audience
+fn jwt_validation(state: &AppState) -> Validation {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_issuer(&[&state.jwt_issuer]);
+    validation.set_audience(&[&state.jwt_audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+    validation
 }
 
 /// Función para generar JWT
-fn create_jwt(payload: &JwtPayload, secret: &str) -> Result<String, AppError> {
+fn create_jwt(payload: &JwtPayload, session_id: Uuid, state: &AppState) -> Result<String, AppError> {
     use chrono::Utc;
     
-    // Agregar expiración (24 horas)
-    let exp = Utc::now()
-        .checked_add_signed(chrono::Duration::hours(24))
+    let now = Utc::now();
+
+    // Agregar expiración (access token de vida corta, se renueva con el refresh token)
+    let exp = now
+        .checked_add_signed(state.access_token_ttl)
         .expect("Invalid timestamp")
         .timestamp() as usize;
 
-    // Claims con expiración
-    #[derive(Debug, Serialize, Deserialize)]
-    struct Claims {
-        id: Uuid,
-        email: String,
-        exp: usize,
-    }
-
-    let claims = Claims {
+    // Claims con expiración, emisor y audiencia para que main-ms pueda validarlos localmente
+    let claims = JwtClaims {
         id: payload.id,
         email: payload.email.clone(),
+        iss: state.jwt_issuer.clone(),
+        aud: state.jwt_audience.clone(),
+        iat: now.timestamp() as usize,
         exp,
+        jti: Some(Uuid::new_v4()),
+        sid: Some(session_id),
     };
 
     encode(
-        &Header::default(),
+        &Header::new(Algorithm::HS256),
         &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
+        &EncodingKey::from_secret(state.jwt_secret.as_bytes()),
     ).map_err(|e| AppError::Jwt(e.to_string()))
 }
\ No newline at end of file
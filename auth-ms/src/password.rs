@@ -0,0 +1,153 @@
+// password.rs
+use axum::{
+    extract::{Json, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use chrono::Utc;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{authenticate, mailer::Email, tokens, AppError, AppState, JwtPayload};
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ChangePasswordRequest {
+    current_password: String,
+    #[validate(length(min = 6))]
+    new_password: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ForgotPasswordRequest {
+    #[validate(email)]
+    email: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResetPasswordRequest {
+    token: String,
+    #[validate(length(min = 6))]
+    new_password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangePasswordResponse {
+    message: String,
+    revoked_sessions: i32,
+    /// Nueva sesión para el cliente que hizo el cambio; las demás quedan cerradas
+    #[serde(flatten)]
+    tokens: tokens::TokenPair,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    message: String,
+}
+
+/// Cambia la contraseña del usuario autenticado verificando la actual y revoca todas sus sesiones
+pub async fn change_password(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let revoked_sessions: Option<i32> = sqlx::query_scalar("SELECT change_password($1, $2, $3)")
+        .bind(claims.id)
+        .bind(&payload.current_password)
+        .bind(&payload.new_password)
+        .fetch_one(&state.pool)
+        .await?;
+
+    let revoked_sessions = revoked_sessions
+        .ok_or_else(|| AppError::Validation("Current password is incorrect".to_string()))?;
+
+    let jwt_payload = JwtPayload {
+        id: claims.id,
+        email: claims.email,
+    };
+
+    let tokens = tokens::issue_token_pair(&state, &jwt_payload).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ChangePasswordResponse {
+            message: "Password changed".to_string(),
+            revoked_sessions,
+            tokens,
+        }),
+    ))
+}
+
+/// Envía un enlace de restablecimiento si el email está registrado. La respuesta es
+/// siempre la misma para no revelar qué emails existen.
+pub async fn forgot_password(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ForgotPasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let reset_token = tokens::generate_token();
+
+    let user_id: Option<Uuid> =
+        sqlx::query_scalar("SELECT create_password_reset_token($1, $2, $3)")
+            .bind(&payload.email)
+            .bind(&reset_token)
+            .bind(Utc::now() + state.password_reset_ttl)
+            .fetch_one(&state.pool)
+            .await?;
+
+    if user_id.is_some() {
+        let email = Email {
+            to: payload.email,
+            subject: "Restablecer contraseña".to_string(),
+            body: format!(
+                "Para restablecer tu contraseña abre el siguiente enlace:\n\n{}?token={}\n\nEl enlace es válido durante {} minutos y solo puede usarse una vez. Si no lo solicitaste, ignora este correo.",
+                state.password_reset_url,
+                reset_token,
+                state.password_reset_ttl.num_minutes()
+            ),
+        };
+
+        // Un fallo de envío no se expone al cliente; el usuario puede volver a solicitarlo
+        if let Err(e) = state.mailer.send(&email).await {
+            error!("Error al enviar el correo de restablecimiento: {}", e);
+        }
+    }
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse {
+            message: "If the email is registered, a password reset link has been sent".to_string(),
+        }),
+    ))
+}
+
+/// Fija una nueva contraseña con un token de restablecimiento (de un solo uso) y revoca todas las sesiones
+pub async fn reset_password(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ResetPasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    let user_id: Option<Uuid> = sqlx::query_scalar("SELECT reset_password($1, $2)")
+        .bind(&payload.token)
+        .bind(&payload.new_password)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if user_id.is_none() {
+        return Err(AppError::Validation("Invalid or expired reset token".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(MessageResponse {
+            message: "Password has been reset".to_string(),
+        }),
+    ))
+}
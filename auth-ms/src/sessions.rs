@@ -0,0 +1,130 @@
+// sessions.rs
+use axum::{
+    extract::{Json, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::types::PgInterval, Row};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{authenticate, require_internal_token, AppError, AppState};
+
+#[derive(Debug, Serialize)]
+pub struct LogoutResponse {
+    message: String,
+    revoked_sessions: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RevocationsQuery {
+    since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Revocation {
+    /// `token` (revocado por jti), `session` (revocado por sid) o `personal_access_token`
+    kind: String,
+    id: Uuid,
+    /// Momento a partir del cual la revocación ya no es necesaria
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevocationsResponse {
+    /// Usar como `since` en la siguiente consulta
+    server_time: DateTime<Utc>,
+    revocations: Vec<Revocation>,
+}
+
+/// Cierra la sesión actual: revoca el access token presentado y su familia de refresh tokens
+pub async fn logout(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let token_expires_at = Utc.timestamp_opt(claims.exp as i64, 0).single();
+
+    let revoked: bool = sqlx::query_scalar("SELECT revoke_session($1, $2, $3, $4)")
+        .bind(claims.id)
+        .bind(claims.sid)
+        .bind(claims.jti)
+        .bind(token_expires_at)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LogoutResponse {
+            message: "Logged out".to_string(),
+            revoked_sessions: i32::from(revoked),
+        }),
+    ))
+}
+
+/// Cierra todas las sesiones del usuario en todos los dispositivos
+pub async fn logout_all(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let revoked_sessions: i32 = sqlx::query_scalar("SELECT revoke_all_sessions($1)")
+        .bind(claims.id)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(LogoutResponse {
+            message: "Logged out from all sessions".to_string(),
+            revoked_sessions,
+        }),
+    ))
+}
+
+/// Lista incremental de revocaciones vigentes, consumida por main-ms para invalidar
+/// tokens que valida localmente o tiene en caché. Si `INTERNAL_API_TOKEN` está definido
+/// se exige en el encabezado `X-Internal-Token`.
+pub async fn revocations(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(query): Query<RevocationsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_internal_token(&headers, &state)?;
+
+    let server_time = Utc::now();
+    let token_ttl = PgInterval {
+        months: 0,
+        days: 0,
+        microseconds: state.access_token_ttl.num_microseconds().unwrap_or(i64::MAX),
+    };
+
+    let rows = sqlx::query(
+        "SELECT kind, id, expires_at FROM get_revocations(COALESCE($1, '-infinity'::TIMESTAMPTZ), $2)",
+    )
+    .bind(query.since)
+    .bind(token_ttl)
+    .fetch_all(&state.pool)
+    .await?;
+
+    let revocations = rows
+        .into_iter()
+        .map(|row| Revocation {
+            kind: row.get("kind"),
+            id: row.get("id"),
+            expires_at: row.get("expires_at"),
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(RevocationsResponse {
+            server_time,
+            revocations,
+        }),
+    ))
+}
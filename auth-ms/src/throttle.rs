@@ -0,0 +1,203 @@
+// throttle.rs
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use ipnet::IpNet;
+use std::net::{IpAddr, SocketAddr};
+use uuid::Uuid;
+
+use crate::config::LoginConfig;
+use crate::{AppError, AppState};
+
+/// Límites de intentos de login fallidos, por email y por dirección del cliente
+#[derive(Debug, Clone)]
+pub struct LoginThrottle {
+    max_attempts_per_email: i32,
+    max_attempts_per_ip: i32,
+    /// Primer bloqueo; se duplica con cada fallo adicional
+    base_lockout: chrono::Duration,
+    max_lockout: chrono::Duration,
+    /// Los fallos más antiguos que esto dejan de contar
+    window: chrono::Duration,
+    /// Proxies (main-ms u otro) cuyo `X-Forwarded-For` se acepta; cualquier otro cliente
+    /// podría falsificarlo para esquivar el bloqueo por dirección
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl LoginThrottle {
+    pub fn from_config(config: &LoginConfig) -> Self {
+        Self {
+            max_attempts_per_email: config.max_attempts_per_email,
+            max_attempts_per_ip: config.max_attempts_per_ip,
+            base_lockout: chrono::Duration::seconds(config.lockout_base_seconds),
+            max_lockout: chrono::Duration::seconds(config.lockout_max_seconds),
+            window: chrono::Duration::minutes(config.attempt_window_minutes),
+            trusted_proxies: config.trusted_networks(),
+        }
+    }
+
+    /// Dirección del cliente. Si la conexión viene de un proxy de confianza, se recorre
+    /// `X-Forwarded-For` de derecha a izquierda saltando los demás proxies de confianza; la
+    /// primera dirección que no lo es fue la que se conectó al proxy. Lo que está más a la
+    /// izquierda lo escribió el cliente y no se usa.
+    pub fn client_ip(&self, headers: &HeaderMap, addr: SocketAddr) -> String {
+        let mut client = addr.ip().to_canonical();
+        if !self.is_trusted(client) {
+            return client.to_string();
+        }
+
+        let forwarded: Vec<&str> = headers
+            .get_all("X-Forwarded-For")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .collect();
+
+        for entry in forwarded.into_iter().rev() {
+            // Una entrada ilegible no puede atribuirse a nadie: queda el último salto conocido
+            let Ok(ip) = entry.trim().parse::<IpAddr>() else {
+                break;
+            };
+            client = ip.to_canonical();
+            if !self.is_trusted(client) {
+                break;
+            }
+        }
+
+        client.to_string()
+    }
+
+    fn is_trusted(&self, ip: IpAddr) -> bool {
+        self.trusted_proxies.iter().any(|network| network.contains(&ip))
+    }
+}
+
+/// Rechaza el intento con 429 si el email o la dirección están bloqueados
+pub async fn check(state: &AppState, email: &str, ip_address: &str) -> Result<(), AppError> {
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar("SELECT check_login_lockout($1, $2)")
+        .bind(email)
+        .bind(ip_address)
+        .fetch_one(&state.pool)
+        .await?;
+
+    match locked_until {
+        Some(locked_until) => Err(too_many_attempts(locked_until)),
+        None => Ok(()),
+    }
+}
+
+/// Registra el intento (auditoría y contadores). Si el fallo provoca un bloqueo se retorna 429.
+pub async fn record(
+    state: &AppState,
+    email: &str,
+    ip_address: &str,
+    user_id: Option<Uuid>,
+) -> Result<(), AppError> {
+    let throttle = &state.login_throttle;
+
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT record_login_attempt($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+    )
+    .bind(email)
+    .bind(ip_address)
+    .bind(user_id)
+    .bind(user_id.is_some())
+    .bind(throttle.max_attempts_per_email)
+    .bind(throttle.max_attempts_per_ip)
+    .bind(throttle.base_lockout)
+    .bind(throttle.max_lockout)
+    .bind(throttle.window)
+    .fetch_one(&state.pool)
+    .await?;
+
+    match locked_until {
+        Some(locked_until) => Err(too_many_attempts(locked_until)),
+        None => Ok(()),
+    }
+}
+
+fn too_many_attempts(locked_until: DateTime<Utc>) -> AppError {
+    // Redondear hacia arriba para que el cliente no reintente un instante antes de tiempo
+    let retry_after = (locked_until - Utc::now()).num_milliseconds().max(0) as u64;
+    AppError::TooManyRequests(retry_after.div_ceil(1000).max(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn throttle(trusted_proxies: &[&str]) -> LoginThrottle {
+        LoginThrottle::from_config(&LoginConfig {
+            trusted_proxies: trusted_proxies.iter().map(|proxy| proxy.to_string()).collect(),
+            ..LoginConfig::default()
+        })
+    }
+
+    fn forwarded(values: &[&str]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for value in values {
+            headers.append("X-Forwarded-For", HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn peer(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 40000)
+    }
+
+    #[test]
+    fn ignores_forwarded_for_without_trusted_proxies() {
+        let throttle = throttle(&[]);
+        let headers = forwarded(&["203.0.113.7"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("198.51.100.1")), "198.51.100.1");
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peer() {
+        let throttle = throttle(&["172.28.0.10"]);
+        let headers = forwarded(&["203.0.113.7"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("172.28.0.1")), "172.28.0.1");
+    }
+
+    #[test]
+    fn uses_last_entry_added_by_trusted_proxy() {
+        let throttle = throttle(&["172.28.0.10"]);
+        // El cliente envió "1.2.3.4"; main-ms agregó la dirección real al final
+        let headers = forwarded(&["1.2.3.4, 203.0.113.7"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("172.28.0.10")), "203.0.113.7");
+    }
+
+    #[test]
+    fn skips_chained_trusted_proxies() {
+        let throttle = throttle(&["10.0.0.0/8"]);
+        let headers = forwarded(&["1.2.3.4, 203.0.113.7", "10.0.0.5"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("10.0.0.2")), "203.0.113.7");
+    }
+
+    #[test]
+    fn keeps_last_known_hop_on_invalid_entry() {
+        let throttle = throttle(&["10.0.0.0/8"]);
+        let headers = forwarded(&["203.0.113.7, not-an-ip, 10.0.0.5"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("10.0.0.2")), "10.0.0.5");
+    }
+
+    #[test]
+    fn uses_peer_when_trusted_proxy_sends_no_header() {
+        let throttle = throttle(&["10.0.0.2"]);
+
+        assert_eq!(throttle.client_ip(&HeaderMap::new(), peer("10.0.0.2")), "10.0.0.2");
+    }
+
+    #[test]
+    fn matches_ipv4_mapped_peer() {
+        let throttle = throttle(&["172.28.0.10"]);
+        let headers = forwarded(&["203.0.113.7"]);
+
+        assert_eq!(throttle.client_ip(&headers, peer("::ffff:172.28.0.10")), "203.0.113.7");
+    }
+}
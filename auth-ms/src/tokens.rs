@@ -0,0 +1,101 @@
+// tokens.rs
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::{create_jwt, AppError, AppState, JwtPayload};
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// Par de tokens entregado al cliente: access token corto + refresh token rotativo
+#[derive(Debug, Serialize)]
+pub struct TokenPair {
+    pub token: String,
+    pub refresh_token: String,
+    /// Segundos de vida del access token
+    pub expires_in: i64,
+}
+
+/// Emite un access token y un refresh token que inicia una nueva familia (una por login)
+pub async fn issue_token_pair(state: &AppState, payload: &JwtPayload) -> Result<TokenPair, AppError> {
+    let refresh_token = generate_token();
+
+    let session_id = sqlx::query_scalar::<_, Uuid>("SELECT create_refresh_token($1, $2, $3)")
+        .bind(payload.id)
+        .bind(&refresh_token)
+        .bind(Utc::now() + state.refresh_token_ttl)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(TokenPair {
+        token: create_jwt(payload, session_id, state)?,
+        refresh_token,
+        expires_in: state.access_token_ttl.num_seconds(),
+    })
+}
+
+/// Intercambia un refresh token por un par nuevo. El token presentado queda usado;
+/// si se vuelve a presentar, se revoca toda su familia.
+pub async fn refresh(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let new_refresh_token = generate_token();
+
+    let row = sqlx::query(
+        r#"
+        SELECT status, user_id, user_email, session_id
+        FROM rotate_refresh_token($1, $2, $3)
+        "#,
+    )
+    .bind(&payload.refresh_token)
+    .bind(&new_refresh_token)
+    .bind(Utc::now() + state.refresh_token_ttl)
+    .fetch_one(&state.pool)
+    .await?;
+
+    let status: String = row.get("status");
+
+    match status.as_str() {
+        "ok" => {}
+        "reused" => {
+            return Err(AppError::Auth(
+                "Refresh token reuse detected; the session has been revoked".to_string(),
+            ))
+        }
+        _ => return Err(AppError::Auth("Invalid or expired refresh token".to_string())),
+    }
+
+    let jwt_payload = JwtPayload {
+        id: row.get("user_id"),
+        email: row.get("user_email"),
+    };
+
+    Ok((
+        StatusCode::OK,
+        Json(TokenPair {
+            token: create_jwt(&jwt_payload, row.get("session_id"), &state)?,
+            refresh_token: new_refresh_token,
+            expires_in: state.access_token_ttl.num_seconds(),
+        }),
+    ))
+}
+
+/// 32 bytes aleatorios codificados en base64url (refresh y reset tokens); en la base de datos solo se guarda su hash
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 32];
+    rand::rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
@@ -0,0 +1,465 @@
+// two_factor.rs
+use axum::{
+    extract::{ConnectInfo, Json, State},
+    http::{HeaderMap, StatusCode},
+    response::IntoResponse,
+};
+use chrono::{DateTime, Utc};
+use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sqlx::Row;
+use std::{net::SocketAddr, sync::Arc};
+use totp_rs::{Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    authenticate, throttle, tokens, AppError, AppState, AuthResponse, JwtPayload, UserResponse,
+};
+
+const RECOVERY_CODE_COUNT: usize = 10;
+/// Duración de cada paso TOTP en segundos (estándar de las apps autenticadoras)
+const TOTP_STEP_SECONDS: u64 = 30;
+
+#[derive(Debug, Deserialize)]
+pub struct EnableRequest {
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableRequest {
+    password: String,
+    /// Código TOTP o código de recuperación
+    code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    challenge_token: String,
+    /// Código TOTP o código de recuperación
+    code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SetupResponse {
+    secret: String,
+    otpauth_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    message: String,
+    /// Se muestran una sola vez; en la base de datos solo se guarda su hash
+    recovery_codes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    message: String,
+}
+
+/// Respuesta de `/api/login` cuando el usuario tiene 2FA activo
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallenge {
+    two_factor_required: bool,
+    challenge_token: String,
+    expires_in: i64,
+}
+
+/// Token de desafío entre los dos pasos del login. Usa una audiencia propia para
+/// que nunca sea aceptado como access token.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    id: Uuid,
+    email: String,
+    iss: String,
+    aud: String,
+    exp: usize,
+}
+
+/// Emite el token de desafío que `/api/login/2fa` canjea por el par de tokens
+pub fn challenge(state: &AppState, payload: &JwtPayload) -> Result<TwoFactorChallenge, AppError> {
+    let exp = (Utc::now() + state.two_factor_challenge_ttl).timestamp() as usize;
+
+    let claims = ChallengeClaims {
+        id: payload.id,
+        email: payload.email.clone(),
+        iss: state.jwt_issuer.clone(),
+        aud: challenge_audience(state),
+        exp,
+    };
+
+    let challenge_token = encode(
+        &Header::new(Algorithm::HS256),
+        &claims,
+        &EncodingKey::from_secret(state.jwt_secret.as_bytes()),
+    )
+    .map_err(|e| AppError::Jwt(e.to_string()))?;
+
+    Ok(TwoFactorChallenge {
+        two_factor_required: true,
+        challenge_token,
+        expires_in: state.two_factor_challenge_ttl.num_seconds(),
+    })
+}
+
+/// Inicia la activación: genera un secreto pendiente y la URI `otpauth://` para el QR
+pub async fn setup(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let Secret::Encoded(secret) = Secret::generate_secret().to_encoded() else {
+        unreachable!("to_encoded always returns an encoded secret");
+    };
+
+    let pending: bool = sqlx::query_scalar("SELECT begin_totp_setup($1, $2)")
+        .bind(claims.id)
+        .bind(&secret)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if !pending {
+        return Err(AppError::Validation("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let totp = build_totp(&state, &secret, &claims.email)?;
+
+    Ok((
+        StatusCode::OK,
+        Json(SetupResponse {
+            secret,
+            otpauth_url: totp.get_url(),
+        }),
+    ))
+}
+
+/// Confirma la activación con un código de la app y entrega los códigos de recuperación
+pub async fn enable(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<EnableRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+    let totp_state = totp_state(&state, claims.id).await?;
+
+    if totp_state.enabled {
+        return Err(AppError::Validation("Two-factor authentication is already enabled".to_string()));
+    }
+
+    let Some(ref secret) = totp_state.secret else {
+        return Err(AppError::Validation("Two-factor setup has not been started".to_string()));
+    };
+
+    if !verify_totp_code(&state, claims.id, secret, &totp_state.email, &payload.code).await? {
+        return Err(AppError::Validation("Invalid two-factor code".to_string()));
+    }
+
+    let recovery_codes = generate_recovery_codes();
+    let normalized: Vec<String> = recovery_codes.iter().map(|code| normalize_recovery_code(code)).collect();
+
+    let enabled: bool = sqlx::query_scalar("SELECT enable_totp($1, $2)")
+        .bind(claims.id)
+        .bind(&normalized)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if !enabled {
+        return Err(AppError::Validation("Two-factor setup has not been started".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(RecoveryCodesResponse {
+            message: "Two-factor authentication enabled".to_string(),
+            recovery_codes,
+        }),
+    ))
+}
+
+/// Desactiva 2FA. Exige re-autenticación: contraseña y un código TOTP o de recuperación.
+pub async fn disable(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<DisableRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let claims = authenticate(&headers, &state).await?;
+
+    let password_ok: bool = sqlx::query_scalar("SELECT verify_password($1, $2)")
+        .bind(claims.id)
+        .bind(&payload.password)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if !password_ok {
+        return Err(AppError::Validation("Current password is incorrect".to_string()));
+    }
+
+    let totp_state = totp_state(&state, claims.id).await?;
+
+    let (true, Some(secret)) = (totp_state.enabled, totp_state.secret.as_deref()) else {
+        return Err(AppError::Validation("Two-factor authentication is not enabled".to_string()));
+    };
+
+    if !verify_second_factor(&state, claims.id, secret, &totp_state.email, &payload.code).await? {
+        return Err(AppError::Validation("Invalid two-factor code".to_string()));
+    }
+
+    sqlx::query_scalar::<_, bool>("SELECT disable_totp($1)")
+        .bind(claims.id)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(MessageResponse {
+            message: "Two-factor authentication disabled".to_string(),
+        }),
+    ))
+}
+
+/// Segundo paso del login: canjea el token de desafío y un código por el par de tokens.
+/// Los códigos incorrectos cuentan como intentos fallidos de login.
+pub async fn login(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let mut validation = Validation::new(Algorithm::HS256);
+    validation.set_issuer(&[&state.jwt_issuer]);
+    validation.set_audience(&[challenge_audience(&state)]);
+
+    let claims = decode::<ChallengeClaims>(
+        &payload.challenge_token,
+        &DecodingKey::from_secret(state.jwt_secret.as_bytes()),
+        &validation,
+    )
+    .map_err(|_| AppError::Auth("Invalid or expired challenge token".to_string()))?
+    .claims;
+
+    let ip_address = state.login_throttle.client_ip(&headers, addr);
+    throttle::check(&state, &claims.email, &ip_address).await?;
+
+    let totp_state = totp_state(&state, claims.id).await?;
+
+    let (true, Some(secret)) = (totp_state.enabled, totp_state.secret.as_deref()) else {
+        return Err(AppError::Auth("Invalid or expired challenge token".to_string()));
+    };
+
+    let verified = verify_second_factor(&state, claims.id, secret, &claims.email, &payload.code).await?;
+    throttle::record(&state, &claims.email, &ip_address, verified.then_some(claims.id)).await?;
+
+    if !verified {
+        return Err(AppError::Auth("Invalid two-factor code".to_string()));
+    }
+
+    let row = sqlx::query("SELECT * FROM get_user_by_id($1)")
+        .bind(claims.id)
+        .fetch_one(&state.pool)
+        .await?;
+
+    let email_verified_at: Option<DateTime<Utc>> = row.get("user_email_verified_at");
+
+    let user_response = UserResponse {
+        id: row.get("user_id"),
+        email: row.get("user_email"),
+        created_at: row.get("user_created_at"),
+        email_verified: email_verified_at.is_some(),
+    };
+
+    let jwt_payload = JwtPayload {
+        id: claims.id,
+        email: claims.email,
+    };
+
+    let tokens = Some(tokens::issue_token_pair(&state, &jwt_payload).await?);
+
+    Ok((
+        StatusCode::OK,
+        Json(AuthResponse {
+            user: user_response,
+            tokens,
+        }),
+    ))
+}
+
+struct TotpState {
+    email: String,
+    secret: Option<String>,
+    enabled: bool,
+}
+
+async fn totp_state(state: &AppState, user_id: Uuid) -> Result<TotpState, AppError> {
+    let row = sqlx::query("SELECT email, secret, enabled FROM get_totp_state($1)")
+        .bind(user_id)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(TotpState {
+        email: row.get("email"),
+        secret: row.get("secret"),
+        enabled: row.get("enabled"),
+    })
+}
+
+fn challenge_audience(state: &AppState) -> String {
+    format!("{}:2fa", state.jwt_audience)
+}
+
+fn build_totp(state: &AppState, secret: &str, email: &str) -> Result<TOTP, AppError> {
+    let secret = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::Internal(format!("Invalid TOTP secret: {:?}", e)))?;
+
+    TOTP::new(
+        totp_rs::Algorithm::SHA1,
+        6,
+        1,
+        TOTP_STEP_SECONDS,
+        secret,
+        Some(state.totp_issuer.clone()),
+        email.to_string(),
+    )
+    .map_err(|e| AppError::Internal(format!("Invalid TOTP configuration: {}", e)))
+}
+
+/// Acepta un código TOTP (con ±1 paso de tolerancia) o, si no lo es, un código de recuperación
+async fn verify_second_factor(
+    state: &AppState,
+    user_id: Uuid,
+    secret: &str,
+    email: &str,
+    code: &str,
+) -> Result<bool, AppError> {
+    let code = code.trim();
+
+    if code.len() == 6 && code.chars().all(|c| c.is_ascii_digit()) {
+        return verify_totp_code(state, user_id, secret, email, code).await;
+    }
+
+    let used: bool = sqlx::query_scalar("SELECT use_totp_recovery_code($1, $2)")
+        .bind(user_id)
+        .bind(normalize_recovery_code(code))
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(used)
+}
+
+/// Verifica un código TOTP y marca su paso como usado para que no pueda repetirse
+async fn verify_totp_code(
+    state: &AppState,
+    user_id: Uuid,
+    secret: &str,
+    email: &str,
+    code: &str,
+) -> Result<bool, AppError> {
+    let totp = build_totp(state, secret, email)?;
+    let current_step = Utc::now().timestamp() as u64 / TOTP_STEP_SECONDS;
+
+    let Some(step) = matching_step(&totp, code, current_step) else {
+        return Ok(false);
+    };
+
+    let fresh: bool = sqlx::query_scalar("SELECT consume_totp_step($1, $2)")
+        .bind(user_id)
+        .bind(step as i64)
+        .fetch_one(&state.pool)
+        .await?;
+
+    Ok(fresh)
+}
+
+/// Paso (el actual o uno de sus vecinos) cuyo código coincide con el recibido
+fn matching_step(totp: &TOTP, code: &str, current_step: u64) -> Option<u64> {
+    [current_step.saturating_sub(1), current_step, current_step + 1]
+        .into_iter()
+        .find(|step| totp.generate(step * TOTP_STEP_SECONDS) == code.trim())
+}
+
+/// Códigos de 10 caracteres hexadecimales con formato `xxxxx-xxxxx`
+fn generate_recovery_codes() -> Vec<String> {
+    let mut rng = rand::rng();
+
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            rng.fill_bytes(&mut bytes);
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}", &hex[..5], &hex[5..])
+        })
+        .collect()
+}
+
+/// Ignora mayúsculas, guiones y espacios al comparar códigos de recuperación
+fn normalize_recovery_code(code: &str) -> String {
+    code.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn totp() -> TOTP {
+        TOTP::new(
+            totp_rs::Algorithm::SHA1,
+            6,
+            1,
+            TOTP_STEP_SECONDS,
+            b"12345678901234567890".to_vec(),
+            Some("todo-app".to_string()),
+            "user@example.com".to_string(),
+        )
+        .unwrap()
+    }
+
+    fn code_at(totp: &TOTP, step: u64) -> String {
+        totp.generate(step * TOTP_STEP_SECONDS)
+    }
+
+    #[test]
+    fn accepts_the_current_step_and_its_neighbours() {
+        let totp = totp();
+        let current = 58_000_000;
+
+        for step in [current - 1, current, current + 1] {
+            assert_eq!(matching_step(&totp, &code_at(&totp, step), current), Some(step));
+        }
+    }
+
+    #[test]
+    fn rejects_codes_outside_the_window() {
+        let totp = totp();
+        let current = 58_000_000;
+
+        assert_eq!(matching_step(&totp, &code_at(&totp, current - 2), current), None);
+        assert_eq!(matching_step(&totp, &code_at(&totp, current + 2), current), None);
+    }
+
+    #[test]
+    fn ignores_surrounding_whitespace() {
+        let totp = totp();
+        let code = format!(" {} ", code_at(&totp, 100));
+
+        assert_eq!(matching_step(&totp, &code, 100), Some(100));
+    }
+
+    #[test]
+    fn first_step_has_no_previous_neighbour() {
+        let totp = totp();
+
+        assert_eq!(matching_step(&totp, &code_at(&totp, 0), 0), Some(0));
+        assert_eq!(matching_step(&totp, &code_at(&totp, 1), 0), Some(1));
+    }
+
+    #[test]
+    fn recovery_codes_ignore_case_and_separators() {
+        assert_eq!(normalize_recovery_code("AB12C-3DE45"), "ab12c3de45");
+        assert_eq!(normalize_recovery_code(" ab12c 3de45 "), "ab12c3de45");
+    }
+}
@@ -0,0 +1,129 @@
+// verification.rs
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use chrono::Utc;
+use log::error;
+use serde::{Deserialize, Serialize};
+use std::{str::FromStr, sync::Arc};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{mailer::Email, tokens, AppError, AppState};
+
+/// Qué hacer con las cuentas cuyo email aún no fue verificado (`EMAIL_VERIFICATION_POLICY`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EmailVerificationPolicy {
+    /// Pueden iniciar sesión; la respuesta indica `email_verified: false`
+    #[default]
+    Flag,
+    /// No pueden iniciar sesión hasta confirmar el email
+    Required,
+}
+
+impl FromStr for EmailVerificationPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "flag" => Ok(EmailVerificationPolicy::Flag),
+            "required" => Ok(EmailVerificationPolicy::Required),
+            _ => Err("must be one of: flag, required".to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerifyEmailRequest {
+    token: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ResendVerificationRequest {
+    #[validate(email)]
+    email: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageResponse {
+    message: String,
+}
+
+/// Emite un token de verificación y lo envía por correo. No hace nada si el email
+/// no existe o ya fue verificado.
+pub async fn send_verification_email(state: &AppState, email: &str) -> Result<(), AppError> {
+    let verification_token = tokens::generate_token();
+
+    let user_id: Option<Uuid> =
+        sqlx::query_scalar("SELECT create_email_verification_token($1, $2, $3)")
+            .bind(email)
+            .bind(&verification_token)
+            .bind(Utc::now() + state.email_verification_ttl)
+            .fetch_one(&state.pool)
+            .await?;
+
+    if user_id.is_none() {
+        return Ok(());
+    }
+
+    let message = Email {
+        to: email.to_string(),
+        subject: "Confirma tu email".to_string(),
+        body: format!(
+            "Para confirmar tu email abre el siguiente enlace:\n\n{}?token={}\n\nEl enlace es válido durante {} horas.",
+            state.email_verification_url,
+            verification_token,
+            state.email_verification_ttl.num_hours()
+        ),
+    };
+
+    // Un fallo de envío no se expone al cliente; el usuario puede pedir un reenvío
+    if let Err(e) = state.mailer.send(&message).await {
+        error!("Error al enviar el correo de verificación: {}", e);
+    }
+
+    Ok(())
+}
+
+/// Confirma el email con el token recibido por correo (de un solo uso)
+pub async fn verify_email(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyEmailRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id: Option<Uuid> = sqlx::query_scalar("SELECT verify_email($1)")
+        .bind(&payload.token)
+        .fetch_one(&state.pool)
+        .await?;
+
+    if user_id.is_none() {
+        return Err(AppError::Validation("Invalid or expired verification token".to_string()));
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(MessageResponse {
+            message: "Email verified".to_string(),
+        }),
+    ))
+}
+
+/// Reenvía el correo de verificación. No requiere sesión (con la política `required`
+/// el usuario no puede iniciarla) y responde igual exista o no el email.
+pub async fn resend_verification(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<ResendVerificationRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    payload.validate().map_err(|e| AppError::Validation(e.to_string()))?;
+
+    send_verification_email(&state, &payload.email).await?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(MessageResponse {
+            message: "If the email is registered and not yet verified, a verification link has been sent".to_string(),
+        }),
+    ))
+}
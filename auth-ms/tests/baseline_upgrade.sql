@@ -0,0 +1,67 @@
+-- Verifica que 0001_baseline.sql actualiza una base creada con el antiguo init.sql
+-- (tests/fixtures/init.sql) sin perder usuarios y que se puede aplicar dos veces. El
+-- init.sql antiguo borra la tabla users, así que se ejecuta sobre una base vacía:
+--
+--   createdb auth_upgrade_test
+--   psql -v ON_ERROR_STOP=1 -d auth_upgrade_test -f tests/baseline_upgrade.sql
+--   dropdb auth_upgrade_test
+--
+-- Todo ocurre en una transacción que se revierte al final, como en el migrador de sqlx.
+\set QUIET on
+\set old_email 'antiguo@example.com'
+\set new_email 'nuevo@example.com'
+
+BEGIN;
+-- Las dos pasadas avisan de cada objeto que ya existe
+SET LOCAL client_min_messages = warning;
+
+-- Base del antiguo init.sql, con un usuario registrado antes de la actualización. Se
+-- descarta lo que imprimen los scripts (sus SELECT de configuración).
+\o /dev/null
+\ir fixtures/init.sql
+SELECT user_id AS old_user FROM register_user(:'old_email', 'password123') \gset
+
+\ir ../migrations/0001_baseline.sql
+-- Idempotente: aplicarla de nuevo no cambia nada
+\ir ../migrations/0001_baseline.sql
+\o
+
+SELECT user_id AS new_user FROM register_user(:'new_email', 'password123') \gset
+
+SELECT set_config('test.old_user', :'old_user', true),
+       set_config('test.new_user', :'new_user', true) \gset
+
+DO $$
+DECLARE
+    old_user UUID := current_setting('test.old_user')::UUID;
+    new_user UUID := current_setting('test.new_user')::UUID;
+BEGIN
+    IF (SELECT count(*) FROM users) <> 2 THEN
+        RAISE EXCEPTION 'The upgrade lost or duplicated users';
+    END IF;
+
+    -- Las cuentas anteriores no deben quedar bloqueadas por la verificación de email
+    IF (SELECT email_verified_at FROM users WHERE id = old_user) IS NULL THEN
+        RAISE EXCEPTION 'Existing users were not marked as verified';
+    END IF;
+
+    IF (SELECT email_verified_at FROM users WHERE id = new_user) IS NOT NULL THEN
+        RAISE EXCEPTION 'New users must start unverified';
+    END IF;
+
+    IF NOT EXISTS (SELECT 1 FROM get_totp_state(old_user) WHERE secret IS NULL AND NOT enabled) THEN
+        RAISE EXCEPTION 'get_totp_state does not work on upgraded users';
+    END IF;
+
+    IF NOT EXISTS (
+        SELECT 1 FROM verify_login_credentials('antiguo@example.com', 'password123')
+        WHERE id = old_user AND email_verified_at IS NOT NULL AND NOT totp_enabled
+    ) THEN
+        RAISE EXCEPTION 'Existing users cannot log in after the upgrade';
+    END IF;
+END;
+$$;
+
+ROLLBACK;
+
+\echo 'Baseline upgrade checks passed'
@@ -1,3 +1,4 @@
+-- Copia del antiguo init.sql (anterior a las migraciones), usada por tests/baseline_upgrade.sql
 -- Add migration script here
 -- ==========================
 -- HABILITAR EXTENSION UUID
@@ -36,8 +36,11 @@ const Header = () => {
     setShowUserPopup(!showUserPopup)
   }
 
-  const handleLogout = () => {
+  const handleLogout = async () => {
+    await authApi.logout()
     localStorage.removeItem("authToken")
+    localStorage.removeItem("refreshToken")
+    localStorage.removeItem("authTokenExpiresAt")
     setUserData(null)
     setShowUserPopup(false)
     window.location.reload()
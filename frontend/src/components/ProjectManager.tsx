@@ -48,7 +48,7 @@ const ProjectManager = () => {
   }
 
   const handleDelete = async (id: string) => {
-    if (confirm("Are you sure you want to delete this project? This action cannot be undone.")) {
+    if (confirm("Are you sure you want to delete this project? Its tasks will be kept without a project. This action cannot be undone.")) {
       await deleteProject(id)
     }
   }
@@ -193,7 +193,7 @@ const TaskCalendar = () => {
                         <div
                           key={task.id}
                           className={`text-xs p-1 rounded truncate ${
-                            task.priority === "high"
+                            (task.priority === "urgent" || task.priority === "high")
                               ? "bg-destructive/20 text-destructive border border-destructive/30"
                               : task.priority === "med"
                                 ? "bg-secondary/20 text-secondary border border-secondary/30"
@@ -260,7 +260,7 @@ const TaskCalendar = () => {
                           <div
                             key={task.id}
                             className={`text-xs p-1 rounded truncate ${
-                              task.priority === "high"
+                              (task.priority === "urgent" || task.priority === "high")
                                 ? "bg-destructive/20 text-destructive border border-destructive/30"
                                 : task.priority === "med"
                                   ? "bg-secondary/20 text-secondary border border-secondary/30"
@@ -317,7 +317,7 @@ const TaskCalendar = () => {
                           <div className="flex flex-wrap items-center gap-2">
                             <span
                               className={`inline-flex items-center gap-1 px-2 py-1 rounded-full text-xs font-medium ${
-                                task.priority === "high"
+                                (task.priority === "urgent" || task.priority === "high")
                                   ? "text-[#c33c54] bg-[#c33c54]/10"
                                   : task.priority === "med"
                                     ? "text-[#37718e] bg-[#37718e]/10"
@@ -21,6 +21,8 @@ const TaskCard: React.FC<TaskCardProps> = ({ task }) => {
 
   const getPriorityColor = (priority: string) => {
     switch (priority) {
+      case "urgent":
+        return "text-white bg-[#c33c54]"
       case "high":
         return "text-[#c33c54] bg-[#c33c54]/10"
       case "med":
@@ -94,6 +94,7 @@ const TaskFilters = () => {
             className="w-full px-3 py-2 bg-[#071013]/50 border border-[#37718e]/30 rounded-lg text-white focus:outline-none focus:ring-2 focus:ring-[#0dab76] focus:border-transparent"
           >
             <option value="">All priorities</option>
+            <option value="urgent">Urgent</option>
             <option value="high">High</option>
             <option value="med">Medium</option>
             <option value="low">Low</option>
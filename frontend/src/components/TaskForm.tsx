@@ -21,7 +21,7 @@ const TaskForm: React.FC<TaskFormProps> = ({ onClose, task }) => {
   const [formData, setFormData] = useState({
     title: task?.title || "",
     description: task?.description || "",
-    priority: (task?.priority || "med") as "low" | "med" | "high",
+    priority: (task?.priority || "med") as Task["priority"],
     due_date: task?.due_date ? new Date(task.due_date).toISOString().split("T")[0] : "",
     project_id: task?.project_id || "",
     tags: task?.tags ? task.tags.map((tag: any) => tag.display_name || tag.name || tag) : ([] as string[]),
@@ -205,6 +205,7 @@ const TaskForm: React.FC<TaskFormProps> = ({ onClose, task }) => {
               <option value="low">Low</option>
               <option value="med">Medium</option>
               <option value="high">High</option>
+              <option value="urgent">Urgent</option>
             </select>
           </div>
 
@@ -1,7 +1,7 @@
 import type React from "react"
 import { useState } from "react"
 import { useAuth } from "../../contexts/AuthContext"
-import { LogIn, Mail, Lock, Shield, Sparkles, Users, Zap } from "lucide-react"
+import { LogIn, Mail, Lock, Shield, Sparkles, Users, Zap, KeyRound } from "lucide-react"
 import { useNavigate, Link } from "react-router-dom"
 
 interface LoginFormProps {
@@ -11,9 +11,10 @@ interface LoginFormProps {
 export const LoginForm: React.FC<LoginFormProps> = ({ onSuccess }) => {
   const [email, setEmail] = useState("")
   const [password, setPassword] = useState("")
+  const [code, setCode] = useState("")
   const [isLoading, setIsLoading] = useState(false)
   const [error, setError] = useState("")
-  const { login } = useAuth()
+  const { login, twoFactorPending, verifyTwoFactor } = useAuth()
   const navigate = useNavigate()
 
   const handleSubmit = async (e: React.FormEvent) => {
@@ -21,6 +22,19 @@ export const LoginForm: React.FC<LoginFormProps> = ({ onSuccess }) => {
     setIsLoading(true)
     setError("")
 
+    // Segundo paso: el usuario tiene 2FA activo y ya validó su contraseña
+    if (twoFactorPending) {
+      const success = await verifyTwoFactor(code)
+      if (success) {
+        navigate("/dashboard")
+        onSuccess?.()
+      } else {
+        setError("Invalid verification code")
+      }
+      setIsLoading(false)
+      return
+    }
+
     const success = await login(email, password)
     if (success) {
       navigate("/dashboard")
@@ -83,6 +97,25 @@ export const LoginForm: React.FC<LoginFormProps> = ({ onSuccess }) => {
               </div>
 
               <form onSubmit={handleSubmit} className="space-y-6">
+                {twoFactorPending ? (
+                <div>
+                  <label className="block text-sm font-semibold text-gray-200 mb-2">Verification Code</label>
+                  <div className="relative">
+                    <KeyRound className="absolute left-3 top-1/2 transform -translate-y-1/2 w-5 h-5 text-gray-400" />
+                    <input
+                      type="text"
+                      inputMode="numeric"
+                      autoComplete="one-time-code"
+                      value={code}
+                      onChange={(e) => setCode(e.target.value)}
+                      className="w-full pl-10 pr-4 py-3 glass-dark rounded-lg text-white placeholder-gray-400 focus:outline-none focus:ring-2 focus:ring-[#0dab76] transition-all duration-200"
+                      placeholder="Code from your authenticator app or a recovery code"
+                      required
+                    />
+                  </div>
+                </div>
+                ) : (
+                <>
                 <div>
                   <label className="block text-sm font-semibold text-gray-200 mb-2">Email Address</label>
                   <div className="relative">
@@ -112,6 +145,8 @@ export const LoginForm: React.FC<LoginFormProps> = ({ onSuccess }) => {
                     />
                   </div>
                 </div>
+                </>
+                )}
 
                 {error && (
                   <div className="text-[#c33c54] text-sm text-center bg-red-500/10 p-3 rounded-lg border border-red-500/20">
@@ -130,6 +165,8 @@ export const LoginForm: React.FC<LoginFormProps> = ({ onSuccess }) => {
                       <div className="w-5 h-5 border-2 border-white/30 border-t-white rounded-full animate-spin mr-2"></div>
                       Signing in...
                     </span>
+                  ) : twoFactorPending ? (
+                    "Verify"
                   ) : (
                     "Sign In"
                   )}
@@ -13,6 +13,9 @@ interface User {
 interface AuthContextType {
   user: User | null
   login: (email: string, password: string) => Promise<boolean>
+  // true cuando el login espera el código 2FA (ver verifyTwoFactor)
+  twoFactorPending: boolean
+  verifyTwoFactor: (code: string) => Promise<boolean>
   register: (name: string, email: string, password: string) => Promise<boolean>
   logout: () => void
   isLoading: boolean
@@ -20,6 +23,41 @@ interface AuthContextType {
 
 const AuthContext = createContext<AuthContextType | undefined>(undefined)
 
+// Margen para renovar el access token antes de que expire
+const REFRESH_MARGIN_MS = 60_000
+
+const storeTokens = (token: string, refreshToken?: string, expiresIn?: number) => {
+  localStorage.setItem("authToken", token)
+  if (refreshToken) {
+    localStorage.setItem("refreshToken", refreshToken)
+  }
+  if (expiresIn) {
+    localStorage.setItem("authTokenExpiresAt", String(Date.now() + expiresIn * 1000))
+  }
+}
+
+const clearTokens = () => {
+  localStorage.removeItem("authToken")
+  localStorage.removeItem("refreshToken")
+  localStorage.removeItem("authTokenExpiresAt")
+}
+
+const refreshTokens = async (): Promise<boolean> => {
+  const refreshToken = localStorage.getItem("refreshToken")
+  if (!refreshToken) {
+    return false
+  }
+
+  const response = await authApi.refresh(refreshToken)
+  if (response.success && response.token) {
+    storeTokens(response.token, response.refreshToken, response.expiresIn)
+    return true
+  }
+
+  clearTokens()
+  return false
+}
+
 export const useAuth = () => {
   const context = useContext(AuthContext)
   if (context === undefined) {
@@ -35,6 +73,8 @@ interface AuthProviderProps {
 export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
   const [user, setUser] = useState<User | null>(null)
   const [isLoading, setIsLoading] = useState(true)
+  const [tokenRefreshes, setTokenRefreshes] = useState(0)
+  const [twoFactorChallenge, setTwoFactorChallenge] = useState<{ email: string; challengeToken: string } | null>(null)
   const navigate = useNavigate()
 
   const validateToken = async (_token: string): Promise<boolean> => {
@@ -49,7 +89,7 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
       return false
     } catch (error: any) {
       if (error.response?.status === 401 || error.response?.status === 403) {
-        localStorage.removeItem("authToken")
+        clearTokens()
         return false
       }
       
@@ -59,6 +99,11 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
 
   useEffect(() => {
     const initAuth = async () => {
+      const expiresAt = Number(localStorage.getItem("authTokenExpiresAt") || 0)
+      if (expiresAt && expiresAt - REFRESH_MARGIN_MS <= Date.now()) {
+        await refreshTokens()
+      }
+
       const token = localStorage.getItem("authToken")
 
       if (token) {
@@ -73,13 +118,42 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     initAuth()
   }, [])
 
+  // Renovar el access token poco antes de que expire mientras haya sesión
+  useEffect(() => {
+    if (!user) {
+      return
+    }
+
+    const expiresAt = Number(localStorage.getItem("authTokenExpiresAt") || 0)
+    if (!expiresAt) {
+      return
+    }
+
+    const timeout = setTimeout(async () => {
+      if (await refreshTokens()) {
+        setTokenRefreshes((count) => count + 1)
+      } else {
+        setUser(null)
+        navigate("/login")
+      }
+    }, Math.max(expiresAt - REFRESH_MARGIN_MS - Date.now(), 0))
+
+    return () => clearTimeout(timeout)
+  }, [user, tokenRefreshes])
+
   const login = async (email: string, password: string): Promise<boolean> => {
     try {
       setIsLoading(true)
       const response = await authApi.login(email, password)
 
+      if (response.twoFactorRequired && response.challengeToken) {
+        setTwoFactorChallenge({ email, challengeToken: response.challengeToken })
+        setIsLoading(false)
+        return false
+      }
+
       if (response && response.success && response.token && response.user) {
-        localStorage.setItem("authToken", response.token)
+        storeTokens(response.token, response.refreshToken, response.expiresIn)
         setUser(response.user)
         setIsLoading(false)
         return true
@@ -93,13 +167,38 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
     }
   }
 
+  const verifyTwoFactor = async (code: string): Promise<boolean> => {
+    if (!twoFactorChallenge) {
+      return false
+    }
+
+    try {
+      setIsLoading(true)
+      const response = await authApi.loginTwoFactor(twoFactorChallenge.email, twoFactorChallenge.challengeToken, code)
+
+      if (response.success && response.token && response.user) {
+        storeTokens(response.token, response.refreshToken, response.expiresIn)
+        setTwoFactorChallenge(null)
+        setUser(response.user)
+        setIsLoading(false)
+        return true
+      }
+
+      setIsLoading(false)
+      return false
+    } catch (error: any) {
+      setIsLoading(false)
+      return false
+    }
+  }
+
   const register = async (name: string, email: string, password: string): Promise<boolean> => {
     try {
       setIsLoading(true)
       const response = await authApi.register(name, email, password)
 
       if (response && response.success && response.token && response.user) {
-        localStorage.setItem("authToken", response.token)
+        storeTokens(response.token, response.refreshToken, response.expiresIn)
         setUser(response.user)
         setIsLoading(false)
         navigate("/dashboard")
@@ -115,7 +214,9 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
   }
 
   const logout = () => {
-    localStorage.removeItem("authToken")
+    // Revocar la sesión en el servidor; la sesión local se limpia igualmente
+    authApi.logout()
+    clearTokens()
     setUser(null)
     navigate("/login")
   }
@@ -123,6 +224,8 @@ export const AuthProvider: React.FC<AuthProviderProps> = ({ children }) => {
   const value: AuthContextType = {
     user,
     login,
+    twoFactorPending: twoFactorChallenge !== null,
+    verifyTwoFactor,
     register,
     logout,
     isLoading
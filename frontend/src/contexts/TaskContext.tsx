@@ -216,16 +216,28 @@ export const TaskProvider: React.FC<TaskProviderProps> = ({ children }) => {
   }, [])
 
   const updateTask = useCallback(async (id: string, taskUpdates: Partial<Task>) => {
+    // Versión que el usuario tenía a la vista: si otro cliente la cambió, el servidor
+    // rechaza la edición (412) y se recargan las tareas
+    const version = tasks.find((task) => task.id === id)?.version
+
     // Optimistic update - update UI immediately
     setTasks((prevTasks) =>
       prevTasks.map((task) =>
-        task.id === id ? { ...task, ...taskUpdates, updated_at: new Date().toISOString() } : task,
+        task.id === id
+          ? {
+              ...task,
+              ...taskUpdates,
+              updated_at: new Date().toISOString(),
+              // El servidor incrementa la versión en cada cambio aplicado
+              version: task.version !== undefined ? task.version + 1 : undefined,
+            }
+          : task,
       ),
     )
 
     // Sync with API in background
     try {
-      const response = await taskApi.updateTask(id, taskUpdates)
+      const response = await taskApi.updateTask(id, taskUpdates, version)
       if (!response.success) {
         // Revert optimistic update on failure
         console.error("Failed to update task, reverting changes")
@@ -236,7 +248,7 @@ export const TaskProvider: React.FC<TaskProviderProps> = ({ children }) => {
       // Revert optimistic update on error
       await refreshTasks()
     }
-  }, [])
+  }, [tasks])
 
   const deleteTask = useCallback(
     async (id: string) => {
@@ -287,7 +299,8 @@ export const TaskProvider: React.FC<TaskProviderProps> = ({ children }) => {
 
   const deleteProject = useCallback(async (id: string) => {
     try {
-      const response = await projectApi.deleteProject(id)
+      // Las tareas del proyecto pasan a la bandeja de entrada
+      const response = await projectApi.deleteProject(id, "inbox")
       if (response.success) {
         await refreshProjects()
         await refreshTasks()
@@ -1,5 +1,5 @@
 import { API_BASE_URL, handleApiResponse } from "../config"
-import type { ApiResponse } from "../types"
+import type { AccessToken, ApiResponse } from "../types"
 
 export const authApi = {
   async login(email: string, password: string): Promise<ApiResponse> {
@@ -14,14 +14,26 @@ export const authApi = {
 
       const data = await handleApiResponse(response)
 
+      // Con 2FA activo el login requiere un segundo paso con el código
+      if (data.two_factor_required) {
+        return {
+          success: false,
+          twoFactorRequired: true,
+          challengeToken: data.challenge_token,
+        }
+      }
+
       if (data.token && data.user_id) {
         return {
           success: true,
           token: data.token,
+          refreshToken: data.refresh_token,
+          expiresIn: data.expires_in,
           user: {
             id: data.user_id,
             email: email,
             name: email.split("@")[0],
+            email_verified: data.email_verified,
           },
         }
       }
@@ -38,6 +50,45 @@ export const authApi = {
     }
   },
 
+  async loginTwoFactor(email: string, challengeToken: string, code: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/login/2fa`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ challenge_token: challengeToken, code }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      if (data.token && data.user_id) {
+        return {
+          success: true,
+          token: data.token,
+          refreshToken: data.refresh_token,
+          expiresIn: data.expires_in,
+          user: {
+            id: data.user_id,
+            email: email,
+            name: email.split("@")[0],
+            email_verified: data.email_verified,
+          },
+        }
+      }
+
+      return {
+        success: false,
+        message: data.message || "Two-factor verification failed",
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Two-factor verification failed",
+      }
+    }
+  },
+
   async register(name: string, email: string, password: string): Promise<ApiResponse> {
     try {
       const response = await fetch(`${API_BASE_URL}/auth/register`, {
@@ -56,6 +107,8 @@ export const authApi = {
           return {
             success: true,
             token: loginResponse.token,
+            refreshToken: loginResponse.refreshToken,
+            expiresIn: loginResponse.expiresIn,
             user: loginResponse.user,
             message: data.message || "Registration successful",
           }
@@ -74,6 +127,367 @@ export const authApi = {
     }
   },
 
+  async refresh(refreshToken: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/refresh`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ refresh_token: refreshToken }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        token: data.token,
+        refreshToken: data.refresh_token,
+        expiresIn: data.expires_in,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Token refresh failed",
+      }
+    }
+  },
+
+  async logout(allSessions: boolean = false): Promise<ApiResponse> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        return { success: true }
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/${allSessions ? "logout-all" : "logout"}`, {
+        method: "POST",
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Logout failed",
+      }
+    }
+  },
+
+  async changePassword(currentPassword: string, newPassword: string): Promise<ApiResponse> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/password/change`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify({ current_password: currentPassword, new_password: newPassword }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      // Las demás sesiones quedan cerradas; esta continúa con el nuevo par de tokens
+      return {
+        success: true,
+        token: data.token,
+        refreshToken: data.refresh_token,
+        expiresIn: data.expires_in,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Password change failed",
+      }
+    }
+  },
+
+  async forgotPassword(email: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/password/forgot`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ email }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Password reset request failed",
+      }
+    }
+  },
+
+  async resetPassword(resetToken: string, newPassword: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/password/reset`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ token: resetToken, new_password: newPassword }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Password reset failed",
+      }
+    }
+  },
+
+  async verifyEmail(verificationToken: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/email/verify`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ token: verificationToken }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Email verification failed",
+      }
+    }
+  },
+
+  async resendVerification(email: string): Promise<ApiResponse> {
+    try {
+      const response = await fetch(`${API_BASE_URL}/auth/email/resend`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+        },
+        body: JSON.stringify({ email }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to resend verification email",
+      }
+    }
+  },
+
+  async setupTwoFactor(): Promise<ApiResponse<{ secret: string; otpauth_url: string }>> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/2fa/setup`, {
+        method: "POST",
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        data: { secret: data.secret, otpauth_url: data.otpauth_url },
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Two-factor setup failed",
+      }
+    }
+  },
+
+  async enableTwoFactor(code: string): Promise<ApiResponse<string[]>> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/2fa/enable`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify({ code }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      // Los códigos de recuperación solo se reciben esta vez
+      return {
+        success: true,
+        data: data.recovery_codes,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to enable two-factor authentication",
+      }
+    }
+  },
+
+  async disableTwoFactor(password: string, code: string): Promise<ApiResponse> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/2fa/disable`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify({ password, code }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to disable two-factor authentication",
+      }
+    }
+  },
+
+  async listAccessTokens(): Promise<ApiResponse<AccessToken[]>> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/tokens`, {
+        method: "GET",
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        data,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to load access tokens",
+      }
+    }
+  },
+
+  async createAccessToken(name: string, scopes: string[], expiresInDays?: number): Promise<ApiResponse<AccessToken>> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/tokens`, {
+        method: "POST",
+        headers: {
+          "Content-Type": "application/json",
+          Authorization: `Bearer ${token}`,
+        },
+        body: JSON.stringify({ name, scopes, expires_in_days: expiresInDays }),
+      })
+
+      const data = await handleApiResponse(response)
+
+      // El valor del token solo se recibe esta vez
+      return {
+        success: true,
+        data,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to create access token",
+      }
+    }
+  },
+
+  async revokeAccessToken(tokenId: string): Promise<ApiResponse> {
+    try {
+      const token = localStorage.getItem("authToken")
+      if (!token) {
+        throw new Error("No auth token found")
+      }
+
+      const response = await fetch(`${API_BASE_URL}/auth/tokens/${tokenId}`, {
+        method: "DELETE",
+        headers: {
+          Authorization: `Bearer ${token}`,
+        },
+      })
+
+      const data = await handleApiResponse(response)
+
+      return {
+        success: true,
+        message: data.message,
+      }
+    } catch (error) {
+      return {
+        success: false,
+        message: error instanceof Error ? error.message : "Failed to revoke access token",
+      }
+    }
+  },
+
   async getMe(): Promise<ApiResponse> {
     try {
       const token = localStorage.getItem("authToken")
@@ -98,6 +512,7 @@ export const authApi = {
           email: data.email,
           name: data.profile_name || data.email.split("@")[0],
           profile_id: data.profile_id,
+          email_verified: data.email_verified,
         },
       }
     } catch (error) {
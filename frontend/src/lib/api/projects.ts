@@ -1,5 +1,5 @@
 import { API_BASE_URL, createAuthHeaders, handleApiResponse } from "../config"
-import type { ApiResponse } from "../types"
+import type { ApiResponse, ProjectDeleteStrategy } from "../types"
 
 export const projectApi = {
   async getProjects(): Promise<ApiResponse> {
@@ -93,9 +93,9 @@ export const projectApi = {
     }
   },
 
-  async deleteProject(id: string): Promise<ApiResponse> {
+  async deleteProject(id: string, strategy: ProjectDeleteStrategy = "restrict"): Promise<ApiResponse> {
     try {
-      const response = await fetch(`${API_BASE_URL}/projects/${id}`, {
+      const response = await fetch(`${API_BASE_URL}/projects/${id}?strategy=${strategy}`, {
         method: "DELETE",
         headers: createAuthHeaders("DELETE"),
       })
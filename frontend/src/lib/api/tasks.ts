@@ -145,23 +145,28 @@ export const taskApi = {
     }
   },
 
-  async updateTask(id: string, updates: any): Promise<ApiResponse> {
+  async updateTask(id: string, updates: any, version?: number): Promise<ApiResponse> {
     try {
+      // JSON Merge Patch: las claves ausentes no cambian y null borra el campo,
+      // así que un proyecto, descripción o fecha vacíos en el formulario se quitan
+      const clearableFields = ["project_id", "description", "due_date"]
       const payload: any = Object.fromEntries(
-        Object.entries(updates).filter(
-          ([key, value]) =>
-            value !== undefined &&
-            value !== null &&
-            value !== "" &&
-            !(key === 'project_id' && (value === "" || value === null || value === undefined)) &&
-            key !== 'tags'
-        )
+        Object.entries(updates)
+          .filter(([key, value]) => value !== undefined && key !== 'tags')
+          .map(([key, value]) => [key, clearableFields.includes(key) && (value === "" || value === null) ? null : value])
+          .filter(([, value]) => value !== "")
       )
 
-      console.log("[v0] Making PUT request to /tasks/" + id)
+      // Con la versión conocida el servidor responde 412 si otro cliente cambió la tarea
+      const headers: Record<string, string> = { ...(createAuthHeaders("PATCH") as Record<string, string>) }
+      if (version !== undefined) {
+        headers["If-Match"] = `"${version}"`
+      }
+
+      console.log("[v0] Making PATCH request to /tasks/" + id)
       const response = await fetch(`${API_BASE_URL}/tasks/${id}`, {
-        method: "PUT",
-        headers: createAuthHeaders("PUT"),
+        method: "PATCH",
+        headers,
         body: JSON.stringify(payload),
       })
 
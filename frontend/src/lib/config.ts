@@ -23,7 +23,7 @@ export const createAuthHeaders = (method: string = "GET"): HeadersInit => {
 export const handleApiResponse = async (response: Response) => {
   if (!response.ok) {
     const errorData = await response.json().catch(() => ({ message: "Network error" }));
-    throw new Error(errorData.message || `HTTP error! status: ${response.status}`);
+    throw new Error(errorData.error || errorData.message || `HTTP error! status: ${response.status}`);
   }
   return response.json();
 }
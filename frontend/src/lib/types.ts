@@ -3,6 +3,10 @@ export interface ApiResponse<T = any> {
   data?: T
   message?: string
   token?: string
+  refreshToken?: string
+  expiresIn?: number
+  twoFactorRequired?: boolean
+  challengeToken?: string
   user?: any
   tasks?: any[]
   projects?: any[]
@@ -17,6 +21,19 @@ export interface User {
   profile_created_at?: string
   profile_user_id?: string
   created_at?: string
+  email_verified?: boolean
+}
+
+export interface AccessToken {
+  id: string
+  name: string
+  token_prefix: string
+  scopes: string[]
+  expires_at: string | null
+  last_used_at: string | null
+  created_at: string
+  // Solo presente al crearlo
+  token?: string
 }
 
 export interface Project {
@@ -27,6 +44,9 @@ export interface Project {
   created_at: string
 }
 
+// Qué hacer con las tareas al eliminar un proyecto
+export type ProjectDeleteStrategy = "restrict" | "cascade" | "inbox"
+
 export interface Tag {
   id: string
   user_id: string
@@ -43,9 +63,166 @@ export interface Task {
   title: string
   description?: string
   status: "todo" | "doing" | "done"
-  priority: "low" | "med" | "high"
+  priority: "low" | "med" | "high" | "urgent"
   due_date?: string
   tags: Tag[]
   created_at: string
   updated_at: string
-}
\ No newline at end of file
+  // Aumenta en cada cambio; se envía como If-Match para no pisar ediciones de otro cliente
+  version?: number
+  parent_task_id?: string | null
+  // Regla RRULE normalizada, p. ej. "FREQ=WEEKLY;BYDAY=MO,WE"
+  recurrence_rule?: string | null
+  // La repetición anterior, si esta tarea se generó al completarla
+  recurrence_source_id?: string | null
+  // Solo en listados y árboles, si la tarea tiene subtareas
+  progress?: TaskProgress
+  subtasks?: Task[]
+  // Solo en GET /tasks/{id}
+  blocked?: boolean
+  blocked_by?: TaskDependency[]
+  blocking?: TaskDependency[]
+}
+
+export interface TaskDependency {
+  id: string
+  title: string
+  status: Task["status"]
+}
+
+export interface TaskProgress {
+  total: number
+  completed: number
+  percent: number
+}
+export interface TaskReminder {
+  id: string
+  task_id: string
+  // Uno de los dos: momento fijo o minutos antes de due_date
+  remind_at?: string | null
+  offset_minutes?: number | null
+  // null si es relativo y la tarea no tiene due_date
+  fire_at?: string | null
+  channels: ("in_app" | "webhook" | "email")[]
+  status: "pending" | "fired" | "missed" | "failed" | "cancelled"
+  delivered_channels: string[]
+  attempts: number
+  last_error?: string | null
+  fired_at?: string | null
+  created_at: string
+}
+
+export type NotificationEventType = "reminder" | "task_overdue"
+
+export interface AppNotification {
+  id: string
+  event_type: NotificationEventType
+  title: string
+  body?: string | null
+  // null si la tarea ya no existe
+  task_id?: string | null
+  read_at?: string | null
+  created_at: string
+}
+
+export interface NotificationsResponse {
+  notifications: AppNotification[]
+  unread_count: number
+  total_count: number
+  page: number
+  limit: number
+  total_pages: number
+}
+
+export type NotificationPreferences = Record<NotificationEventType, boolean>
+
+// Evento del stream GET /events; el tipo SSE es `${entity}.${action}`
+export interface ChangeEvent {
+  id: number
+  entity: "task" | "project" | "tag" | "task_tag"
+  action: "created" | "updated" | "deleted"
+  // En task_tag, la tarea; related_id es la etiqueta
+  entity_id: string
+  related_id?: string
+  occurred_at: string
+}
+
+// WebSocket /ws
+export type PresenceActivity = "viewing" | "editing"
+
+export interface Presence {
+  connection_id: string
+  user_id: string
+  task_id: string
+  activity: PresenceActivity
+  since: string
+}
+
+export type ClientMessage =
+  | { type: "auth"; token: string }
+  | { type: "subscribe"; project_id: string }
+  | { type: "unsubscribe"; project_id: string }
+  | { type: "presence"; task_id?: string | null; activity?: PresenceActivity }
+  | { type: "ping" }
+
+export type ServerMessage =
+  | { type: "ready"; connection_id: string; user_id: string }
+  | { type: "subscribed"; project_id: string; presence: Presence[] }
+  | { type: "unsubscribed"; project_id: string }
+  // Sin task_id: la conexión dejó la tarea o se desconectó
+  | { type: "presence"; project_id: string; connection_id: string; user_id: string; task_id?: string; activity?: PresenceActivity }
+  | { type: "task_changed"; project_id: string | null; task_id: string; action: "created" | "updated" | "deleted"; task: Record<string, unknown> | null }
+  | { type: "resync" }
+  | { type: "pong" }
+  | { type: "error"; code: string; message: string }
+
+// GET /sync: con full, reemplaza el estado local; si no, los cambios desde `since`
+export interface TaskTagLink {
+  task_id: string
+  tag_id: string
+}
+
+export interface SyncResponse {
+  cursor: number
+  has_more: boolean
+  full: boolean
+  tasks: Task[]
+  projects: Project[]
+  tags: Tag[]
+  task_tags: (TaskTagLink & { created_at?: string })[]
+  deleted: {
+    tasks: string[]
+    projects: string[]
+    tags: string[]
+    task_tags: TaskTagLink[]
+  }
+}
+
+// POST /sync; id, task_id, tag_id, data.project_id y data.parent_task_id aceptan "$<client_id>"
+export interface SyncMutation {
+  client_id?: string
+  entity: "task" | "project" | "tag" | "task_tag"
+  op: "create" | "update" | "delete"
+  id?: string
+  task_id?: string
+  tag_id?: string
+  base_version?: number
+  data?: Record<string, unknown>
+}
+
+export type SyncMutationStatus = "applied" | "conflict" | "not_found" | "invalid" | "rejected" | "error"
+
+export interface SyncMutationResult {
+  index: number
+  client_id: string | null
+  status: SyncMutationStatus
+  id: string | null
+  response: unknown
+  // Solo en conflict: el estado actual de la entidad
+  current?: Record<string, unknown>
+}
+
+export interface SyncMutationsResponse {
+  results: SyncMutationResult[]
+  applied: number
+}
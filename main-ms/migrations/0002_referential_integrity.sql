@@ -0,0 +1,96 @@
+-- Integridad referencial entre proyectos, tareas y etiquetas.
+-- Antes de crear las claves foráneas se corrigen los datos huérfanos que dejaba el esquema
+-- anterior: las tareas que apuntan a un proyecto inexistente (o de otro usuario) pasan a la
+-- bandeja de entrada y se eliminan los vínculos de task_tags sin tarea o sin etiqueta.
+
+UPDATE tasks t
+SET project_id = NULL
+WHERE t.project_id IS NOT NULL
+  AND NOT EXISTS (
+      SELECT 1 FROM projects p
+      WHERE p.id = t.project_id AND p.user_id = t.user_id
+  );
+
+DELETE FROM task_tags tt
+WHERE NOT EXISTS (SELECT 1 FROM tasks t WHERE t.id = tt.task_id)
+   OR NOT EXISTS (SELECT 1 FROM tags tg WHERE tg.id = tt.tag_id);
+
+-- La clave compuesta garantiza además que el proyecto de una tarea sea del mismo usuario.
+-- Borrar un proyecto con tareas falla: delete_project decide antes qué hacer con ellas.
+ALTER TABLE projects
+    ADD CONSTRAINT projects_id_user_id_key UNIQUE (id, user_id);
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_project_fkey
+    FOREIGN KEY (project_id, user_id) REFERENCES projects (id, user_id)
+    ON DELETE NO ACTION;
+
+-- Un vínculo no tiene sentido sin su tarea o su etiqueta
+ALTER TABLE task_tags
+    ADD CONSTRAINT task_tags_task_fkey
+    FOREIGN KEY (task_id) REFERENCES tasks (id) ON DELETE CASCADE;
+
+ALTER TABLE task_tags
+    ADD CONSTRAINT task_tags_tag_fkey
+    FOREIGN KEY (tag_id) REFERENCES tags (id) ON DELETE CASCADE;
+
+-- Eliminar proyecto indicando qué hacer con sus tareas:
+--   restrict: no se elimina si tiene tareas
+--   cascade:  se eliminan también sus tareas (y sus etiquetas asignadas)
+--   inbox:    las tareas pasan a la bandeja de entrada (sin proyecto)
+-- Devuelve una fila si el proyecto existe: si se eliminó y cuántas tareas afectó
+-- (las eliminadas, las movidas o, con restrict, las que impiden eliminarlo).
+DROP FUNCTION IF EXISTS delete_project(UUID, UUID);
+CREATE OR REPLACE FUNCTION delete_project(
+    p_user_id UUID,
+    p_project_id UUID,
+    p_strategy TEXT DEFAULT 'restrict'
+)
+RETURNS TABLE (
+    deleted BOOLEAN,
+    affected_tasks INTEGER
+) AS $$
+DECLARE
+    v_task_count INTEGER;
+BEGIN
+    IF p_strategy NOT IN ('restrict', 'cascade', 'inbox') THEN
+        RAISE EXCEPTION 'Invalid delete strategy: %', p_strategy
+            USING ERRCODE = 'invalid_parameter_value';
+    END IF;
+
+    -- Bloquea el proyecto para que no se le asignen tareas mientras se decide
+    PERFORM 1 FROM projects
+    WHERE id = p_project_id AND user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_strategy = 'cascade' THEN
+        DELETE FROM tasks
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSIF p_strategy = 'inbox' THEN
+        UPDATE tasks SET project_id = NULL
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSE
+        SELECT COUNT(*)::INTEGER INTO v_task_count
+        FROM tasks
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+
+        IF v_task_count > 0 THEN
+            RETURN QUERY SELECT FALSE, v_task_count;
+            RETURN;
+        END IF;
+    END IF;
+
+    DELETE FROM projects
+    WHERE id = p_project_id AND user_id = p_user_id;
+
+    RETURN QUERY SELECT TRUE, v_task_count;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION delete_project(UUID, UUID, TEXT) TO todo_app_user;
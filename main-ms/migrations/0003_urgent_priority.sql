@@ -0,0 +1,95 @@
+-- Prioridad "urgent": la API ya la aceptaba pero el enum no la tenía y crear una tarea
+-- urgente fallaba al convertir el valor. Se añade por encima de "high".
+ALTER TYPE task_priority ADD VALUE IF NOT EXISTS 'urgent' AFTER 'high';
+
+-- Ordenar por prioridad usa el orden del enum en lugar de un CASE con los valores, que
+-- no incluía "urgent" (esas tareas quedaban al final).
+CREATE OR REPLACE FUNCTION get_user_tasks_paginated(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_sort_by TEXT DEFAULT 'created_at',
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0
+)
+RETURNS TABLE(
+    id UUID,
+    user_id UUID,
+    project_id UUID,
+    title VARCHAR,
+    description TEXT,
+    status task_status,
+    priority task_priority,
+    due_date TIMESTAMPTZ,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+)
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    IF p_sort_by = 'title' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.title, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSIF p_sort_by = 'due_date' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.due_date NULLS LAST, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSIF p_sort_by = 'priority' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        -- El enum está declarado de menor a mayor prioridad (low, med, high, urgent)
+        ORDER BY t.priority DESC, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSE
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+    END IF;
+END;
+$$;
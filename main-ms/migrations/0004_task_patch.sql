@@ -0,0 +1,60 @@
+-- update_task combinaba cada parámetro con COALESCE, así que un NULL significaba "sin
+-- cambios" y no había forma de quitar la fecha límite, el proyecto o la descripción.
+-- PUT pasa a ser un reemplazo completo y PATCH aplica un JSON Merge Patch (RFC 7396).
+
+-- Reemplazo completo: los parámetros NULL dejan el campo en NULL. Se elimina primero
+-- porque CREATE OR REPLACE no puede quitar los valores por defecto de los parámetros.
+DROP FUNCTION IF EXISTS update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ);
+CREATE FUNCTION update_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_project_id UUID,
+    p_title VARCHAR,
+    p_description TEXT,
+    p_status task_status,
+    p_priority task_priority,
+    p_due_date TIMESTAMPTZ
+)
+RETURNS BOOLEAN AS $$
+BEGIN
+    UPDATE tasks
+    SET
+        project_id = p_project_id,
+        title = p_title,
+        description = p_description,
+        status = p_status,
+        priority = p_priority,
+        due_date = p_due_date
+    WHERE id = p_task_id AND user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Actualización parcial: solo cambian las claves presentes en p_patch; una clave con
+-- null deja el campo en NULL. main-ms valida el patch antes de llamarla (claves
+-- permitidas, tipos y que title, status y priority no sean null).
+DROP FUNCTION IF EXISTS patch_task(UUID, UUID, JSONB);
+CREATE FUNCTION patch_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_patch JSONB
+)
+RETURNS BOOLEAN AS $$
+BEGIN
+    UPDATE tasks
+    SET
+        project_id = CASE WHEN p_patch ? 'project_id' THEN (p_patch->>'project_id')::UUID ELSE project_id END,
+        title = CASE WHEN p_patch ? 'title' THEN p_patch->>'title' ELSE title END,
+        description = CASE WHEN p_patch ? 'description' THEN p_patch->>'description' ELSE description END,
+        status = CASE WHEN p_patch ? 'status' THEN (p_patch->>'status')::task_status ELSE status END,
+        priority = CASE WHEN p_patch ? 'priority' THEN (p_patch->>'priority')::task_priority ELSE priority END,
+        due_date = CASE WHEN p_patch ? 'due_date' THEN (p_patch->>'due_date')::TIMESTAMPTZ ELSE due_date END
+    WHERE id = p_task_id AND user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION patch_task(UUID, UUID, JSONB) TO todo_app_user;
@@ -0,0 +1,297 @@
+-- Control de concurrencia optimista: tareas y proyectos llevan una versión que aumenta en
+-- cada UPDATE. La API la expone como ETag y las actualizaciones aceptan las versiones
+-- esperadas (If-Match); si la fila cambió mientras tanto no se aplican.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE projects ADD COLUMN IF NOT EXISTS version INTEGER NOT NULL DEFAULT 1;
+
+CREATE OR REPLACE FUNCTION bump_row_version()
+RETURNS TRIGGER AS $$
+BEGIN
+    NEW.version = OLD.version + 1;
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS bump_tasks_version ON tasks;
+CREATE TRIGGER bump_tasks_version
+    BEFORE UPDATE ON tasks
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_row_version();
+
+DROP TRIGGER IF EXISTS bump_projects_version ON projects;
+CREATE TRIGGER bump_projects_version
+    BEFORE UPDATE ON projects
+    FOR EACH ROW
+    EXECUTE FUNCTION bump_row_version();
+
+-- Las lecturas devuelven filas completas (SETOF) para incluir la versión. get_user_tasks_paginated
+-- ya usaba SELECT t.* con una lista fija de columnas y dejaría de funcionar con la columna nueva.
+DROP FUNCTION IF EXISTS get_task(UUID, UUID);
+CREATE FUNCTION get_task(
+    p_user_id UUID,
+    p_task_id UUID
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.*
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_tasks_by_tag(UUID, UUID);
+CREATE FUNCTION get_tasks_by_tag(
+    p_user_id UUID,
+    p_tag_id UUID
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.*
+    FROM tasks t
+    JOIN task_tags tt ON t.id = tt.task_id
+    WHERE tt.tag_id = p_tag_id AND t.user_id = p_user_id
+    ORDER BY t.created_at DESC;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER);
+CREATE FUNCTION get_user_tasks_paginated(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_sort_by TEXT DEFAULT 'created_at',
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0
+)
+RETURNS SETOF tasks
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    IF p_sort_by = 'title' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.title, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSIF p_sort_by = 'due_date' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.due_date NULLS LAST, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSIF p_sort_by = 'priority' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        -- El enum está declarado de menor a mayor prioridad (low, med, high, urgent)
+        ORDER BY t.priority DESC, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+        
+    ELSE
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR 
+               t.title ILIKE '%' || p_search || '%' OR 
+               t.description ILIKE '%' || p_search || '%')
+        ORDER BY t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+    END IF;
+END;
+$$;
+
+DROP FUNCTION IF EXISTS get_user_projects(UUID);
+CREATE FUNCTION get_user_projects(
+    p_user_id UUID
+)
+RETURNS SETOF projects AS $$
+BEGIN
+    RETURN QUERY
+    SELECT p.*
+    FROM projects p
+    WHERE p.user_id = p_user_id
+    ORDER BY p.created_at DESC;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_project(UUID, UUID);
+CREATE FUNCTION get_project(
+    p_user_id UUID,
+    p_project_id UUID
+)
+RETURNS SETOF projects AS $$
+BEGIN
+    RETURN QUERY
+    SELECT p.*
+    FROM projects p
+    WHERE p.id = p_project_id AND p.user_id = p_user_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Las actualizaciones reciben p_if_match (versiones aceptadas; NULL: sin condición) y
+-- devuelven una fila con el resultado: ninguna si la fila no existe, (FALSE, versión
+-- actual) si la versión no coincide y (TRUE, versión nueva) si se aplicó el cambio.
+DROP FUNCTION IF EXISTS update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ);
+CREATE FUNCTION update_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_project_id UUID,
+    p_title VARCHAR,
+    p_description TEXT,
+    p_status task_status,
+    p_priority task_priority,
+    p_due_date TIMESTAMPTZ,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE tasks t
+    SET
+        project_id = p_project_id,
+        title = p_title,
+        description = p_description,
+        status = p_status,
+        priority = p_priority,
+        due_date = p_due_date
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    RETURNING t.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS patch_task(UUID, UUID, JSONB);
+CREATE FUNCTION patch_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_patch JSONB,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE tasks t
+    SET
+        project_id = CASE WHEN p_patch ? 'project_id' THEN (p_patch->>'project_id')::UUID ELSE t.project_id END,
+        title = CASE WHEN p_patch ? 'title' THEN p_patch->>'title' ELSE t.title END,
+        description = CASE WHEN p_patch ? 'description' THEN p_patch->>'description' ELSE t.description END,
+        status = CASE WHEN p_patch ? 'status' THEN (p_patch->>'status')::task_status ELSE t.status END,
+        priority = CASE WHEN p_patch ? 'priority' THEN (p_patch->>'priority')::task_priority ELSE t.priority END,
+        due_date = CASE WHEN p_patch ? 'due_date' THEN (p_patch->>'due_date')::TIMESTAMPTZ ELSE t.due_date END
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    RETURNING t.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS update_project(UUID, UUID, VARCHAR, VARCHAR);
+CREATE FUNCTION update_project(
+    p_user_id UUID,
+    p_project_id UUID,
+    p_name VARCHAR,
+    p_color VARCHAR DEFAULT NULL,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT p.version INTO v_version
+    FROM projects p
+    WHERE p.id = p_project_id AND p.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE projects p
+    SET name = p_name, color = p_color
+    WHERE p.id = p_project_id AND p.user_id = p_user_id
+    RETURNING p.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION get_task(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_tasks_by_tag(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_projects(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_project(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ, INTEGER[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION patch_task(UUID, UUID, JSONB, INTEGER[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION update_project(UUID, UUID, VARCHAR, VARCHAR, INTEGER[]) TO todo_app_user;
@@ -0,0 +1,362 @@
+-- Subtareas: una tarea puede colgar de otra del mismo usuario (parent_task_id). Al borrar
+-- una tarea se borran también sus subtareas, y el progreso de una tarea padre se calcula
+-- a partir del estado de sus subtareas directas.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS parent_task_id UUID;
+
+-- Igual que con los proyectos, la clave compuesta garantiza que la tarea padre sea del
+-- mismo usuario
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_id_user_id_key UNIQUE (id, user_id);
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_parent_fkey
+    FOREIGN KEY (parent_task_id, user_id) REFERENCES tasks (id, user_id)
+    ON DELETE CASCADE;
+
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_parent_not_self CHECK (parent_task_id <> id);
+
+CREATE INDEX IF NOT EXISTS idx_tasks_parent_task_id ON tasks (parent_task_id);
+
+-- Mover una tarea debajo de una de sus subtareas crearía un ciclo. El lock consultivo por
+-- usuario serializa los cambios de jerarquía: sin él, mover A bajo B y B bajo A a la vez
+-- pasaría ambas comprobaciones.
+CREATE OR REPLACE FUNCTION check_task_hierarchy()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtextextended('task_hierarchy:' || NEW.user_id::TEXT, 0));
+
+    IF EXISTS (
+        WITH RECURSIVE ancestors AS (
+            SELECT t.id, t.parent_task_id
+            FROM tasks t
+            WHERE t.id = NEW.parent_task_id
+            UNION ALL
+            SELECT t.id, t.parent_task_id
+            FROM tasks t
+            JOIN ancestors a ON t.id = a.parent_task_id
+        )
+        SELECT 1 FROM ancestors WHERE id = NEW.id
+    ) THEN
+        RAISE EXCEPTION 'A task cannot be moved under one of its subtasks'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'tasks_parent_cycle';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS check_tasks_hierarchy ON tasks;
+CREATE TRIGGER check_tasks_hierarchy
+    BEFORE UPDATE OF parent_task_id ON tasks
+    FOR EACH ROW
+    WHEN (NEW.parent_task_id IS NOT NULL AND NEW.parent_task_id IS DISTINCT FROM OLD.parent_task_id)
+    EXECUTE FUNCTION check_task_hierarchy();
+
+-- Completar una tarea con subtareas abiertas depende de app.parent_completion, que main-ms
+-- fija en cada transacción (tasks.parent_completion en su configuración):
+--   block:   se rechaza (por defecto, también si no está fijado)
+--   cascade: se completan también todas sus subtareas
+--   allow:   se completa sin tocar las subtareas
+CREATE OR REPLACE FUNCTION check_open_subtasks()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_open INTEGER;
+BEGIN
+    IF COALESCE(NULLIF(current_setting('app.parent_completion', true), ''), 'block') <> 'block' THEN
+        RETURN NEW;
+    END IF;
+
+    SELECT COUNT(*)::INTEGER INTO v_open
+    FROM tasks t
+    WHERE t.parent_task_id = NEW.id AND t.user_id = NEW.user_id AND t.status <> 'done';
+
+    IF v_open > 0 THEN
+        RAISE EXCEPTION 'Task has % open subtask(s)', v_open
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'tasks_open_subtasks';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Cada subtarea completada dispara a su vez este trigger, así que la cascada recorre
+-- todo el subárbol
+CREATE OR REPLACE FUNCTION cascade_task_completion()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF current_setting('app.parent_completion', true) = 'cascade' THEN
+        UPDATE tasks t
+        SET status = 'done'
+        WHERE t.parent_task_id = NEW.id AND t.user_id = NEW.user_id AND t.status <> 'done';
+    END IF;
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS check_tasks_open_subtasks ON tasks;
+CREATE TRIGGER check_tasks_open_subtasks
+    BEFORE UPDATE OF status ON tasks
+    FOR EACH ROW
+    WHEN (NEW.status = 'done' AND OLD.status IS DISTINCT FROM 'done')
+    EXECUTE FUNCTION check_open_subtasks();
+
+DROP TRIGGER IF EXISTS cascade_tasks_completion ON tasks;
+CREATE TRIGGER cascade_tasks_completion
+    AFTER UPDATE OF status ON tasks
+    FOR EACH ROW
+    WHEN (NEW.status = 'done' AND OLD.status IS DISTINCT FROM 'done')
+    EXECUTE FUNCTION cascade_task_completion();
+
+-- create_task acepta la tarea padre. Una subtarea sin proyecto hereda el de su padre.
+DROP FUNCTION IF EXISTS create_task(UUID, VARCHAR, UUID, TEXT, task_status, task_priority, TIMESTAMPTZ);
+CREATE FUNCTION create_task(
+    p_user_id UUID,
+    p_title VARCHAR,
+    p_project_id UUID DEFAULT NULL,
+    p_description TEXT DEFAULT NULL,
+    p_status task_status DEFAULT 'todo',
+    p_priority task_priority DEFAULT 'med',
+    p_due_date TIMESTAMPTZ DEFAULT NULL,
+    p_parent_task_id UUID DEFAULT NULL
+)
+RETURNS UUID AS $$
+DECLARE
+    v_project_id UUID := p_project_id;
+    new_id UUID;
+BEGIN
+    IF v_project_id IS NULL AND p_parent_task_id IS NOT NULL THEN
+        SELECT t.project_id INTO v_project_id
+        FROM tasks t
+        WHERE t.id = p_parent_task_id AND t.user_id = p_user_id;
+    END IF;
+
+    INSERT INTO tasks (user_id, project_id, title, description, status, priority, due_date, parent_task_id)
+    VALUES (p_user_id, v_project_id, p_title, p_description, p_status, p_priority, p_due_date, p_parent_task_id)
+    RETURNING id INTO new_id;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Mover una tarea: p_parent_task_id NULL la deja en el nivel superior. Mismo contrato que
+-- update_task: ninguna fila si no existe, (FALSE, versión actual) si p_if_match no
+-- coincide y (TRUE, versión nueva) si se movió.
+DROP FUNCTION IF EXISTS move_task(UUID, UUID, UUID, INTEGER[]);
+CREATE FUNCTION move_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_parent_task_id UUID,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE tasks t
+    SET parent_task_id = p_parent_task_id
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    RETURNING t.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Subtareas directas de una tarea, en orden de creación
+DROP FUNCTION IF EXISTS get_subtasks(UUID, UUID);
+CREATE FUNCTION get_subtasks(
+    p_user_id UUID,
+    p_task_id UUID
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.*
+    FROM tasks t
+    WHERE t.parent_task_id = p_task_id AND t.user_id = p_user_id
+    ORDER BY t.created_at, t.id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Todas las subtareas, a cualquier profundidad, de las tareas indicadas (sin incluirlas).
+-- main-ms arma el árbol con parent_task_id.
+DROP FUNCTION IF EXISTS get_task_descendants(UUID, UUID[]);
+CREATE FUNCTION get_task_descendants(
+    p_user_id UUID,
+    p_task_ids UUID[]
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    WITH RECURSIVE descendants AS (
+        SELECT t.*
+        FROM tasks t
+        WHERE t.parent_task_id = ANY(p_task_ids) AND t.user_id = p_user_id
+        UNION ALL
+        SELECT t.*
+        FROM tasks t
+        JOIN descendants d ON t.parent_task_id = d.id
+        WHERE t.user_id = p_user_id
+    )
+    SELECT d.*
+    FROM descendants d
+    ORDER BY d.created_at, d.id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Progreso de cada tarea con subtareas: cuántas subtareas directas tiene y cuántas están
+-- completadas. Las tareas sin subtareas no aparecen.
+DROP FUNCTION IF EXISTS get_subtask_progress(UUID, UUID[]);
+CREATE FUNCTION get_subtask_progress(
+    p_user_id UUID,
+    p_task_ids UUID[]
+)
+RETURNS TABLE(task_id UUID, total INTEGER, completed INTEGER) AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.parent_task_id,
+           COUNT(*)::INTEGER,
+           (COUNT(*) FILTER (WHERE t.status = 'done'))::INTEGER
+    FROM tasks t
+    WHERE t.parent_task_id = ANY(p_task_ids) AND t.user_id = p_user_id
+    GROUP BY t.parent_task_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- El listado puede limitarse a las tareas de nivel superior (p_top_level_only); el conteo
+-- recibe el mismo filtro para que la paginación coincida
+DROP FUNCTION IF EXISTS get_user_tasks_count(UUID, task_status, task_priority, UUID, TEXT);
+CREATE FUNCTION get_user_tasks_count(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_top_level_only BOOLEAN DEFAULT FALSE
+)
+RETURNS TABLE(count BIGINT)
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT COUNT(*)::BIGINT
+    FROM tasks t
+    WHERE t.user_id = p_user_id
+      AND (p_status IS NULL OR t.status = p_status)
+      AND (p_priority IS NULL OR t.priority = p_priority)
+      AND (p_project_id IS NULL OR t.project_id = p_project_id)
+      AND (p_search IS NULL OR
+           t.title ILIKE '%' || p_search || '%' OR
+           t.description ILIKE '%' || p_search || '%')
+      AND (NOT p_top_level_only OR t.parent_task_id IS NULL);
+END;
+$$;
+
+DROP FUNCTION IF EXISTS get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER);
+CREATE FUNCTION get_user_tasks_paginated(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_sort_by TEXT DEFAULT 'created_at',
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0,
+    p_top_level_only BOOLEAN DEFAULT FALSE
+)
+RETURNS SETOF tasks
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    IF p_sort_by = 'title' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+        ORDER BY t.title, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSIF p_sort_by = 'due_date' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+        ORDER BY t.due_date NULLS LAST, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSIF p_sort_by = 'priority' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+        -- El enum está declarado de menor a mayor prioridad (low, med, high, urgent)
+        ORDER BY t.priority DESC, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSE
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+        ORDER BY t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+    END IF;
+END;
+$$;
+
+GRANT EXECUTE ON FUNCTION create_task(UUID, VARCHAR, UUID, TEXT, task_status, task_priority, TIMESTAMPTZ, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION move_task(UUID, UUID, UUID, INTEGER[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_subtasks(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_task_descendants(UUID, UUID[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_subtask_progress(UUID, UUID[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_tasks_count(UUID, task_status, task_priority, UUID, TEXT, BOOLEAN) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER, BOOLEAN) TO todo_app_user;
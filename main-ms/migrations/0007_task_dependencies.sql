@@ -0,0 +1,295 @@
+-- Dependencias entre tareas: task_id está bloqueada por blocked_by_task_id hasta que esta
+-- se complete. Las dos tareas son del mismo usuario (claves compuestas) y al borrar
+-- cualquiera de ellas desaparece la dependencia.
+CREATE TABLE IF NOT EXISTS task_dependencies (
+    task_id UUID NOT NULL,
+    blocked_by_task_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (task_id, blocked_by_task_id),
+    CONSTRAINT task_dependencies_task_fkey
+        FOREIGN KEY (task_id, user_id) REFERENCES tasks (id, user_id) ON DELETE CASCADE,
+    CONSTRAINT task_dependencies_blocker_fkey
+        FOREIGN KEY (blocked_by_task_id, user_id) REFERENCES tasks (id, user_id) ON DELETE CASCADE,
+    CONSTRAINT task_dependencies_not_self CHECK (task_id <> blocked_by_task_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_dependencies_blocked_by ON task_dependencies (blocked_by_task_id);
+
+ALTER TABLE task_dependencies ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS task_dependencies_policy ON task_dependencies;
+CREATE POLICY task_dependencies_policy ON task_dependencies
+    USING (user_id = app_current_user_id());
+
+GRANT SELECT, INSERT, DELETE ON TABLE task_dependencies TO todo_app_user;
+
+-- Una dependencia nueva no puede cerrar un ciclo: se rechaza si la tarea bloqueante ya
+-- depende, directa o indirectamente, de la tarea bloqueada. El lock consultivo por usuario
+-- evita que dos inserciones simultáneas formen un ciclo entre ambas.
+CREATE OR REPLACE FUNCTION check_task_dependency_cycle()
+RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_advisory_xact_lock(hashtextextended('task_dependencies:' || NEW.user_id::TEXT, 0));
+
+    IF EXISTS (
+        WITH RECURSIVE blockers AS (
+            SELECT d.blocked_by_task_id AS id
+            FROM task_dependencies d
+            WHERE d.task_id = NEW.blocked_by_task_id
+            UNION
+            SELECT d.blocked_by_task_id
+            FROM task_dependencies d
+            JOIN blockers b ON d.task_id = b.id
+        )
+        SELECT 1 FROM blockers WHERE id = NEW.task_id
+    ) THEN
+        RAISE EXCEPTION 'Dependency would create a cycle'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'task_dependencies_cycle';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS check_task_dependencies_cycle ON task_dependencies;
+CREATE TRIGGER check_task_dependencies_cycle
+    BEFORE INSERT ON task_dependencies
+    FOR EACH ROW
+    EXECUTE FUNCTION check_task_dependency_cycle();
+
+-- Una tarea está bloqueada si alguna de sus bloqueantes no está completada
+CREATE OR REPLACE FUNCTION task_is_blocked(p_task_id UUID)
+RETURNS BOOLEAN AS $$
+    SELECT EXISTS (
+        SELECT 1
+        FROM task_dependencies d
+        JOIN tasks b ON b.id = d.blocked_by_task_id
+        WHERE d.task_id = p_task_id AND b.status <> 'done'
+    );
+$$ LANGUAGE sql STABLE;
+
+-- Pasar a doing o done una tarea bloqueada depende de app.blocked_status_change, que
+-- main-ms fija en cada transacción (tasks.blocked_status_change en su configuración):
+--   reject: se rechaza (por defecto, también si no está fijado)
+--   warn:   se permite; main-ms avisa en la respuesta
+CREATE OR REPLACE FUNCTION check_unfinished_blockers()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF current_setting('app.blocked_status_change', true) = 'warn' THEN
+        RETURN NEW;
+    END IF;
+
+    IF task_is_blocked(NEW.id) THEN
+        RAISE EXCEPTION 'Task is blocked by unfinished tasks'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'tasks_unfinished_blockers';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS check_tasks_unfinished_blockers ON tasks;
+CREATE TRIGGER check_tasks_unfinished_blockers
+    BEFORE UPDATE OF status ON tasks
+    FOR EACH ROW
+    WHEN (NEW.status IN ('doing', 'done') AND OLD.status IS DISTINCT FROM NEW.status)
+    EXECUTE FUNCTION check_unfinished_blockers();
+
+-- Devuelve TRUE si se creó la dependencia y FALSE si ya existía. Si alguna de las tareas
+-- no existe falla la clave foránea.
+DROP FUNCTION IF EXISTS add_task_dependency(UUID, UUID, UUID);
+CREATE FUNCTION add_task_dependency(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_blocked_by_task_id UUID
+)
+RETURNS BOOLEAN AS $$
+BEGIN
+    INSERT INTO task_dependencies (task_id, blocked_by_task_id, user_id)
+    VALUES (p_task_id, p_blocked_by_task_id, p_user_id)
+    ON CONFLICT (task_id, blocked_by_task_id) DO NOTHING;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS remove_task_dependency(UUID, UUID, UUID);
+CREATE FUNCTION remove_task_dependency(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_blocked_by_task_id UUID
+)
+RETURNS BOOLEAN AS $$
+BEGIN
+    DELETE FROM task_dependencies
+    WHERE task_id = p_task_id
+      AND blocked_by_task_id = p_blocked_by_task_id
+      AND user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Tareas que bloquean a p_task_id
+DROP FUNCTION IF EXISTS get_task_blockers(UUID, UUID);
+CREATE FUNCTION get_task_blockers(
+    p_user_id UUID,
+    p_task_id UUID
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.*
+    FROM tasks t
+    JOIN task_dependencies d ON d.blocked_by_task_id = t.id
+    WHERE d.task_id = p_task_id AND d.user_id = p_user_id
+    ORDER BY d.created_at, t.id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Tareas bloqueadas por p_task_id
+DROP FUNCTION IF EXISTS get_blocked_tasks(UUID, UUID);
+CREATE FUNCTION get_blocked_tasks(
+    p_user_id UUID,
+    p_task_id UUID
+)
+RETURNS SETOF tasks AS $$
+BEGIN
+    RETURN QUERY
+    SELECT t.*
+    FROM tasks t
+    JOIN task_dependencies d ON d.task_id = t.id
+    WHERE d.blocked_by_task_id = p_task_id AND d.user_id = p_user_id
+    ORDER BY d.created_at, t.id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Filtro por tareas bloqueadas (p_blocked); NULL no filtra
+DROP FUNCTION IF EXISTS get_user_tasks_count(UUID, task_status, task_priority, UUID, TEXT, BOOLEAN);
+CREATE FUNCTION get_user_tasks_count(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_top_level_only BOOLEAN DEFAULT FALSE,
+    p_blocked BOOLEAN DEFAULT NULL
+)
+RETURNS TABLE(count BIGINT)
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    RETURN QUERY
+    SELECT COUNT(*)::BIGINT
+    FROM tasks t
+    WHERE t.user_id = p_user_id
+      AND (p_status IS NULL OR t.status = p_status)
+      AND (p_priority IS NULL OR t.priority = p_priority)
+      AND (p_project_id IS NULL OR t.project_id = p_project_id)
+      AND (p_search IS NULL OR
+           t.title ILIKE '%' || p_search || '%' OR
+           t.description ILIKE '%' || p_search || '%')
+      AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+      AND (p_blocked IS NULL OR task_is_blocked(t.id) = p_blocked);
+END;
+$$;
+
+DROP FUNCTION IF EXISTS get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER, BOOLEAN);
+CREATE FUNCTION get_user_tasks_paginated(
+    p_user_id UUID,
+    p_status task_status DEFAULT NULL,
+    p_priority task_priority DEFAULT NULL,
+    p_project_id UUID DEFAULT NULL,
+    p_search TEXT DEFAULT NULL,
+    p_sort_by TEXT DEFAULT 'created_at',
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0,
+    p_top_level_only BOOLEAN DEFAULT FALSE,
+    p_blocked BOOLEAN DEFAULT NULL
+)
+RETURNS SETOF tasks
+LANGUAGE plpgsql
+SECURITY INVOKER
+AS $$
+BEGIN
+    IF p_sort_by = 'title' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+          AND (p_blocked IS NULL OR task_is_blocked(t.id) = p_blocked)
+        ORDER BY t.title, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSIF p_sort_by = 'due_date' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+          AND (p_blocked IS NULL OR task_is_blocked(t.id) = p_blocked)
+        ORDER BY t.due_date NULLS LAST, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSIF p_sort_by = 'priority' THEN
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+          AND (p_blocked IS NULL OR task_is_blocked(t.id) = p_blocked)
+        -- El enum está declarado de menor a mayor prioridad (low, med, high, urgent)
+        ORDER BY t.priority DESC, t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+
+    ELSE
+        RETURN QUERY
+        SELECT t.*
+        FROM tasks t
+        WHERE t.user_id = p_user_id
+          AND (p_status IS NULL OR t.status = p_status)
+          AND (p_priority IS NULL OR t.priority = p_priority)
+          AND (p_project_id IS NULL OR t.project_id = p_project_id)
+          AND (p_search IS NULL OR
+               t.title ILIKE '%' || p_search || '%' OR
+               t.description ILIKE '%' || p_search || '%')
+          AND (NOT p_top_level_only OR t.parent_task_id IS NULL)
+          AND (p_blocked IS NULL OR task_is_blocked(t.id) = p_blocked)
+        ORDER BY t.created_at DESC
+        LIMIT p_limit
+        OFFSET p_offset;
+    END IF;
+END;
+$$;
+
+GRANT EXECUTE ON FUNCTION task_is_blocked(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION add_task_dependency(UUID, UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION remove_task_dependency(UUID, UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_task_blockers(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_blocked_tasks(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_tasks_count(UUID, task_status, task_priority, UUID, TEXT, BOOLEAN, BOOLEAN) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_tasks_paginated(UUID, task_status, task_priority, UUID, TEXT, TEXT, INTEGER, INTEGER, BOOLEAN, BOOLEAN) TO todo_app_user;
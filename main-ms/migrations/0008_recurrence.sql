@@ -0,0 +1,171 @@
+-- Tareas recurrentes: recurrence_rule guarda una regla RRULE (RFC 5545) que main-ms valida
+-- y normaliza. Al completar una tarea recurrente main-ms crea la siguiente repetición, que
+-- apunta a la completada con recurrence_source_id.
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS recurrence_rule TEXT;
+ALTER TABLE tasks ADD COLUMN IF NOT EXISTS recurrence_source_id UUID;
+
+-- Si se borra la repetición anterior, la siguiente se conserva
+ALTER TABLE tasks
+    ADD CONSTRAINT tasks_recurrence_source_fkey
+    FOREIGN KEY (recurrence_source_id, user_id) REFERENCES tasks (id, user_id)
+    ON DELETE SET NULL (recurrence_source_id);
+
+-- Cada tarea genera como mucho una repetición, aunque se complete varias veces
+CREATE UNIQUE INDEX IF NOT EXISTS tasks_recurrence_source_key ON tasks (recurrence_source_id);
+
+DROP FUNCTION IF EXISTS create_task(UUID, VARCHAR, UUID, TEXT, task_status, task_priority, TIMESTAMPTZ, UUID);
+CREATE FUNCTION create_task(
+    p_user_id UUID,
+    p_title VARCHAR,
+    p_project_id UUID DEFAULT NULL,
+    p_description TEXT DEFAULT NULL,
+    p_status task_status DEFAULT 'todo',
+    p_priority task_priority DEFAULT 'med',
+    p_due_date TIMESTAMPTZ DEFAULT NULL,
+    p_parent_task_id UUID DEFAULT NULL,
+    p_recurrence_rule TEXT DEFAULT NULL
+)
+RETURNS UUID AS $$
+DECLARE
+    v_project_id UUID := p_project_id;
+    new_id UUID;
+BEGIN
+    IF v_project_id IS NULL AND p_parent_task_id IS NOT NULL THEN
+        SELECT t.project_id INTO v_project_id
+        FROM tasks t
+        WHERE t.id = p_parent_task_id AND t.user_id = p_user_id;
+    END IF;
+
+    INSERT INTO tasks (user_id, project_id, title, description, status, priority, due_date, parent_task_id, recurrence_rule)
+    VALUES (p_user_id, v_project_id, p_title, p_description, p_status, p_priority, p_due_date, p_parent_task_id, p_recurrence_rule)
+    RETURNING id INTO new_id;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- PUT también reemplaza la regla: omitirla deja de repetir la tarea
+DROP FUNCTION IF EXISTS update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ, INTEGER[]);
+CREATE FUNCTION update_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_project_id UUID,
+    p_title VARCHAR,
+    p_description TEXT,
+    p_status task_status,
+    p_priority task_priority,
+    p_due_date TIMESTAMPTZ,
+    p_recurrence_rule TEXT DEFAULT NULL,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE tasks t
+    SET
+        project_id = p_project_id,
+        title = p_title,
+        description = p_description,
+        status = p_status,
+        priority = p_priority,
+        due_date = p_due_date,
+        recurrence_rule = p_recurrence_rule
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    RETURNING t.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+CREATE OR REPLACE FUNCTION patch_task(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_patch JSONB,
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE(updated BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    UPDATE tasks t
+    SET
+        project_id = CASE WHEN p_patch ? 'project_id' THEN (p_patch->>'project_id')::UUID ELSE t.project_id END,
+        title = CASE WHEN p_patch ? 'title' THEN p_patch->>'title' ELSE t.title END,
+        description = CASE WHEN p_patch ? 'description' THEN p_patch->>'description' ELSE t.description END,
+        status = CASE WHEN p_patch ? 'status' THEN (p_patch->>'status')::task_status ELSE t.status END,
+        priority = CASE WHEN p_patch ? 'priority' THEN (p_patch->>'priority')::task_priority ELSE t.priority END,
+        due_date = CASE WHEN p_patch ? 'due_date' THEN (p_patch->>'due_date')::TIMESTAMPTZ ELSE t.due_date END,
+        recurrence_rule = CASE WHEN p_patch ? 'recurrence_rule' THEN p_patch->>'recurrence_rule' ELSE t.recurrence_rule END
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    RETURNING t.version INTO v_version;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Crea la repetición siguiente a p_task_id, con la fecha y la regla que calcula main-ms
+-- (COUNT descuenta la repetición completada). Copia proyecto, tarea padre, título,
+-- descripción, prioridad y etiquetas. Devuelve NULL si esa tarea ya generó su repetición.
+DROP FUNCTION IF EXISTS create_next_occurrence(UUID, UUID, TIMESTAMPTZ, TEXT);
+CREATE FUNCTION create_next_occurrence(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_due_date TIMESTAMPTZ,
+    p_recurrence_rule TEXT
+)
+RETURNS UUID AS $$
+DECLARE
+    new_id UUID;
+BEGIN
+    INSERT INTO tasks (user_id, project_id, parent_task_id, title, description, status, priority,
+                       due_date, recurrence_rule, recurrence_source_id)
+    SELECT t.user_id, t.project_id, t.parent_task_id, t.title, t.description, 'todo', t.priority,
+           p_due_date, p_recurrence_rule, t.id
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    ON CONFLICT (recurrence_source_id) DO NOTHING
+    RETURNING id INTO new_id;
+
+    IF new_id IS NOT NULL THEN
+        INSERT INTO task_tags (task_id, tag_id)
+        SELECT new_id, tt.tag_id
+        FROM task_tags tt
+        WHERE tt.task_id = p_task_id;
+    END IF;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION create_task(UUID, VARCHAR, UUID, TEXT, task_status, task_priority, TIMESTAMPTZ, UUID, TEXT) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION update_task(UUID, UUID, UUID, VARCHAR, TEXT, task_status, task_priority, TIMESTAMPTZ, TEXT, INTEGER[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION create_next_occurrence(UUID, UUID, TIMESTAMPTZ, TEXT) TO todo_app_user;
@@ -0,0 +1,335 @@
+-- Recordatorios de tareas. Cada uno vence en un momento fijo (remind_at) o unos minutos
+-- antes de la fecha límite de la tarea (offset_minutes); fire_at es el momento efectivo y
+-- se recalcula cuando cambia la fecha límite. El planificador de main-ms los dispara por
+-- los canales indicados y deja el resultado en la fila, así que sobreviven a reinicios.
+DO $$
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM pg_type WHERE typname = 'reminder_status') THEN
+        CREATE TYPE reminder_status AS ENUM ('pending', 'fired', 'missed', 'failed', 'cancelled');
+    END IF;
+END$$;
+
+GRANT USAGE ON TYPE reminder_status TO todo_app_user;
+
+-- Notificaciones dentro de la aplicación (el canal in_app de los recordatorios)
+CREATE TABLE IF NOT EXISTS notifications (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    user_id UUID NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    title VARCHAR(255) NOT NULL,
+    body TEXT,
+    task_id UUID,
+    read_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- La notificación se conserva aunque se borre la tarea
+    CONSTRAINT notifications_task_fkey
+        FOREIGN KEY (task_id, user_id) REFERENCES tasks (id, user_id) ON DELETE SET NULL (task_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_notifications_user_id ON notifications (user_id, created_at);
+
+ALTER TABLE notifications ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS notifications_policy ON notifications;
+CREATE POLICY notifications_policy ON notifications
+    USING (user_id = app_current_user_id());
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON TABLE notifications TO todo_app_user;
+
+CREATE TABLE IF NOT EXISTS task_reminders (
+    id UUID DEFAULT gen_random_uuid() PRIMARY KEY,
+    task_id UUID NOT NULL,
+    user_id UUID NOT NULL,
+    remind_at TIMESTAMP WITH TIME ZONE,
+    offset_minutes INTEGER,
+    -- NULL si depende de una fecha límite que la tarea no tiene
+    fire_at TIMESTAMP WITH TIME ZONE,
+    channels TEXT[] NOT NULL DEFAULT '{in_app}',
+    -- Destinatario del canal email: el email de la cuenta al crear el recordatorio
+    email VARCHAR(255),
+    status reminder_status NOT NULL DEFAULT 'pending',
+    -- Canales ya entregados; un reintento solo usa los que faltan
+    delivered_channels TEXT[] NOT NULL DEFAULT '{}',
+    attempts INTEGER NOT NULL DEFAULT 0,
+    last_error TEXT,
+    -- Mientras no venza, el recordatorio está tomado por un planificador o esperando reintento
+    locked_until TIMESTAMP WITH TIME ZONE,
+    fired_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT task_reminders_task_fkey
+        FOREIGN KEY (task_id, user_id) REFERENCES tasks (id, user_id) ON DELETE CASCADE,
+    CONSTRAINT task_reminders_time_check CHECK ((remind_at IS NULL) <> (offset_minutes IS NULL)),
+    CONSTRAINT task_reminders_offset_check CHECK (offset_minutes >= 0),
+    CONSTRAINT task_reminders_channels_check
+        CHECK (cardinality(channels) > 0 AND channels <@ ARRAY['in_app', 'webhook', 'email']),
+    CONSTRAINT task_reminders_email_check CHECK (NOT ('email' = ANY(channels)) OR email IS NOT NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_task_reminders_task_id ON task_reminders (task_id);
+CREATE INDEX IF NOT EXISTS idx_task_reminders_pending ON task_reminders (fire_at) WHERE status = 'pending';
+
+ALTER TABLE task_reminders ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS task_reminders_policy ON task_reminders;
+CREATE POLICY task_reminders_policy ON task_reminders
+    USING (user_id = app_current_user_id());
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON TABLE task_reminders TO todo_app_user;
+
+-- fire_at de un recordatorio nuevo o editado
+CREATE OR REPLACE FUNCTION set_reminder_fire_at()
+RETURNS TRIGGER AS $$
+BEGIN
+    IF NEW.remind_at IS NOT NULL THEN
+        NEW.fire_at := NEW.remind_at;
+    ELSE
+        SELECT t.due_date - make_interval(mins => NEW.offset_minutes) INTO NEW.fire_at
+        FROM tasks t
+        WHERE t.id = NEW.task_id AND t.user_id = NEW.user_id;
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP TRIGGER IF EXISTS set_reminder_fire_at ON task_reminders;
+CREATE TRIGGER set_reminder_fire_at
+    BEFORE INSERT OR UPDATE OF remind_at, offset_minutes ON task_reminders
+    FOR EACH ROW EXECUTE FUNCTION set_reminder_fire_at();
+
+-- Los recordatorios relativos siguen a la fecha límite. Si la fecha se mueve al futuro, los
+-- que ya se dispararon (o se perdieron) vuelven a quedar pendientes.
+CREATE OR REPLACE FUNCTION reschedule_task_reminders()
+RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE task_reminders r
+    SET fire_at = NEW.due_date - make_interval(mins => r.offset_minutes)
+    WHERE r.task_id = NEW.id AND r.user_id = NEW.user_id AND r.offset_minutes IS NOT NULL;
+
+    UPDATE task_reminders r
+    SET status = 'pending', delivered_channels = '{}', attempts = 0, last_error = NULL,
+        locked_until = NULL, fired_at = NULL
+    WHERE r.task_id = NEW.id AND r.user_id = NEW.user_id AND r.offset_minutes IS NOT NULL
+      AND r.status <> 'pending' AND r.fire_at > now();
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP TRIGGER IF EXISTS reschedule_task_reminders ON tasks;
+CREATE TRIGGER reschedule_task_reminders
+    AFTER UPDATE OF due_date ON tasks
+    FOR EACH ROW
+    WHEN (OLD.due_date IS DISTINCT FROM NEW.due_date)
+    EXECUTE FUNCTION reschedule_task_reminders();
+
+DROP FUNCTION IF EXISTS create_task_reminder(UUID, UUID, TIMESTAMPTZ, INTEGER, TEXT[], VARCHAR);
+CREATE FUNCTION create_task_reminder(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_remind_at TIMESTAMPTZ,
+    p_offset_minutes INTEGER,
+    p_channels TEXT[],
+    p_email VARCHAR DEFAULT NULL
+)
+RETURNS UUID AS $$
+DECLARE
+    new_id UUID;
+BEGIN
+    INSERT INTO task_reminders (task_id, user_id, remind_at, offset_minutes, channels, email)
+    VALUES (p_task_id, p_user_id, p_remind_at, p_offset_minutes, p_channels, p_email)
+    RETURNING id INTO new_id;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_task_reminders(UUID, UUID);
+CREATE FUNCTION get_task_reminders(p_user_id UUID, p_task_id UUID)
+RETURNS SETOF task_reminders AS $$
+    SELECT r.*
+    FROM task_reminders r
+    WHERE r.task_id = p_task_id AND r.user_id = p_user_id
+    ORDER BY r.fire_at NULLS LAST, r.created_at;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_task_reminder(UUID, UUID);
+CREATE FUNCTION get_task_reminder(p_user_id UUID, p_reminder_id UUID)
+RETURNS SETOF task_reminders AS $$
+    SELECT r.*
+    FROM task_reminders r
+    WHERE r.id = p_reminder_id AND r.user_id = p_user_id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- Recordatorios de todas las tareas del usuario, los más recientes primero
+DROP FUNCTION IF EXISTS get_user_reminders(UUID, reminder_status, UUID, INTEGER, INTEGER);
+CREATE FUNCTION get_user_reminders(
+    p_user_id UUID,
+    p_status reminder_status DEFAULT NULL,
+    p_task_id UUID DEFAULT NULL,
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0
+)
+RETURNS SETOF task_reminders AS $$
+    SELECT r.*
+    FROM task_reminders r
+    WHERE r.user_id = p_user_id
+      AND (p_status IS NULL OR r.status = p_status)
+      AND (p_task_id IS NULL OR r.task_id = p_task_id)
+    ORDER BY r.fire_at DESC NULLS LAST, r.created_at DESC
+    LIMIT p_limit OFFSET p_offset;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_user_reminders_count(UUID, reminder_status, UUID);
+CREATE FUNCTION get_user_reminders_count(
+    p_user_id UUID,
+    p_status reminder_status DEFAULT NULL,
+    p_task_id UUID DEFAULT NULL
+)
+RETURNS TABLE(count BIGINT) AS $$
+    SELECT COUNT(*)
+    FROM task_reminders r
+    WHERE r.user_id = p_user_id
+      AND (p_status IS NULL OR r.status = p_status)
+      AND (p_task_id IS NULL OR r.task_id = p_task_id);
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS delete_task_reminder(UUID, UUID);
+CREATE FUNCTION delete_task_reminder(p_user_id UUID, p_reminder_id UUID)
+RETURNS BOOLEAN AS $$
+BEGIN
+    DELETE FROM task_reminders r
+    WHERE r.id = p_reminder_id AND r.user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS create_notification(UUID, VARCHAR, VARCHAR, TEXT, UUID);
+CREATE FUNCTION create_notification(
+    p_user_id UUID,
+    p_event_type VARCHAR,
+    p_title VARCHAR,
+    p_body TEXT DEFAULT NULL,
+    p_task_id UUID DEFAULT NULL
+)
+RETURNS UUID AS $$
+DECLARE
+    new_id UUID;
+BEGIN
+    INSERT INTO notifications (user_id, event_type, title, body, task_id)
+    VALUES (p_user_id, p_event_type, p_title, p_body, p_task_id)
+    RETURNING id INTO new_id;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Toma hasta p_limit recordatorios vencidos de cualquier usuario para dispararlos, y marca
+-- como perdidos los que vencieron hace más de p_missed_after sin llegar a intentarse (el
+-- servicio estuvo detenido). Es la única función SECURITY DEFINER: el planificador no actúa
+-- en nombre de un usuario, así que solo recibe los ids y el resto lo hace con as_user y RLS.
+-- SKIP LOCKED y locked_until evitan que dos réplicas tomen el mismo recordatorio; si el
+-- proceso muere a mitad de la entrega, el lease vence y otro lo reintenta.
+DROP FUNCTION IF EXISTS claim_due_reminders(INTEGER, INTERVAL, INTERVAL);
+CREATE FUNCTION claim_due_reminders(p_limit INTEGER, p_lease INTERVAL, p_missed_after INTERVAL)
+RETURNS TABLE(reminder_id UUID, user_id UUID) AS $$
+BEGIN
+    UPDATE task_reminders r
+    SET status = 'missed', locked_until = NULL
+    WHERE r.status = 'pending'
+      AND r.attempts = 0
+      AND r.fire_at < now() - p_missed_after
+      AND (r.locked_until IS NULL OR r.locked_until <= now());
+
+    RETURN QUERY
+    WITH due AS (
+        SELECT r.id
+        FROM task_reminders r
+        WHERE r.status = 'pending'
+          AND r.fire_at <= now()
+          AND (r.locked_until IS NULL OR r.locked_until <= now())
+        ORDER BY r.fire_at
+        LIMIT p_limit
+        FOR UPDATE SKIP LOCKED
+    )
+    UPDATE task_reminders r
+    SET locked_until = now() + p_lease, attempts = r.attempts + 1
+    FROM due
+    WHERE r.id = due.id
+    RETURNING r.id, r.user_id;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = public;
+
+REVOKE EXECUTE ON FUNCTION claim_due_reminders(INTEGER, INTERVAL, INTERVAL) FROM PUBLIC;
+
+-- Resultado de una entrega: p_status 'pending' con p_retry_at programa un reintento
+DROP FUNCTION IF EXISTS record_reminder_delivery(UUID, UUID, reminder_status, TEXT[], TEXT, TIMESTAMPTZ);
+CREATE FUNCTION record_reminder_delivery(
+    p_user_id UUID,
+    p_reminder_id UUID,
+    p_status reminder_status,
+    p_delivered_channels TEXT[],
+    p_error TEXT DEFAULT NULL,
+    p_retry_at TIMESTAMPTZ DEFAULT NULL
+)
+RETURNS BOOLEAN AS $$
+BEGIN
+    UPDATE task_reminders r
+    SET
+        status = p_status,
+        delivered_channels = p_delivered_channels,
+        last_error = p_error,
+        locked_until = p_retry_at,
+        fired_at = CASE WHEN p_status = 'fired' THEN now() ELSE r.fired_at END
+    WHERE r.id = p_reminder_id AND r.user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- La repetición siguiente de una tarea recurrente conserva sus recordatorios relativos
+CREATE OR REPLACE FUNCTION create_next_occurrence(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_due_date TIMESTAMPTZ,
+    p_recurrence_rule TEXT
+)
+RETURNS UUID AS $$
+DECLARE
+    new_id UUID;
+BEGIN
+    INSERT INTO tasks (user_id, project_id, parent_task_id, title, description, status, priority,
+                       due_date, recurrence_rule, recurrence_source_id)
+    SELECT t.user_id, t.project_id, t.parent_task_id, t.title, t.description, 'todo', t.priority,
+           p_due_date, p_recurrence_rule, t.id
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    ON CONFLICT (recurrence_source_id) DO NOTHING
+    RETURNING id INTO new_id;
+
+    IF new_id IS NOT NULL THEN
+        INSERT INTO task_tags (task_id, tag_id)
+        SELECT new_id, tt.tag_id
+        FROM task_tags tt
+        WHERE tt.task_id = p_task_id;
+
+        INSERT INTO task_reminders (task_id, user_id, offset_minutes, channels, email)
+        SELECT new_id, r.user_id, r.offset_minutes, r.channels, r.email
+        FROM task_reminders r
+        WHERE r.task_id = p_task_id AND r.user_id = p_user_id AND r.offset_minutes IS NOT NULL;
+    END IF;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION create_task_reminder(UUID, UUID, TIMESTAMPTZ, INTEGER, TEXT[], VARCHAR) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_task_reminders(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_task_reminder(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_reminders(UUID, reminder_status, UUID, INTEGER, INTEGER) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_reminders_count(UUID, reminder_status, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION delete_task_reminder(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION create_notification(UUID, VARCHAR, VARCHAR, TEXT, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION claim_due_reminders(INTEGER, INTERVAL, INTERVAL) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION record_reminder_delivery(UUID, UUID, reminder_status, TEXT[], TEXT, TIMESTAMPTZ) TO todo_app_user;
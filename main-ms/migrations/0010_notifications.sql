@@ -0,0 +1,190 @@
+-- Bandeja de notificaciones. Cada notificación tiene un tipo de evento; el usuario puede
+-- desactivar tipos en notification_preferences (sin fila, el tipo está activo) y
+-- create_notification no crea las de tipos desactivados.
+ALTER TABLE notifications
+    ADD CONSTRAINT notifications_event_type_check CHECK (event_type IN ('reminder', 'task_overdue'));
+
+-- Para listar primero las no leídas
+CREATE INDEX IF NOT EXISTS idx_notifications_unread ON notifications (user_id, created_at) WHERE read_at IS NULL;
+
+CREATE TABLE IF NOT EXISTS notification_preferences (
+    user_id UUID NOT NULL,
+    event_type VARCHAR(50) NOT NULL,
+    enabled BOOLEAN NOT NULL,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, event_type),
+    CONSTRAINT notification_preferences_event_type_check CHECK (event_type IN ('reminder', 'task_overdue'))
+);
+
+ALTER TABLE notification_preferences ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS notification_preferences_policy ON notification_preferences;
+CREATE POLICY notification_preferences_policy ON notification_preferences
+    USING (user_id = app_current_user_id());
+
+GRANT SELECT, INSERT, UPDATE, DELETE ON TABLE notification_preferences TO todo_app_user;
+
+-- Tareas vencidas ya notificadas, con la fecha límite que tenían. Si la fecha cambia y la
+-- tarea vuelve a vencer, se notifica de nuevo.
+CREATE TABLE IF NOT EXISTS task_overdue_notices (
+    task_id UUID PRIMARY KEY,
+    user_id UUID NOT NULL,
+    due_date TIMESTAMP WITH TIME ZONE NOT NULL,
+    notified_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT task_overdue_notices_task_fkey
+        FOREIGN KEY (task_id, user_id) REFERENCES tasks (id, user_id) ON DELETE CASCADE
+);
+
+ALTER TABLE task_overdue_notices ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS task_overdue_notices_policy ON task_overdue_notices;
+CREATE POLICY task_overdue_notices_policy ON task_overdue_notices
+    USING (user_id = app_current_user_id());
+
+GRANT SELECT ON TABLE task_overdue_notices TO todo_app_user;
+
+-- Devuelve NULL (sin crear nada) si el usuario desactivó ese tipo de evento
+CREATE OR REPLACE FUNCTION create_notification(
+    p_user_id UUID,
+    p_event_type VARCHAR,
+    p_title VARCHAR,
+    p_body TEXT DEFAULT NULL,
+    p_task_id UUID DEFAULT NULL
+)
+RETURNS UUID AS $$
+DECLARE
+    new_id UUID;
+BEGIN
+    IF EXISTS (
+        SELECT 1 FROM notification_preferences p
+        WHERE p.user_id = p_user_id AND p.event_type = p_event_type AND NOT p.enabled
+    ) THEN
+        RETURN NULL;
+    END IF;
+
+    INSERT INTO notifications (user_id, event_type, title, body, task_id)
+    VALUES (p_user_id, p_event_type, p_title, p_body, p_task_id)
+    RETURNING id INTO new_id;
+
+    RETURN new_id;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- No leídas primero; dentro de cada grupo, las más recientes primero
+DROP FUNCTION IF EXISTS get_user_notifications(UUID, BOOLEAN, INTEGER, INTEGER);
+CREATE FUNCTION get_user_notifications(
+    p_user_id UUID,
+    p_unread_only BOOLEAN DEFAULT FALSE,
+    p_limit INTEGER DEFAULT 10,
+    p_offset INTEGER DEFAULT 0
+)
+RETURNS SETOF notifications AS $$
+    SELECT n.*
+    FROM notifications n
+    WHERE n.user_id = p_user_id
+      AND (NOT p_unread_only OR n.read_at IS NULL)
+    ORDER BY n.read_at IS NULL DESC, n.created_at DESC, n.id
+    LIMIT p_limit OFFSET p_offset;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS get_user_notifications_count(UUID);
+CREATE FUNCTION get_user_notifications_count(p_user_id UUID)
+RETURNS TABLE(total_count BIGINT, unread_count BIGINT) AS $$
+    SELECT COUNT(*), COUNT(*) FILTER (WHERE n.read_at IS NULL)
+    FROM notifications n
+    WHERE n.user_id = p_user_id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- Una notificación ya leída conserva su read_at
+DROP FUNCTION IF EXISTS mark_notification_read(UUID, UUID);
+CREATE FUNCTION mark_notification_read(p_user_id UUID, p_notification_id UUID)
+RETURNS BOOLEAN AS $$
+BEGIN
+    UPDATE notifications n
+    SET read_at = COALESCE(n.read_at, now())
+    WHERE n.id = p_notification_id AND n.user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS mark_all_notifications_read(UUID);
+CREATE FUNCTION mark_all_notifications_read(p_user_id UUID)
+RETURNS INTEGER AS $$
+DECLARE
+    updated_count INTEGER;
+BEGIN
+    UPDATE notifications n
+    SET read_at = now()
+    WHERE n.user_id = p_user_id AND n.read_at IS NULL;
+
+    GET DIAGNOSTICS updated_count = ROW_COUNT;
+    RETURN updated_count;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS delete_notification(UUID, UUID);
+CREATE FUNCTION delete_notification(p_user_id UUID, p_notification_id UUID)
+RETURNS BOOLEAN AS $$
+BEGIN
+    DELETE FROM notifications n
+    WHERE n.id = p_notification_id AND n.user_id = p_user_id;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- Solo los tipos con preferencia guardada; los demás están activos
+DROP FUNCTION IF EXISTS get_notification_preferences(UUID);
+CREATE FUNCTION get_notification_preferences(p_user_id UUID)
+RETURNS TABLE(event_type VARCHAR, enabled BOOLEAN) AS $$
+    SELECT p.event_type, p.enabled
+    FROM notification_preferences p
+    WHERE p.user_id = p_user_id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+DROP FUNCTION IF EXISTS set_notification_preference(UUID, VARCHAR, BOOLEAN);
+CREATE FUNCTION set_notification_preference(p_user_id UUID, p_event_type VARCHAR, p_enabled BOOLEAN)
+RETURNS VOID AS $$
+    INSERT INTO notification_preferences (user_id, event_type, enabled)
+    VALUES (p_user_id, p_event_type, p_enabled)
+    ON CONFLICT (user_id, event_type)
+    DO UPDATE SET enabled = EXCLUDED.enabled, updated_at = now();
+$$ LANGUAGE sql SECURITY INVOKER;
+
+-- Toma hasta p_limit tareas que vencieron en los últimos p_window sin completarse y que aún
+-- no se notificaron con su fecha límite actual (las más antiguas no se notifican: el
+-- servicio estuvo detenido, o son anteriores a esta función). Como claim_due_reminders, es
+-- SECURITY DEFINER y solo devuelve ids; si dos réplicas toman la misma tarea, el WHERE del
+-- ON CONFLICT deja que solo una la reciba.
+DROP FUNCTION IF EXISTS claim_overdue_tasks(INTEGER, INTERVAL);
+CREATE FUNCTION claim_overdue_tasks(p_limit INTEGER, p_window INTERVAL)
+RETURNS TABLE(task_id UUID, user_id UUID) AS $$
+    INSERT INTO task_overdue_notices AS n (task_id, user_id, due_date)
+    SELECT t.id, t.user_id, t.due_date
+    FROM tasks t
+    WHERE t.due_date <= now()
+      AND t.due_date > now() - p_window
+      AND t.status <> 'done'
+      AND NOT EXISTS (
+          SELECT 1 FROM task_overdue_notices o
+          WHERE o.task_id = t.id AND o.due_date = t.due_date
+      )
+    ORDER BY t.due_date
+    LIMIT p_limit
+    ON CONFLICT ON CONSTRAINT task_overdue_notices_pkey
+    DO UPDATE SET due_date = EXCLUDED.due_date, notified_at = now()
+    WHERE n.due_date IS DISTINCT FROM EXCLUDED.due_date
+    RETURNING n.task_id, n.user_id;
+$$ LANGUAGE sql SECURITY DEFINER SET search_path = public;
+
+REVOKE EXECUTE ON FUNCTION claim_overdue_tasks(INTEGER, INTERVAL) FROM PUBLIC;
+
+GRANT EXECUTE ON FUNCTION get_user_notifications(UUID, BOOLEAN, INTEGER, INTEGER) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_user_notifications_count(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION mark_notification_read(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION mark_all_notifications_read(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION delete_notification(UUID, UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_notification_preferences(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION set_notification_preference(UUID, VARCHAR, BOOLEAN) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION claim_overdue_tasks(INTEGER, INTERVAL) TO todo_app_user;
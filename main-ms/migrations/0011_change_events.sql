@@ -0,0 +1,129 @@
+-- Registro de cambios de tareas, proyectos, etiquetas y vínculos tarea-etiqueta. Lo llenan
+-- triggers, así que captura también los cambios hechos directamente con las funciones
+-- almacenadas, y cada evento se publica con NOTIFY en el canal `change_events` para el
+-- stream SSE de main-ms. El id es el cursor de `Last-Event-ID`.
+CREATE TABLE IF NOT EXISTS change_events (
+    id BIGSERIAL PRIMARY KEY,
+    user_id UUID NOT NULL,
+    entity VARCHAR(20) NOT NULL,
+    action VARCHAR(10) NOT NULL,
+    -- En un vínculo tarea-etiqueta, la tarea; related_id es la etiqueta
+    entity_id UUID NOT NULL,
+    related_id UUID,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT change_events_entity_check CHECK (entity IN ('task', 'project', 'tag', 'task_tag')),
+    CONSTRAINT change_events_action_check CHECK (action IN ('created', 'updated', 'deleted'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_change_events_user_id ON change_events (user_id, id);
+
+ALTER TABLE change_events ENABLE ROW LEVEL SECURITY;
+
+DROP POLICY IF EXISTS change_events_policy ON change_events;
+CREATE POLICY change_events_policy ON change_events
+    USING (user_id = app_current_user_id());
+
+-- Solo lectura: los eventos los escriben los triggers
+GRANT SELECT ON TABLE change_events TO todo_app_user;
+
+-- SECURITY DEFINER para que todo_app_user no pueda escribir eventos por su cuenta, y para
+-- encontrar al dueño de un vínculo cuya tarea o etiqueta se está eliminando en cascada.
+-- El bloqueo por usuario hace que sus eventos se confirmen en el orden de sus ids: sin él,
+-- una transacción más lenta podría confirmar un id menor que otro ya enviado, y un cliente
+-- que retoma desde el mayor lo perdería.
+CREATE OR REPLACE FUNCTION record_change_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_row RECORD;
+    v_action VARCHAR;
+    v_user_id UUID;
+    v_entity_id UUID;
+    v_related_id UUID;
+    v_event change_events;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        v_row := OLD;
+        v_action := 'deleted';
+    ELSIF TG_OP = 'INSERT' THEN
+        v_row := NEW;
+        v_action := 'created';
+    ELSE
+        IF OLD IS NOT DISTINCT FROM NEW THEN
+            RETURN NULL;
+        END IF;
+        v_row := NEW;
+        v_action := 'updated';
+    END IF;
+
+    IF TG_ARGV[0] = 'task_tag' THEN
+        v_entity_id := v_row.task_id;
+        v_related_id := v_row.tag_id;
+        SELECT t.user_id INTO v_user_id FROM tasks t WHERE t.id = v_row.task_id;
+        IF v_user_id IS NULL THEN
+            SELECT g.user_id INTO v_user_id FROM tags g WHERE g.id = v_row.tag_id;
+        END IF;
+        -- Se eliminaron la tarea y la etiqueta a la vez: sus propios eventos bastan
+        IF v_user_id IS NULL THEN
+            RETURN NULL;
+        END IF;
+    ELSE
+        v_entity_id := v_row.id;
+        v_user_id := v_row.user_id;
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtextextended('change_events:' || v_user_id::TEXT, 0));
+
+    INSERT INTO change_events (user_id, entity, action, entity_id, related_id)
+    VALUES (v_user_id, TG_ARGV[0], v_action, v_entity_id, v_related_id)
+    RETURNING * INTO v_event;
+
+    PERFORM pg_notify('change_events', row_to_json(v_event)::TEXT);
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = public;
+
+REVOKE EXECUTE ON FUNCTION record_change_event() FROM PUBLIC;
+
+DROP TRIGGER IF EXISTS tasks_change_events ON tasks;
+CREATE TRIGGER tasks_change_events
+    AFTER INSERT OR UPDATE OR DELETE ON tasks
+    FOR EACH ROW EXECUTE FUNCTION record_change_event('task');
+
+DROP TRIGGER IF EXISTS projects_change_events ON projects;
+CREATE TRIGGER projects_change_events
+    AFTER INSERT OR UPDATE OR DELETE ON projects
+    FOR EACH ROW EXECUTE FUNCTION record_change_event('project');
+
+DROP TRIGGER IF EXISTS tags_change_events ON tags;
+CREATE TRIGGER tags_change_events
+    AFTER INSERT OR UPDATE OR DELETE ON tags
+    FOR EACH ROW EXECUTE FUNCTION record_change_event('tag');
+
+DROP TRIGGER IF EXISTS task_tags_change_events ON task_tags;
+CREATE TRIGGER task_tags_change_events
+    AFTER INSERT OR DELETE ON task_tags
+    FOR EACH ROW EXECUTE FUNCTION record_change_event('task_tag');
+
+-- Eventos posteriores a p_after_id, en orden
+DROP FUNCTION IF EXISTS get_change_events(UUID, BIGINT, INTEGER);
+CREATE FUNCTION get_change_events(p_user_id UUID, p_after_id BIGINT, p_limit INTEGER DEFAULT 500)
+RETURNS SETOF change_events AS $$
+    SELECT e.*
+    FROM change_events e
+    WHERE e.user_id = p_user_id AND e.id > p_after_id
+    ORDER BY e.id
+    LIMIT p_limit;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- Id del último evento del usuario (0 si no tiene)
+DROP FUNCTION IF EXISTS get_last_change_event_id(UUID);
+CREATE FUNCTION get_last_change_event_id(p_user_id UUID)
+RETURNS BIGINT AS $$
+    SELECT COALESCE(max(e.id), 0)
+    FROM change_events e
+    WHERE e.user_id = p_user_id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION get_change_events(UUID, BIGINT, INTEGER) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_last_change_event_id(UUID) TO todo_app_user;
@@ -0,0 +1,200 @@
+-- Sincronización incremental para clientes sin conexión. El cursor es el id de
+-- change_events (0011), que ya registra también las eliminaciones; aquí se agregan las
+-- consultas de sincronización, la limpieza de eventos antiguos y eliminaciones
+-- condicionadas por versión, como las actualizaciones.
+
+-- Hasta qué id se eliminaron eventos: un cursor menor pudo perder cambios
+CREATE TABLE IF NOT EXISTS change_events_horizon (
+    singleton BOOLEAN PRIMARY KEY DEFAULT TRUE,
+    pruned_through BIGINT NOT NULL DEFAULT 0,
+    pruned_at TIMESTAMP WITH TIME ZONE,
+    CONSTRAINT change_events_horizon_singleton CHECK (singleton)
+);
+
+INSERT INTO change_events_horizon (singleton) VALUES (TRUE) ON CONFLICT DO NOTHING;
+
+GRANT SELECT ON TABLE change_events_horizon TO todo_app_user;
+
+-- Elimina los eventos anteriores a p_retention y devuelve cuántos eliminó. Como
+-- claim_due_reminders, es SECURITY DEFINER: recorre los eventos de todos los usuarios.
+DROP FUNCTION IF EXISTS prune_change_events(INTERVAL);
+CREATE FUNCTION prune_change_events(p_retention INTERVAL)
+RETURNS INTEGER AS $$
+DECLARE
+    v_deleted INTEGER;
+    v_max_id BIGINT;
+BEGIN
+    WITH deleted AS (
+        DELETE FROM change_events e
+        WHERE e.occurred_at < now() - p_retention
+        RETURNING e.id
+    )
+    SELECT COUNT(*), max(id) INTO v_deleted, v_max_id FROM deleted;
+
+    IF v_deleted > 0 THEN
+        UPDATE change_events_horizon
+        SET pruned_through = GREATEST(pruned_through, v_max_id), pruned_at = now();
+    END IF;
+
+    RETURN v_deleted;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = public;
+
+REVOKE EXECUTE ON FUNCTION prune_change_events(INTERVAL) FROM PUBLIC;
+
+DROP FUNCTION IF EXISTS get_change_events_horizon();
+CREATE FUNCTION get_change_events_horizon()
+RETURNS BIGINT AS $$
+    SELECT COALESCE((SELECT h.pruned_through FROM change_events_horizon h), 0);
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- Estado actual de todas las entidades del usuario, para la primera sincronización
+DROP FUNCTION IF EXISTS get_sync_snapshot(UUID);
+CREATE FUNCTION get_sync_snapshot(p_user_id UUID)
+RETURNS TABLE(entity VARCHAR, entity_id UUID, related_id UUID, data JSONB) AS $$
+    SELECT 'project'::VARCHAR, p.id, NULL::UUID, to_jsonb(p) - 'user_id'
+    FROM projects p WHERE p.user_id = p_user_id
+    UNION ALL
+    SELECT 'tag', g.id, NULL, to_jsonb(g) - 'user_id'
+    FROM tags g WHERE g.user_id = p_user_id
+    UNION ALL
+    SELECT 'task', t.id, NULL, to_jsonb(t) - 'user_id'
+    FROM tasks t WHERE t.user_id = p_user_id
+    UNION ALL
+    SELECT 'task_tag', tt.task_id, tt.tag_id, to_jsonb(tt)
+    FROM task_tags tt JOIN tasks t ON t.id = tt.task_id
+    WHERE t.user_id = p_user_id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- Entidades que cambiaron en los siguientes p_limit eventos posteriores a p_since, una fila
+-- por entidad con su estado actual; `data` NULL significa que ya no existe (lápida).
+-- `event_id` es el último evento de la entidad en la página.
+DROP FUNCTION IF EXISTS get_sync_changes(UUID, BIGINT, INTEGER);
+CREATE FUNCTION get_sync_changes(p_user_id UUID, p_since BIGINT, p_limit INTEGER)
+RETURNS TABLE(event_id BIGINT, entity VARCHAR, entity_id UUID, related_id UUID, data JSONB) AS $$
+    WITH page AS (
+        SELECT e.id, e.entity, e.entity_id, e.related_id
+        FROM change_events e
+        WHERE e.user_id = p_user_id AND e.id > p_since
+        ORDER BY e.id
+        LIMIT p_limit
+    ),
+    latest AS (
+        SELECT DISTINCT ON (pg.entity, pg.entity_id, pg.related_id) pg.*
+        FROM page pg
+        ORDER BY pg.entity, pg.entity_id, pg.related_id, pg.id DESC
+    )
+    SELECT l.id, l.entity, l.entity_id, l.related_id,
+           CASE l.entity
+               WHEN 'project' THEN (SELECT to_jsonb(p) - 'user_id' FROM projects p WHERE p.id = l.entity_id AND p.user_id = p_user_id)
+               WHEN 'tag' THEN (SELECT to_jsonb(g) - 'user_id' FROM tags g WHERE g.id = l.entity_id AND g.user_id = p_user_id)
+               WHEN 'task' THEN (SELECT to_jsonb(t) - 'user_id' FROM tasks t WHERE t.id = l.entity_id AND t.user_id = p_user_id)
+               WHEN 'task_tag' THEN (
+                   SELECT to_jsonb(tt)
+                   FROM task_tags tt JOIN tasks t ON t.id = tt.task_id
+                   WHERE tt.task_id = l.entity_id AND tt.tag_id = l.related_id AND t.user_id = p_user_id
+               )
+           END
+    FROM latest l
+    ORDER BY l.id;
+$$ LANGUAGE sql STABLE SECURITY INVOKER;
+
+-- delete_task con If-Match: sin fila si la tarea no existe; (FALSE, versión) si la versión
+-- no coincide
+DROP FUNCTION IF EXISTS delete_task(UUID, UUID);
+CREATE FUNCTION delete_task(p_user_id UUID, p_task_id UUID, p_if_match INTEGER[] DEFAULT NULL)
+RETURNS TABLE(deleted BOOLEAN, current_version INTEGER) AS $$
+DECLARE
+    v_version INTEGER;
+BEGIN
+    SELECT t.version INTO v_version
+    FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, v_version;
+        RETURN;
+    END IF;
+
+    DELETE FROM tasks t
+    WHERE t.id = p_task_id AND t.user_id = p_user_id;
+
+    RETURN QUERY SELECT TRUE, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+-- delete_project con If-Match: si la versión no coincide devuelve (FALSE, NULL, versión);
+-- con restrict y tareas, (FALSE, tareas, versión)
+DROP FUNCTION IF EXISTS delete_project(UUID, UUID, TEXT);
+CREATE FUNCTION delete_project(
+    p_user_id UUID,
+    p_project_id UUID,
+    p_strategy TEXT DEFAULT 'restrict',
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE (
+    deleted BOOLEAN,
+    affected_tasks INTEGER,
+    current_version INTEGER
+) AS $$
+DECLARE
+    v_task_count INTEGER;
+    v_version INTEGER;
+BEGIN
+    IF p_strategy NOT IN ('restrict', 'cascade', 'inbox') THEN
+        RAISE EXCEPTION 'Invalid delete strategy: %', p_strategy
+            USING ERRCODE = 'invalid_parameter_value';
+    END IF;
+
+    -- Bloquea el proyecto para que no se le asignen tareas mientras se decide
+    SELECT p.version INTO v_version
+    FROM projects p
+    WHERE p.id = p_project_id AND p.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, NULL::INTEGER, v_version;
+        RETURN;
+    END IF;
+
+    IF p_strategy = 'cascade' THEN
+        DELETE FROM tasks
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSIF p_strategy = 'inbox' THEN
+        UPDATE tasks SET project_id = NULL
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSE
+        SELECT COUNT(*)::INTEGER INTO v_task_count
+        FROM tasks
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+
+        IF v_task_count > 0 THEN
+            RETURN QUERY SELECT FALSE, v_task_count, v_version;
+            RETURN;
+        END IF;
+    END IF;
+
+    DELETE FROM projects
+    WHERE id = p_project_id AND user_id = p_user_id;
+
+    RETURN QUERY SELECT TRUE, v_task_count, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION prune_change_events(INTERVAL) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_change_events_horizon() TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_sync_snapshot(UUID) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION get_sync_changes(UUID, BIGINT, INTEGER) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION delete_task(UUID, UUID, INTEGER[]) TO todo_app_user;
+GRANT EXECUTE ON FUNCTION delete_project(UUID, UUID, TEXT, INTEGER[]) TO todo_app_user;
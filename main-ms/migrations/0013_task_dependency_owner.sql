@@ -0,0 +1,62 @@
+-- Las dependencias toman su dueño del usuario de la transacción (app_current_user_id(), 0001)
+-- en lugar de confiar en el parámetro. Un p_user_id distinto falla con un error explícito
+-- en vez de depender de que RLS o las claves foráneas lo rechacen después, y el lock del
+-- chequeo de ciclos ya no usa una clave elegida por quien llama.
+
+CREATE OR REPLACE FUNCTION check_task_dependency_cycle()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_user_id UUID := app_current_user_id();
+BEGIN
+    IF v_user_id IS NULL OR NEW.user_id IS DISTINCT FROM v_user_id THEN
+        RAISE EXCEPTION 'Dependency owner does not match the current user'
+            USING ERRCODE = 'insufficient_privilege';
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtextextended('task_dependencies:' || v_user_id::TEXT, 0));
+
+    IF EXISTS (
+        WITH RECURSIVE blockers AS (
+            SELECT d.blocked_by_task_id AS id
+            FROM task_dependencies d
+            WHERE d.task_id = NEW.blocked_by_task_id
+            UNION
+            SELECT d.blocked_by_task_id
+            FROM task_dependencies d
+            JOIN blockers b ON d.task_id = b.id
+        )
+        SELECT 1 FROM blockers WHERE id = NEW.task_id
+    ) THEN
+        RAISE EXCEPTION 'Dependency would create a cycle'
+            USING ERRCODE = 'check_violation', CONSTRAINT = 'task_dependencies_cycle';
+    END IF;
+
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Misma firma que en 0007; p_user_id se conserva para no romper a quien llama, pero solo
+-- se compara con el usuario de la transacción
+CREATE OR REPLACE FUNCTION add_task_dependency(
+    p_user_id UUID,
+    p_task_id UUID,
+    p_blocked_by_task_id UUID
+)
+RETURNS BOOLEAN AS $$
+DECLARE
+    v_user_id UUID := app_current_user_id();
+BEGIN
+    IF v_user_id IS NULL OR p_user_id IS DISTINCT FROM v_user_id THEN
+        RAISE EXCEPTION 'p_user_id does not match the current user'
+            USING ERRCODE = 'insufficient_privilege';
+    END IF;
+
+    INSERT INTO task_dependencies (task_id, blocked_by_task_id, user_id)
+    VALUES (p_task_id, p_blocked_by_task_id, v_user_id)
+    ON CONFLICT (task_id, blocked_by_task_id) DO NOTHING;
+
+    RETURN FOUND;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION add_task_dependency(UUID, UUID, UUID) TO todo_app_user;
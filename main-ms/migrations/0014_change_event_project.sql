@@ -0,0 +1,72 @@
+-- La notificación de un cambio de tarea lleva también su proyecto (`project_id`) y, si la
+-- tarea cambió de proyecto, el anterior (`previous_project_id`). Así el WebSocket de
+-- presencia descarta los cambios de proyectos no suscritos sin consultar la tarea. Solo
+-- cambia el NOTIFY: la tabla change_events y la sincronización siguen igual.
+CREATE OR REPLACE FUNCTION record_change_event()
+RETURNS TRIGGER AS $$
+DECLARE
+    v_row RECORD;
+    v_action VARCHAR;
+    v_user_id UUID;
+    v_entity_id UUID;
+    v_related_id UUID;
+    v_project_id UUID;
+    v_previous_project_id UUID;
+    v_event change_events;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        v_row := OLD;
+        v_action := 'deleted';
+    ELSIF TG_OP = 'INSERT' THEN
+        v_row := NEW;
+        v_action := 'created';
+    ELSE
+        IF OLD IS NOT DISTINCT FROM NEW THEN
+            RETURN NULL;
+        END IF;
+        v_row := NEW;
+        v_action := 'updated';
+    END IF;
+
+    IF TG_ARGV[0] = 'task_tag' THEN
+        v_entity_id := v_row.task_id;
+        v_related_id := v_row.tag_id;
+        SELECT t.user_id INTO v_user_id FROM tasks t WHERE t.id = v_row.task_id;
+        IF v_user_id IS NULL THEN
+            SELECT g.user_id INTO v_user_id FROM tags g WHERE g.id = v_row.tag_id;
+        END IF;
+        -- Se eliminaron la tarea y la etiqueta a la vez: sus propios eventos bastan
+        IF v_user_id IS NULL THEN
+            RETURN NULL;
+        END IF;
+    ELSE
+        v_entity_id := v_row.id;
+        v_user_id := v_row.user_id;
+    END IF;
+
+    IF TG_ARGV[0] = 'task' THEN
+        v_project_id := v_row.project_id;
+        IF TG_OP = 'UPDATE' AND OLD.project_id IS DISTINCT FROM NEW.project_id THEN
+            v_previous_project_id := OLD.project_id;
+        END IF;
+    END IF;
+
+    PERFORM pg_advisory_xact_lock(hashtextextended('change_events:' || v_user_id::TEXT, 0));
+
+    INSERT INTO change_events (user_id, entity, action, entity_id, related_id)
+    VALUES (v_user_id, TG_ARGV[0], v_action, v_entity_id, v_related_id)
+    RETURNING * INTO v_event;
+
+    PERFORM pg_notify(
+        'change_events',
+        (to_jsonb(v_event) || jsonb_build_object(
+            'project_id', v_project_id,
+            'previous_project_id', v_previous_project_id
+        ))::TEXT
+    );
+
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER SET search_path = public;
+
+REVOKE EXECUTE ON FUNCTION record_change_event() FROM PUBLIC;
@@ -0,0 +1,77 @@
+-- delete_project con `cascade` cuenta en `affected_tasks` todas las tareas borradas, también
+-- las subtareas que están en otros proyectos y se borran con su tarea padre (0006). Misma
+-- firma y contrato que en 0012.
+CREATE OR REPLACE FUNCTION delete_project(
+    p_user_id UUID,
+    p_project_id UUID,
+    p_strategy TEXT DEFAULT 'restrict',
+    p_if_match INTEGER[] DEFAULT NULL
+)
+RETURNS TABLE (
+    deleted BOOLEAN,
+    affected_tasks INTEGER,
+    current_version INTEGER
+) AS $$
+DECLARE
+    v_task_count INTEGER;
+    v_version INTEGER;
+BEGIN
+    IF p_strategy NOT IN ('restrict', 'cascade', 'inbox') THEN
+        RAISE EXCEPTION 'Invalid delete strategy: %', p_strategy
+            USING ERRCODE = 'invalid_parameter_value';
+    END IF;
+
+    -- Bloquea el proyecto para que no se le asignen tareas mientras se decide
+    SELECT p.version INTO v_version
+    FROM projects p
+    WHERE p.id = p_project_id AND p.user_id = p_user_id
+    FOR UPDATE;
+
+    IF NOT FOUND THEN
+        RETURN;
+    END IF;
+
+    IF p_if_match IS NOT NULL AND NOT (v_version = ANY(p_if_match)) THEN
+        RETURN QUERY SELECT FALSE, NULL::INTEGER, v_version;
+        RETURN;
+    END IF;
+
+    IF p_strategy = 'cascade' THEN
+        -- Las subtareas de otros proyectos también se borran (por la clave de la tarea
+        -- padre), así que se borran aquí para contarlas
+        WITH RECURSIVE doomed AS (
+            SELECT t.id
+            FROM tasks t
+            WHERE t.project_id = p_project_id AND t.user_id = p_user_id
+            UNION
+            SELECT t.id
+            FROM tasks t
+            JOIN doomed d ON t.parent_task_id = d.id
+            WHERE t.user_id = p_user_id
+        )
+        DELETE FROM tasks
+        WHERE id IN (SELECT id FROM doomed) AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSIF p_strategy = 'inbox' THEN
+        UPDATE tasks SET project_id = NULL
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+        GET DIAGNOSTICS v_task_count = ROW_COUNT;
+    ELSE
+        SELECT COUNT(*)::INTEGER INTO v_task_count
+        FROM tasks
+        WHERE project_id = p_project_id AND user_id = p_user_id;
+
+        IF v_task_count > 0 THEN
+            RETURN QUERY SELECT FALSE, v_task_count, v_version;
+            RETURN;
+        END IF;
+    END IF;
+
+    DELETE FROM projects
+    WHERE id = p_project_id AND user_id = p_user_id;
+
+    RETURN QUERY SELECT TRUE, v_task_count, v_version;
+END;
+$$ LANGUAGE plpgsql SECURITY INVOKER;
+
+GRANT EXECUTE ON FUNCTION delete_project(UUID, UUID, TEXT, INTEGER[]) TO todo_app_user;
@@ -1,17 +1,129 @@
-use sqlx::{postgres::PgPoolOptions, PgPool};
-use std::env;
+use sqlx::{
+    migrate::{Migrate, MigrateError, Migrator},
+    postgres::PgPoolOptions,
+    Connection, PgConnection, PgPool,
+};
+use std::collections::HashSet;
+use uuid::Uuid;
 
-pub async fn connect() -> PgPool {
-    dotenvy::dotenv().ok();
-    
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL no está definido en las variables de entorno");
+use crate::config::{Config, DatabaseConfig};
 
-    println!("🔗 Conectando a: {}", database_url);
+pub async fn connect(config: &DatabaseConfig) -> PgPool {
+    println!("🔗 Conectando a: {}", crate::config::redact_url_password(&config.url));
 
-    PgPoolOptions::new()
-        .max_connections(5)
-        .connect(&database_url)
+    let pool = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .connect(&config.url)
         .await
-        .expect("No se pudo conectar a la base de datos")
-}
\ No newline at end of file
+        .expect("No se pudo conectar a la base de datos");
+
+    warn_if_rls_bypassed(&pool).await;
+
+    pool
+}
+
+/// El error es una violación de una restricción UNIQUE (por ejemplo, un tag repetido)
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|error| error.is_unique_violation())
+}
+
+/// Nombre de la restricción que violó la operación. Los triggers de la base de datos
+/// también lo fijan (`RAISE ... USING CONSTRAINT`) para distinguir sus errores.
+pub fn violated_constraint(error: &sqlx::Error) -> Option<&str> {
+    error.as_database_error().and_then(|error| error.constraint())
+}
+
+/// Migraciones de `migrations/`, embebidas en el binario al compilar
+static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Aplica las migraciones pendientes con `database.migration_url`. sqlx toma un lock
+/// consultivo, así que varias réplicas arrancando a la vez no las aplican dos veces, y
+/// rechaza arrancar si una migración ya aplicada cambió (checksum distinto).
+pub async fn migrate(config: &DatabaseConfig) -> Result<(), MigrateError> {
+    let mut conn = PgConnection::connect(config.migration_url()).await?;
+
+    conn.ensure_migrations_table().await?;
+    let applied: HashSet<i64> = conn
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    MIGRATOR.run(&mut conn).await?;
+
+    let mut pending = MIGRATOR
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .peekable();
+
+    if pending.peek().is_none() {
+        println!("📦 Esquema al día, no hay migraciones pendientes");
+    }
+    for migration in pending {
+        println!("📦 Migración aplicada: {} ({})", migration.version, migration.description);
+    }
+
+    conn.close().await?;
+    Ok(())
+}
+
+/// Ejecuta `operation` en una transacción propia con `app.current_user_id` fijado solo
+/// para ella (equivalente a `SET LOCAL`, que no admite parámetros). Al terminar la
+/// transacción el valor desaparece, así que no se filtra al siguiente uso de la conexión
+/// del pool; las políticas RLS de la base de datos filtran con ese usuario. También fija
+/// las políticas de tareas que aplican los triggers (`app.parent_completion` y
+/// `app.blocked_status_change`).
+pub async fn as_user<T, F>(pool: &PgPool, user_id: Uuid, operation: F) -> Result<T, sqlx::Error>
+where
+    F: AsyncFnOnce(&mut PgConnection) -> Result<T, sqlx::Error>,
+{
+    let mut tx = pool.begin().await?;
+
+    let tasks = &Config::get().tasks;
+    sqlx::query(
+        "SELECT set_config('app.current_user_id', $1, true), \
+                set_config('app.parent_completion', $2, true), \
+                set_config('app.blocked_status_change', $3, true)",
+    )
+    .bind(user_id.to_string())
+    .bind(tasks.parent_completion.as_str())
+    .bind(tasks.blocked_status_change.as_str())
+    .execute(&mut *tx)
+    .await?;
+
+    let result = operation(&mut tx).await?;
+    tx.commit().await?;
+
+    Ok(result)
+}
+
+/// Las políticas RLS solo aíslan a los usuarios si la aplicación se conecta con un rol
+/// restringido (`todo_app_user`): un superusuario, un rol con BYPASSRLS o el dueño de las
+/// tablas las ignoran
+async fn warn_if_rls_bypassed(pool: &PgPool) {
+    let check = sqlx::query_as::<_, (String, bool)>(
+        r#"
+        SELECT current_user::TEXT,
+               r.rolsuper OR r.rolbypassrls OR EXISTS (
+                   SELECT 1 FROM pg_tables
+                   WHERE schemaname = 'public' AND tablename = 'tasks' AND tableowner = current_user
+               )
+        FROM pg_roles r
+        WHERE r.rolname = current_user
+        "#,
+    )
+    .fetch_one(pool)
+    .await;
+
+    match check {
+        Ok((role, true)) => eprintln!(
+            "⚠️ Conectado como '{}', que no está sujeto a Row Level Security: usa todo_app_user en DATABASE_URL",
+            role
+        ),
+        Ok(_) => {}
+        Err(e) => eprintln!("⚠️ No se pudo verificar el rol de la base de datos: {}", e),
+    }
+}
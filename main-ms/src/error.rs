@@ -0,0 +1,258 @@
+// error.rs
+use axum::{
+    extract::rejection::{JsonRejection, PathRejection, QueryRejection},
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Cuerpo de todas las respuestas de error de la API.
+///
+/// `error` es un mensaje legible y `code` un identificador estable para los clientes;
+/// `details` solo aparece en los errores de validación, con un elemento por campo.
+#[derive(Debug, Serialize)]
+pub struct ErrorBody {
+    pub error: String,
+    pub code: &'static str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<FieldError>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum AppError {
+    /// Uno o más campos inválidos (400 `validation_failed`)
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<FieldError>),
+    /// Petición mal formada: cuerpo, query o ruta que no se pueden interpretar
+    #[error("Invalid request: {message}")]
+    InvalidRequest {
+        status: StatusCode,
+        code: &'static str,
+        message: String,
+    },
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    /// El estado actual impide la operación (409)
+    #[error("Conflict: {message}")]
+    Conflict { code: &'static str, message: String },
+    /// `If-Match` no coincide con la versión actual del recurso (412)
+    #[error("Precondition failed: {0}")]
+    PreconditionFailed(String),
+    /// Error devuelto por auth-ms: se conserva su status y, en un login bloqueado, `Retry-After`
+    #[error("Auth service responded {status}: {message}")]
+    AuthService {
+        status: StatusCode,
+        message: String,
+        retry_after: Option<HeaderValue>,
+    },
+    /// auth-ms no respondió o respondió algo que no se pudo interpretar (502)
+    #[error("Auth service unavailable: {0}")]
+    AuthServiceUnavailable(String),
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("Internal error: {0}")]
+    Internal(String),
+}
+
+impl AppError {
+    /// Error de validación de un solo campo
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        AppError::Validation(vec![FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        }])
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+}
+
+impl AppError {
+    /// Status y cuerpo con los que se responde el error, sin armar la respuesta HTTP. Lo
+    /// usan también quienes informan el error dentro de otra respuesta (la sincronización).
+    pub fn into_body(self) -> (StatusCode, ErrorBody) {
+        let (status, body, _) = self.into_parts();
+        (status, body)
+    }
+
+    fn into_parts(self) -> (StatusCode, ErrorBody, Option<HeaderValue>) {
+        // Los detalles de los errores internos solo van al log: pueden incluir SQL o datos de
+        // otras peticiones
+        if matches!(
+            self,
+            AppError::Database(_) | AppError::Internal(_) | AppError::AuthServiceUnavailable(_)
+        ) {
+            eprintln!("❌ {}", self);
+        }
+
+        let mut retry_after_header = None;
+
+        let (status, code, error, details) = match self {
+            AppError::Validation(details) => {
+                let error = details
+                    .iter()
+                    .map(|detail| format!("{}: {}", detail.field, detail.message))
+                    .collect::<Vec<_>>()
+                    .join("; ");
+                (StatusCode::BAD_REQUEST, "validation_failed", error, details)
+            }
+            AppError::InvalidRequest { status, code, message } => (status, code, message, Vec::new()),
+            AppError::Unauthorized(message) => (StatusCode::UNAUTHORIZED, "unauthorized", message, Vec::new()),
+            AppError::Forbidden(message) => (StatusCode::FORBIDDEN, "forbidden", message, Vec::new()),
+            AppError::NotFound(message) => (StatusCode::NOT_FOUND, "not_found", message, Vec::new()),
+            AppError::Conflict { code, message } => (StatusCode::CONFLICT, code, message, Vec::new()),
+            AppError::PreconditionFailed(message) => {
+                (StatusCode::PRECONDITION_FAILED, "precondition_failed", message, Vec::new())
+            }
+            AppError::AuthService { status, message, retry_after } => {
+                retry_after_header = retry_after;
+
+                if status.is_server_error() {
+                    eprintln!("❌ Auth service responded {}: {}", status, message);
+                    (
+                        StatusCode::BAD_GATEWAY,
+                        "auth_service_error",
+                        "Authentication service error".to_string(),
+                        Vec::new(),
+                    )
+                } else {
+                    (status, auth_service_code(status), message, Vec::new())
+                }
+            }
+            AppError::AuthServiceUnavailable(_) => (
+                StatusCode::BAD_GATEWAY,
+                "auth_service_unavailable",
+                "Authentication service unavailable".to_string(),
+                Vec::new(),
+            ),
+            AppError::Database(_) | AppError::Internal(_) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "internal_error",
+                "Internal server error".to_string(),
+                Vec::new(),
+            ),
+        };
+
+        (status, ErrorBody { error, code, details }, retry_after_header)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, body, retry_after) = self.into_parts();
+
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, retry_after);
+        }
+        response
+    }
+}
+
+/// auth-ms solo devuelve un mensaje: el código se deduce del status
+fn auth_service_code(status: StatusCode) -> &'static str {
+    match status {
+        StatusCode::BAD_REQUEST => "bad_request",
+        StatusCode::UNAUTHORIZED => "unauthorized",
+        StatusCode::FORBIDDEN => "forbidden",
+        StatusCode::NOT_FOUND => "not_found",
+        StatusCode::CONFLICT => "conflict",
+        StatusCode::TOO_MANY_REQUESTS => "too_many_requests",
+        _ => "auth_service_error",
+    }
+}
+
+impl From<JsonRejection> for AppError {
+    fn from(rejection: JsonRejection) -> Self {
+        AppError::InvalidRequest {
+            status: rejection.status(),
+            code: "invalid_body",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<QueryRejection> for AppError {
+    fn from(rejection: QueryRejection) -> Self {
+        AppError::InvalidRequest {
+            status: rejection.status(),
+            code: "invalid_query",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<PathRejection> for AppError {
+    fn from(rejection: PathRejection) -> Self {
+        AppError::InvalidRequest {
+            status: rejection.status(),
+            code: "invalid_path",
+            message: rejection.body_text(),
+        }
+    }
+}
+
+/// Un fallo al contactar auth-ms o al leer su respuesta
+impl From<reqwest::Error> for AppError {
+    fn from(error: reqwest::Error) -> Self {
+        AppError::AuthServiceUnavailable(error.to_string())
+    }
+}
+
+/// Acumula los errores de validación de una petición para informarlos todos juntos
+#[derive(Debug, Default)]
+pub struct Validator(Vec<FieldError>);
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn error(&mut self, field: &str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field: field.to_string(),
+            message: message.into(),
+        });
+    }
+
+    /// Anota `message` si no se cumple `condition`
+    pub fn check(&mut self, condition: bool, field: &str, message: impl Into<String>) {
+        if !condition {
+            self.error(field, message);
+        }
+    }
+
+    /// Interpreta un campo opcional; si el valor no es válido anota el error y devuelve `None`
+    pub fn parse<T>(&mut self, field: &str, value: Option<&str>) -> Option<T>
+    where
+        T: FromStr<Err = String>,
+    {
+        match value?.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(message) => {
+                self.error(field, message);
+                None
+            }
+        }
+    }
+
+    pub fn finish(self) -> Result<(), AppError> {
+        if self.0.is_empty() {
+            Ok(())
+        } else {
+            Err(AppError::Validation(self.0))
+        }
+    }
+}
@@ -0,0 +1,135 @@
+// events.rs
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::PgListener, PgPool};
+use std::{
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::config::SyncConfig;
+
+/// Canal de NOTIFY en el que publica el trigger `record_change_event`
+const CHANNEL: &str = "change_events";
+
+/// Señales en cola por suscriptor; uno que se atrasa más que esto vuelve a leer de la
+/// base de datos
+const CAPACITY: usize = 1024;
+
+/// Espera antes de reconectar el listener tras un error
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Cada cuánto se eliminan los eventos más antiguos que `SYNC_RETENTION_DAYS`
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Cambio de una tarea, proyecto, etiqueta o vínculo tarea-etiqueta (tabla `change_events`)
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct ChangeEvent {
+    /// Creciente por usuario; es el id del evento SSE
+    pub id: i64,
+    #[serde(skip_serializing)]
+    pub user_id: Uuid,
+    /// `task`, `project`, `tag` o `task_tag`
+    pub entity: String,
+    /// `created`, `updated` o `deleted`
+    pub action: String,
+    /// En un vínculo tarea-etiqueta, la tarea
+    pub entity_id: Uuid,
+    /// En un vínculo tarea-etiqueta, la etiqueta
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub related_id: Option<Uuid>,
+    pub occurred_at: DateTime<Utc>,
+    /// En una tarea, su proyecto. Solo viene en la notificación, no en la tabla.
+    #[serde(default, skip_serializing)]
+    #[sqlx(skip)]
+    pub project_id: Option<Uuid>,
+    /// En una tarea que cambió de proyecto, el anterior
+    #[serde(default, skip_serializing)]
+    #[sqlx(skip)]
+    pub previous_project_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone)]
+pub enum Signal {
+    Event(Arc<ChangeEvent>),
+    /// Se pudieron perder notificaciones (se cayó la conexión del listener): cada
+    /// suscriptor debe leer de la base de datos lo que le falte
+    Resync,
+}
+
+static BUS: OnceLock<broadcast::Sender<Signal>> = OnceLock::new();
+
+/// Escucha el canal `change_events` de Postgres y reparte los eventos a los streams
+/// abiertos en este proceso. Una sola conexión escucha por todos los usuarios; cada
+/// suscriptor filtra los suyos. También elimina periódicamente los eventos antiguos.
+pub fn spawn(pool: PgPool, config: &'static SyncConfig) {
+    let (sender, _) = broadcast::channel(CAPACITY);
+    let sender = BUS.get_or_init(|| sender).clone();
+
+    if config.retention_days > 0 {
+        tokio::spawn(prune(pool.clone(), Duration::from_secs(u64::from(config.retention_days) * 86_400)));
+    }
+    tokio::spawn(listen(pool, sender));
+}
+
+/// Recibe las señales emitidas desde ahora
+pub fn subscribe() -> broadcast::Receiver<Signal> {
+    BUS.get().expect("events::spawn must be called at startup").subscribe()
+}
+
+async fn listen(pool: PgPool, sender: broadcast::Sender<Signal>) {
+    loop {
+        if let Err(e) = forward(&pool, &sender).await {
+            eprintln!("⚠️ Se perdió la conexión del listener de eventos: {}", e);
+        }
+
+        // Lo que llegó mientras no había conexión se lee de la base de datos
+        let _ = sender.send(Signal::Resync);
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+async fn forward(pool: &PgPool, sender: &broadcast::Sender<Signal>) -> Result<(), sqlx::Error> {
+    let mut listener = PgListener::connect_with(pool).await?;
+    listener.listen(CHANNEL).await?;
+
+    // La conexión anterior pudo perder eventos
+    let _ = sender.send(Signal::Resync);
+
+    loop {
+        // `None`: se cortó la conexión y sqlx reconectó, pero lo de entretanto se perdió
+        let Some(notification) = listener.try_recv().await? else {
+            let _ = sender.send(Signal::Resync);
+            continue;
+        };
+
+        match serde_json::from_str::<ChangeEvent>(notification.payload()) {
+            // Sin suscriptores `send` falla, y no importa
+            Ok(event) => {
+                let _ = sender.send(Signal::Event(Arc::new(event)));
+            }
+            Err(e) => eprintln!("⚠️ Evento con formato inválido en {}: {}", CHANNEL, e),
+        }
+    }
+}
+
+/// Con varias réplicas todas limpian; la operación es idempotente
+async fn prune(pool: PgPool, retention: Duration) {
+    let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match sqlx::query_scalar::<_, i32>("SELECT prune_change_events($1)")
+            .bind(retention)
+            .fetch_one(&pool)
+            .await
+        {
+            Ok(0) => {}
+            Ok(deleted) => println!("🧹 Eventos de cambios eliminados por antigüedad: {}", deleted),
+            Err(e) => eprintln!("⚠️ No se pudieron eliminar los eventos antiguos: {}", e),
+        }
+    }
+}
@@ -1,35 +1,77 @@
-use tower_http::cors::{CorsLayer, Any};
 use std::net::SocketAddr;
 use tokio::net::TcpListener;
 
+mod config;
+mod error;
+mod extract;
 mod routes;
 mod db;
+mod events;
+mod mailer;
+mod models;
+mod presence;
+mod reminders;
 pub mod utils;
 
 #[tokio::main]
 async fn main() {
+    dotenvy::dotenv().ok();
+
+    let args = config::Args::parse().unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        std::process::exit(2);
+    });
+
+    let config = config::Config::load(args.config_file.as_deref()).unwrap_or_else(|e| {
+        eprint!("{}", e);
+        std::process::exit(1);
+    });
+
+    if args.print_config {
+        print!("{}", config.to_redacted_toml());
+        return;
+    }
+
+    let config = config::init(config);
+
+    // Migraciones embebidas: `main-ms migrate` las aplica y sale
+    if args.command == config::Command::Migrate || config.database.migrate_on_startup {
+        if let Err(e) = db::migrate(&config.database).await {
+            eprintln!("❌ No se pudieron aplicar las migraciones: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if args.command == config::Command::Migrate {
+        return;
+    }
+
     // Conexión a DB
-    let pool = db::connect().await;
+    let pool = db::connect(&config.database).await;
+
+    // Validación local de JWT (clave compartida con auth-ms)
+    utils::token::init();
+
+    // Cambios de datos para el stream SSE (LISTEN change_events)
+    events::spawn(pool.clone(), &config.sync);
 
-    // 👇 Configuración CORRECTA de CORS con tower-http
-    let cors = CorsLayer::new()
-        .allow_origin(Any) // Permite cualquier origen
-        .allow_methods(Any) // Permite cualquier método
-        .allow_headers(Any) // Permite cualquier header
-        .allow_credentials(false); // Si no usas cookies, false está bien
+    // Recordatorios vencidos (REMINDERS_ENABLED)
+    reminders::spawn(pool.clone(), config);
 
-    // Cargar rutas y aplicar CORS
+    // Cargar rutas y aplicar CORS (CORS_ALLOWED_ORIGINS)
     let app = routes::app()
         .with_state(pool)
-        .layer(cors); // 👈 Aplica el layer de CORS
+        .layer(config.cors.layer());
 
-    // Dirección del servidor - IMPORTANTE para Docker
-    let addr = SocketAddr::from(([0, 0, 0, 0], 8080)); // 👈 Cambia a 0.0.0.0
+    // Dirección del servidor (BIND_ADDRESS y PORT); en Docker debe ser 0.0.0.0
+    let addr = config.bind_addr();
     println!("🚀 Servidor escuchando en http://{}", addr);
 
     // Crear listener
     let listener = TcpListener::bind(addr).await.unwrap();
 
     // Levantar servidor
-    axum::serve(listener, app).await.unwrap();
+    // ConnectInfo: el login reenvía la dirección del cliente a auth-ms
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .await
+        .unwrap();
 }
\ No newline at end of file
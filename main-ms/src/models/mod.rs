@@ -0,0 +1,22 @@
+// models/mod.rs
+mod notification;
+mod recurrence;
+mod reminder;
+mod task;
+
+pub use notification::NotificationEvent;
+pub use recurrence::RecurrenceRule;
+pub use reminder::{ReminderChannel, ReminderStatus};
+pub use task::{TaskPriority, TaskStatus};
+
+/// `FromStr` de los enums: el error lista los valores válidos
+fn parse_variant<T: Copy>(variants: &[T], as_str: fn(T) -> &'static str, value: &str) -> Result<T, String> {
+    variants
+        .iter()
+        .copied()
+        .find(|variant| as_str(*variant) == value)
+        .ok_or_else(|| {
+            let allowed: Vec<&str> = variants.iter().map(|variant| as_str(*variant)).collect();
+            format!("must be one of: {}", allowed.join(", "))
+        })
+}
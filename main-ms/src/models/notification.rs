@@ -0,0 +1,42 @@
+// models/notification.rs
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use super::parse_variant;
+
+/// Tipo de evento de una notificación. En la base de datos es texto (columna `event_type`),
+/// con los mismos valores; cada usuario puede desactivar los tipos que no le interesan.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationEvent {
+    /// Se disparó un recordatorio con el canal `in_app`
+    Reminder,
+    /// Venció la fecha límite de una tarea sin completarse
+    TaskOverdue,
+}
+
+impl NotificationEvent {
+    pub const ALL: [NotificationEvent; 2] = [NotificationEvent::Reminder, NotificationEvent::TaskOverdue];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NotificationEvent::Reminder => "reminder",
+            NotificationEvent::TaskOverdue => "task_overdue",
+        }
+    }
+}
+
+impl fmt::Display for NotificationEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NotificationEvent {
+    type Err = String;
+
+    /// El error es el mensaje que se devuelve al cliente
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_variant(&NotificationEvent::ALL, NotificationEvent::as_str, value)
+    }
+}
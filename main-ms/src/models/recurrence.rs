@@ -0,0 +1,543 @@
+// models/recurrence.rs
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use std::{fmt, str::FromStr};
+
+/// Periodos que se recorren buscando la siguiente repetición antes de darse por vencido
+/// (por ejemplo, un 29 de febrero anual necesita hasta 8 años)
+const MAX_PERIODS: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Frequency {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        }
+    }
+}
+
+/// Un elemento de BYDAY: día de la semana, con ordinal en las reglas mensuales
+/// (`1MO` es el primer lunes del mes y `-1FR` el último viernes)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i8>,
+    pub weekday: Weekday,
+}
+
+/// Cuándo termina la serie. `Count` incluye la repetición actual: al generar la siguiente
+/// se descuenta una.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceEnd {
+    Never,
+    Count(u32),
+    Until(DateTime<Utc>),
+}
+
+/// Regla de repetición: el subconjunto de RRULE (RFC 5545) con FREQ, INTERVAL, BYDAY y
+/// COUNT o UNTIL. La fecha límite de la tarea hace de DTSTART y las fechas se calculan en
+/// UTC, conservando la hora.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    pub end: RecurrenceEnd,
+}
+
+impl RecurrenceRule {
+    /// Repetición siguiente a la que vence en `start`, con la regla que debe llevar. `None`
+    /// si la serie ya terminó.
+    pub fn next(&self, start: DateTime<Utc>) -> Option<(DateTime<Utc>, RecurrenceRule)> {
+        let end = match self.end {
+            RecurrenceEnd::Count(remaining) if remaining <= 1 => return None,
+            RecurrenceEnd::Count(remaining) => RecurrenceEnd::Count(remaining - 1),
+            end => end,
+        };
+
+        let due_date = self.next_after(start)?;
+        Some((due_date, RecurrenceRule { end, ..self.clone() }))
+    }
+
+    /// Las próximas `limit` repeticiones después de `start`, en el mismo orden en que se
+    /// generarían al ir completándolas
+    pub fn upcoming(&self, start: DateTime<Utc>, limit: usize) -> Vec<DateTime<Utc>> {
+        let mut dates = Vec::with_capacity(limit);
+        let mut rule = self.clone();
+        let mut current = start;
+
+        while dates.len() < limit {
+            let Some((due_date, next_rule)) = rule.next(current) else {
+                break;
+            };
+            dates.push(due_date);
+            current = due_date;
+            rule = next_rule;
+        }
+
+        dates
+    }
+
+    /// Primera fecha de la regla posterior a `start`, contando los periodos desde el que
+    /// contiene a `start`
+    fn next_after(&self, start: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let date = start.date_naive();
+        let time = start.time();
+
+        for period in 0..MAX_PERIODS {
+            let step = period.checked_mul(self.interval)?;
+
+            for day in self.candidates(date, step)? {
+                let occurrence = day.and_time(time).and_utc();
+                if occurrence <= start {
+                    continue;
+                }
+                if let RecurrenceEnd::Until(until) = self.end {
+                    if occurrence > until {
+                        return None;
+                    }
+                }
+                return Some(occurrence);
+            }
+        }
+
+        None
+    }
+
+    /// Días de la regla, en orden, dentro del periodo que está `step` periodos después del
+    /// que contiene a `date`. `None` si la fecha se sale del rango de chrono.
+    fn candidates(&self, date: NaiveDate, step: u32) -> Option<Vec<NaiveDate>> {
+        let days = match self.frequency {
+            Frequency::Daily => {
+                let day = date.checked_add_days(Days::new(step.into()))?;
+                if self.by_day.is_empty() || self.by_day.iter().any(|by_day| by_day.weekday == day.weekday()) {
+                    vec![day]
+                } else {
+                    Vec::new()
+                }
+            }
+            Frequency::Weekly => {
+                let monday = date.checked_sub_days(Days::new(date.weekday().num_days_from_monday().into()))?;
+                let week = monday.checked_add_days(Days::new(u64::from(step) * 7))?;
+
+                let mut offsets: Vec<u32> = if self.by_day.is_empty() {
+                    vec![date.weekday().num_days_from_monday()]
+                } else {
+                    self.by_day
+                        .iter()
+                        .map(|by_day| by_day.weekday.num_days_from_monday())
+                        .collect()
+                };
+                offsets.sort_unstable();
+                offsets.dedup();
+
+                offsets
+                    .into_iter()
+                    .map(|offset| week.checked_add_days(Days::new(offset.into())))
+                    .collect::<Option<Vec<_>>>()?
+            }
+            Frequency::Monthly => {
+                let first = date.with_day(1)?.checked_add_months(Months::new(step))?;
+                if self.by_day.is_empty() {
+                    // Los meses sin ese día (un 31 en abril) no tienen repetición
+                    first.with_day(date.day()).into_iter().collect()
+                } else {
+                    self.monthly_by_day(first)?
+                }
+            }
+            Frequency::Yearly => {
+                let year = date.year().checked_add(i32::try_from(step).ok()?)?;
+                NaiveDate::from_ymd_opt(year, date.month(), date.day()).into_iter().collect()
+            }
+        };
+
+        Some(days)
+    }
+
+    fn monthly_by_day(&self, first: NaiveDate) -> Option<Vec<NaiveDate>> {
+        let next_month = first.checked_add_months(Months::new(1))?;
+        let month: Vec<NaiveDate> = first.iter_days().take_while(|day| *day < next_month).collect();
+
+        let mut days = Vec::new();
+        for by_day in &self.by_day {
+            let matching: Vec<NaiveDate> = month
+                .iter()
+                .copied()
+                .filter(|day| day.weekday() == by_day.weekday)
+                .collect();
+
+            match by_day.ordinal {
+                None => days.extend(matching),
+                Some(ordinal) if ordinal > 0 => days.extend(matching.get(ordinal as usize - 1)),
+                Some(ordinal) => days.extend(matching.iter().rev().nth(ordinal.unsigned_abs() as usize - 1)),
+            }
+        }
+
+        days.sort_unstable();
+        days.dedup();
+        Some(days)
+    }
+}
+
+/// Forma normalizada, la que se guarda: `FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10`
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FREQ={}", self.frequency.as_str())?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+
+        if !self.by_day.is_empty() {
+            let by_day: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|by_day| match by_day.ordinal {
+                    Some(ordinal) => format!("{}{}", ordinal, weekday_code(by_day.weekday)),
+                    None => weekday_code(by_day.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", by_day.join(","))?;
+        }
+
+        match self.end {
+            RecurrenceEnd::Never => Ok(()),
+            RecurrenceEnd::Count(count) => write!(f, ";COUNT={}", count),
+            RecurrenceEnd::Until(until) => write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ")),
+        }
+    }
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    /// Acepta el prefijo `RRULE:` y mayúsculas o minúsculas. El error es el mensaje que se
+    /// devuelve al cliente.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_ascii_uppercase();
+        let value = value.strip_prefix("RRULE:").unwrap_or(&value);
+
+        let mut frequency = None;
+        let mut interval = None;
+        let mut by_day = None;
+        let mut count = None;
+        let mut until = None;
+
+        for part in value.split(';').filter(|part| !part.is_empty()) {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("invalid part '{}': expected NAME=VALUE", part))?;
+
+            match name {
+                "FREQ" => set_once(&mut frequency, name, parse_frequency(value)?)?,
+                "INTERVAL" => set_once(&mut interval, name, parse_positive(name, value)?)?,
+                "BYDAY" => set_once(&mut by_day, name, parse_by_day(value)?)?,
+                "COUNT" => set_once(&mut count, name, parse_positive(name, value)?)?,
+                "UNTIL" => set_once(&mut until, name, parse_until(value)?)?,
+                _ => {
+                    return Err(format!(
+                        "unsupported part '{}' (supported: FREQ, INTERVAL, BYDAY, COUNT, UNTIL)",
+                        name
+                    ))
+                }
+            }
+        }
+
+        let frequency = frequency.ok_or("FREQ is required")?;
+        let by_day = by_day.unwrap_or_default();
+
+        if frequency == Frequency::Yearly && !by_day.is_empty() {
+            return Err("BYDAY is not supported with FREQ=YEARLY".to_string());
+        }
+        if frequency != Frequency::Monthly && by_day.iter().any(|by_day| by_day.ordinal.is_some()) {
+            return Err("BYDAY ordinals (e.g. 1MO) are only supported with FREQ=MONTHLY".to_string());
+        }
+
+        let end = match (count, until) {
+            (Some(_), Some(_)) => return Err("COUNT and UNTIL cannot be used together".to_string()),
+            (Some(count), None) => RecurrenceEnd::Count(count),
+            (None, Some(until)) => RecurrenceEnd::Until(until),
+            (None, None) => RecurrenceEnd::Never,
+        };
+
+        Ok(RecurrenceRule {
+            frequency,
+            interval: interval.unwrap_or(1),
+            by_day,
+            end,
+        })
+    }
+}
+
+fn set_once<T>(slot: &mut Option<T>, name: &str, value: T) -> Result<(), String> {
+    if slot.replace(value).is_some() {
+        return Err(format!("{} is repeated", name));
+    }
+    Ok(())
+}
+
+fn parse_frequency(value: &str) -> Result<Frequency, String> {
+    match value {
+        "DAILY" => Ok(Frequency::Daily),
+        "WEEKLY" => Ok(Frequency::Weekly),
+        "MONTHLY" => Ok(Frequency::Monthly),
+        "YEARLY" => Ok(Frequency::Yearly),
+        _ => Err("FREQ must be one of: DAILY, WEEKLY, MONTHLY, YEARLY".to_string()),
+    }
+}
+
+fn parse_positive(name: &str, value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(number) if number > 0 => Ok(number),
+        _ => Err(format!("{} must be a positive integer", name)),
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<Vec<ByDay>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            let invalid = || format!("invalid BYDAY value '{}' (expected e.g. MO, 1MO or -1FR)", item);
+
+            let split = item.len().checked_sub(2).filter(|split| item.is_char_boundary(*split)).ok_or_else(invalid)?;
+            let (ordinal, code) = item.split_at(split);
+            let weekday = parse_weekday(code).ok_or_else(invalid)?;
+
+            let ordinal = if ordinal.is_empty() {
+                None
+            } else {
+                match ordinal.parse::<i8>() {
+                    Ok(ordinal) if ordinal != 0 && (-5..=5).contains(&ordinal) => Some(ordinal),
+                    _ => return Err(invalid()),
+                }
+            };
+
+            Ok(ByDay { ordinal, weekday })
+        })
+        .collect()
+}
+
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    // Una fecha sin hora incluye todo ese día
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        return date
+            .and_hms_opt(23, 59, 59)
+            .map(|until| until.and_utc())
+            .ok_or_else(|| "invalid UNTIL date".to_string());
+    }
+
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%SZ")
+        .map(|until| until.and_utc())
+        .map_err(|_| "UNTIL must be a UTC date (YYYYMMDD or YYYYMMDDTHHMMSSZ)".to_string())
+}
+
+fn parse_weekday(code: &str) -> Option<Weekday> {
+    match code {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(value: &str) -> RecurrenceRule {
+        value.parse().unwrap()
+    }
+
+    fn at(value: &str) -> DateTime<Utc> {
+        value.parse().unwrap()
+    }
+
+    fn dates(values: &[&str]) -> Vec<DateTime<Utc>> {
+        values.iter().map(|value| at(value)).collect()
+    }
+
+    #[test]
+    fn parses_and_normalizes() {
+        let parsed = rule("rrule:freq=weekly;interval=2;byday=mo,we;count=10");
+
+        assert_eq!(parsed.frequency, Frequency::Weekly);
+        assert_eq!(parsed.interval, 2);
+        assert_eq!(parsed.end, RecurrenceEnd::Count(10));
+        assert_eq!(parsed.to_string(), "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=10");
+        assert_eq!(rule("FREQ=MONTHLY;BYDAY=-1FR").to_string(), "FREQ=MONTHLY;BYDAY=-1FR");
+    }
+
+    #[test]
+    fn until_without_time_includes_the_whole_day() {
+        assert_eq!(rule("FREQ=DAILY;UNTIL=20250103").end, RecurrenceEnd::Until(at("2025-01-03T23:59:59Z")));
+        assert_eq!(
+            rule("FREQ=DAILY;UNTIL=20250103T080000Z").to_string(),
+            "FREQ=DAILY;UNTIL=20250103T080000Z"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_rules() {
+        let cases = [
+            ("", "FREQ is required"),
+            ("INTERVAL=2", "FREQ is required"),
+            ("FREQ=HOURLY", "FREQ must be one of"),
+            ("FREQ=DAILY;FREQ=WEEKLY", "FREQ is repeated"),
+            ("FREQ=DAILY;INTERVAL=0", "INTERVAL must be a positive integer"),
+            ("FREQ=DAILY;COUNT=-1", "COUNT must be a positive integer"),
+            ("FREQ=DAILY;COUNT=2;UNTIL=20250101", "COUNT and UNTIL cannot be used together"),
+            ("FREQ=DAILY;BYMONTH=1", "unsupported part 'BYMONTH'"),
+            ("FREQ=DAILY;WKST", "expected NAME=VALUE"),
+            ("FREQ=WEEKLY;BYDAY=XX", "invalid BYDAY value 'XX'"),
+            ("FREQ=MONTHLY;BYDAY=6MO", "invalid BYDAY value '6MO'"),
+            ("FREQ=MONTHLY;BYDAY=0MO", "invalid BYDAY value '0MO'"),
+            ("FREQ=WEEKLY;BYDAY=1MO", "only supported with FREQ=MONTHLY"),
+            ("FREQ=YEARLY;BYDAY=MO", "not supported with FREQ=YEARLY"),
+            ("FREQ=DAILY;UNTIL=2025-01-01", "UNTIL must be a UTC date"),
+        ];
+
+        for (value, expected) in cases {
+            let error = value.parse::<RecurrenceRule>().unwrap_err();
+            assert!(error.contains(expected), "{}: {}", value, error);
+        }
+    }
+
+    #[test]
+    fn daily() {
+        let start = at("2025-01-01T09:30:00Z");
+
+        assert_eq!(
+            rule("FREQ=DAILY;INTERVAL=2").upcoming(start, 3),
+            dates(&["2025-01-03T09:30:00Z", "2025-01-05T09:30:00Z", "2025-01-07T09:30:00Z"])
+        );
+        assert_eq!(
+            rule("FREQ=DAILY;BYDAY=MO,FR").upcoming(start, 3),
+            dates(&["2025-01-03T09:30:00Z", "2025-01-06T09:30:00Z", "2025-01-10T09:30:00Z"])
+        );
+    }
+
+    #[test]
+    fn weekly() {
+        // 2025-01-01 es miércoles
+        let start = at("2025-01-01T09:00:00Z");
+
+        assert_eq!(
+            rule("FREQ=WEEKLY").upcoming(start, 2),
+            dates(&["2025-01-08T09:00:00Z", "2025-01-15T09:00:00Z"])
+        );
+        assert_eq!(
+            rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=WE,MO").upcoming(start, 4),
+            dates(&[
+                "2025-01-13T09:00:00Z",
+                "2025-01-15T09:00:00Z",
+                "2025-01-27T09:00:00Z",
+                "2025-01-29T09:00:00Z",
+            ])
+        );
+    }
+
+    #[test]
+    fn monthly() {
+        let start = at("2025-01-01T09:00:00Z");
+
+        assert_eq!(
+            rule("FREQ=MONTHLY;INTERVAL=3").upcoming(start, 2),
+            dates(&["2025-04-01T09:00:00Z", "2025-07-01T09:00:00Z"])
+        );
+        assert_eq!(
+            rule("FREQ=MONTHLY;BYDAY=1MO").upcoming(start, 3),
+            dates(&["2025-01-06T09:00:00Z", "2025-02-03T09:00:00Z", "2025-03-03T09:00:00Z"])
+        );
+        assert_eq!(
+            rule("FREQ=MONTHLY;BYDAY=-1FR").upcoming(start, 3),
+            dates(&["2025-01-31T09:00:00Z", "2025-02-28T09:00:00Z", "2025-03-28T09:00:00Z"])
+        );
+    }
+
+    #[test]
+    fn monthly_skips_months_without_that_day() {
+        assert_eq!(
+            rule("FREQ=MONTHLY").upcoming(at("2025-01-31T09:00:00Z"), 4),
+            dates(&[
+                "2025-03-31T09:00:00Z",
+                "2025-05-31T09:00:00Z",
+                "2025-07-31T09:00:00Z",
+                "2025-08-31T09:00:00Z",
+            ])
+        );
+    }
+
+    #[test]
+    fn yearly() {
+        assert_eq!(
+            rule("FREQ=YEARLY").upcoming(at("2025-03-15T09:00:00Z"), 2),
+            dates(&["2026-03-15T09:00:00Z", "2027-03-15T09:00:00Z"])
+        );
+    }
+
+    #[test]
+    fn yearly_skips_years_without_february_29() {
+        assert_eq!(
+            rule("FREQ=YEARLY").upcoming(at("2024-02-29T09:00:00Z"), 2),
+            dates(&["2028-02-29T09:00:00Z", "2032-02-29T09:00:00Z"])
+        );
+    }
+
+    #[test]
+    fn count_is_decremented_until_the_series_ends() {
+        let start = at("2025-01-01T09:00:00Z");
+
+        let (due_date, next) = rule("FREQ=DAILY;COUNT=3").next(start).unwrap();
+        assert_eq!(due_date, at("2025-01-02T09:00:00Z"));
+        assert_eq!(next.end, RecurrenceEnd::Count(2));
+
+        let (due_date, last) = next.next(due_date).unwrap();
+        assert_eq!(due_date, at("2025-01-03T09:00:00Z"));
+        assert_eq!(last.end, RecurrenceEnd::Count(1));
+        assert_eq!(last.next(due_date), None);
+
+        assert_eq!(rule("FREQ=DAILY;COUNT=3").upcoming(start, 10).len(), 2);
+        assert_eq!(rule("FREQ=DAILY;COUNT=1").next(start), None);
+    }
+
+    #[test]
+    fn until_cuts_the_series() {
+        let start = at("2025-01-01T09:00:00Z");
+
+        assert_eq!(
+            rule("FREQ=DAILY;UNTIL=20250103").upcoming(start, 10),
+            dates(&["2025-01-02T09:00:00Z", "2025-01-03T09:00:00Z"])
+        );
+        assert_eq!(
+            rule("FREQ=DAILY;UNTIL=20250103T080000Z").upcoming(start, 10),
+            dates(&["2025-01-02T09:00:00Z"])
+        );
+
+        let (_, next) = rule("FREQ=DAILY;UNTIL=20250103").next(start).unwrap();
+        assert_eq!(next.end, RecurrenceEnd::Until(at("2025-01-03T23:59:59Z")));
+    }
+}
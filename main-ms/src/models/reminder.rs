@@ -0,0 +1,97 @@
+// models/reminder.rs
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use super::parse_variant;
+
+/// Estado de un recordatorio (tipo `reminder_status` en Postgres)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "reminder_status", rename_all = "lowercase")]
+pub enum ReminderStatus {
+    /// Aún no vence, o espera un reintento
+    Pending,
+    /// Se entregó por todos sus canales
+    Fired,
+    /// Venció mientras el servicio estaba detenido y ya no se envía
+    Missed,
+    /// Agotó los intentos sin poder entregarse
+    Failed,
+    /// La tarea ya estaba completada al vencer
+    Cancelled,
+}
+
+impl ReminderStatus {
+    pub const ALL: [ReminderStatus; 5] = [
+        ReminderStatus::Pending,
+        ReminderStatus::Fired,
+        ReminderStatus::Missed,
+        ReminderStatus::Failed,
+        ReminderStatus::Cancelled,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReminderStatus::Pending => "pending",
+            ReminderStatus::Fired => "fired",
+            ReminderStatus::Missed => "missed",
+            ReminderStatus::Failed => "failed",
+            ReminderStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for ReminderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReminderStatus {
+    type Err = String;
+
+    /// El error es el mensaje que se devuelve al cliente
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_variant(&ReminderStatus::ALL, ReminderStatus::as_str, value)
+    }
+}
+
+/// Canal por el que se entrega un recordatorio. En la base de datos es texto (columna
+/// `channels TEXT[]`), con los mismos valores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReminderChannel {
+    /// Una notificación dentro de la aplicación
+    InApp,
+    /// Un POST a `REMINDER_WEBHOOK_URL`
+    Webhook,
+    /// Un correo al email de la cuenta
+    Email,
+}
+
+impl ReminderChannel {
+    pub const ALL: [ReminderChannel; 3] = [ReminderChannel::InApp, ReminderChannel::Webhook, ReminderChannel::Email];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReminderChannel::InApp => "in_app",
+            ReminderChannel::Webhook => "webhook",
+            ReminderChannel::Email => "email",
+        }
+    }
+}
+
+impl fmt::Display for ReminderChannel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ReminderChannel {
+    type Err = String;
+
+    /// El error es el mensaje que se devuelve al cliente
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_variant(&ReminderChannel::ALL, ReminderChannel::as_str, value)
+    }
+}
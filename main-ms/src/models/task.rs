@@ -0,0 +1,89 @@
+// models/task.rs
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+use super::parse_variant;
+
+/// Estado de una tarea (tipo `task_status` en Postgres)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "task_status", rename_all = "lowercase")]
+pub enum TaskStatus {
+    #[default]
+    Todo,
+    Doing,
+    Done,
+}
+
+impl TaskStatus {
+    pub const ALL: [TaskStatus; 3] = [TaskStatus::Todo, TaskStatus::Doing, TaskStatus::Done];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskStatus::Todo => "todo",
+            TaskStatus::Doing => "doing",
+            TaskStatus::Done => "done",
+        }
+    }
+}
+
+impl fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskStatus {
+    type Err = String;
+
+    /// El error es el mensaje que se devuelve al cliente
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_variant(&TaskStatus::ALL, TaskStatus::as_str, value)
+    }
+}
+
+/// Prioridad de una tarea (tipo `task_priority` en Postgres). Las variantes siguen el
+/// orden del enum en la base de datos, de menor a mayor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "task_priority", rename_all = "lowercase")]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Med,
+    High,
+    Urgent,
+}
+
+impl TaskPriority {
+    pub const ALL: [TaskPriority; 4] = [
+        TaskPriority::Low,
+        TaskPriority::Med,
+        TaskPriority::High,
+        TaskPriority::Urgent,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TaskPriority::Low => "low",
+            TaskPriority::Med => "med",
+            TaskPriority::High => "high",
+            TaskPriority::Urgent => "urgent",
+        }
+    }
+}
+
+impl fmt::Display for TaskPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TaskPriority {
+    type Err = String;
+
+    /// El error es el mensaje que se devuelve al cliente
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        parse_variant(&TaskPriority::ALL, TaskPriority::as_str, value)
+    }
+}
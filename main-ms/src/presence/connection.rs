@@ -0,0 +1,385 @@
+// presence/connection.rs
+use axum::extract::ws::{Message, WebSocket};
+use chrono::Utc;
+use futures_util::{
+    stream::{SplitSink, SplitStream},
+    SinkExt, StreamExt,
+};
+use serde::Deserialize;
+use sqlx::PgPool;
+use std::{collections::HashSet, time::Duration};
+use tokio::{
+    sync::broadcast::error::RecvError,
+    time::{Instant, MissedTickBehavior},
+};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::events::{self, ChangeEvent, Signal};
+use crate::utils::auth::authenticate_session;
+
+use super::{Activity, Outbox, Presence, ServerMessage, HUB};
+
+/// Tiempo para enviar el mensaje `auth` cuando no hubo header `Authorization`
+const AUTH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Cada cuánto el servidor envía un ping y vuelve a validar el token
+const PING_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Sin ningún mensaje del cliente (ni pong) en este tiempo, la conexión se cierra y su
+/// presencia desaparece
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Mensajes del cliente; el campo `type` indica cuál es
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage {
+    Auth {
+        token: String,
+    },
+    Subscribe {
+        project_id: Uuid,
+    },
+    Unsubscribe {
+        project_id: Uuid,
+    },
+    /// Sin `task_id` deja la tarea en la que estaba
+    Presence {
+        task_id: Option<Uuid>,
+        activity: Option<Activity>,
+    },
+    Ping,
+}
+
+type Sink = SplitSink<WebSocket, Message>;
+
+/// Token con el que se abrió la conexión y su titular
+pub struct Credentials {
+    pub token: String,
+    pub user_id: Uuid,
+}
+
+/// Atiende una conexión hasta que se cierra. `credentials` es `None` si el cliente debe
+/// autenticarse con un primer mensaje `{"type": "auth", "token": "..."}`, porque los
+/// navegadores no pueden enviar headers al abrir un WebSocket.
+pub async fn run(socket: WebSocket, pool: PgPool, credentials: Option<Credentials>) {
+    let (mut sink, mut stream) = socket.split();
+
+    let Credentials { token, user_id } = match credentials {
+        Some(credentials) => credentials,
+        None => match authenticate(&mut stream).await {
+            Ok(credentials) => credentials,
+            Err(message) => return reject(&mut sink, "unauthorized", message).await,
+        },
+    };
+
+    let (outbox, mut inbox) = Outbox::channel();
+    let mut session = Session {
+        pool,
+        user_id,
+        connection_id: Uuid::new_v4(),
+        outbox: outbox.clone(),
+        subscriptions: HashSet::new(),
+        presence_project: None,
+    };
+
+    let mut changes = events::subscribe();
+    let mut heartbeat = tokio::time::interval(PING_INTERVAL);
+    heartbeat.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut last_seen = Instant::now();
+
+    session.send(ServerMessage::Ready {
+        connection_id: session.connection_id,
+        user_id,
+    });
+
+    loop {
+        tokio::select! {
+            message = stream.next() => {
+                let Some(Ok(message)) = message else {
+                    break;
+                };
+                last_seen = Instant::now();
+
+                match message {
+                    Message::Text(text) => session.handle(text.as_str()).await,
+                    Message::Close(_) => break,
+                    // axum responde los pings; los pongs solo cuentan como actividad
+                    _ => {}
+                }
+            }
+            Some(message) = inbox.recv() => {
+                if send(&mut sink, &message).await.is_err() {
+                    break;
+                }
+            }
+            _ = outbox.overflowed() => {
+                eprintln!("⚠️ La conexión {} no lee sus mensajes; se cierra", session.connection_id);
+                reject(&mut sink, "overflow", "Too many pending messages".to_string()).await;
+                break;
+            }
+            signal = changes.recv() => match signal {
+                Ok(Signal::Event(event)) => session.on_change(&event).await,
+                Ok(Signal::Resync) | Err(RecvError::Lagged(_)) => {
+                    if !session.subscriptions.is_empty() {
+                        session.send(ServerMessage::Resync);
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break;
+                }
+                // La sesión pudo expirar o revocarse después de abrir la conexión
+                if let Err(e) = authenticate_session(&token).await {
+                    reject(&mut sink, "unauthorized", rejection(e)).await;
+                    break;
+                }
+                if sink.send(Message::Ping(Default::default())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    session.close();
+}
+
+async fn authenticate(stream: &mut SplitStream<WebSocket>) -> Result<Credentials, String> {
+    let first = tokio::time::timeout(AUTH_TIMEOUT, stream.next())
+        .await
+        .map_err(|_| "Authentication timed out".to_string())?;
+
+    let Some(Ok(Message::Text(text))) = first else {
+        return Err("Expected an auth message".to_string());
+    };
+
+    match serde_json::from_str::<ClientMessage>(text.as_str()) {
+        Ok(ClientMessage::Auth { token }) => {
+            let user_id = authenticate_session(&token).await.map_err(rejection)?;
+            Ok(Credentials { token, user_id })
+        }
+        _ => Err("The first message must be of type auth".to_string()),
+    }
+}
+
+fn rejection(error: AppError) -> String {
+    match error {
+        AppError::Forbidden(message) | AppError::Unauthorized(message) => message,
+        other => other.to_string(),
+    }
+}
+
+/// Envía el error directamente (la cola puede estar llena) y cierra la conexión
+async fn reject(sink: &mut Sink, code: &'static str, message: String) {
+    let _ = send(sink, &ServerMessage::Error { code, message }).await;
+    let _ = sink.send(Message::Close(None)).await;
+}
+
+async fn send(sink: &mut Sink, message: &ServerMessage) -> Result<(), axum::Error> {
+    let text = serde_json::to_string(message).expect("ServerMessage siempre se puede serializar");
+    sink.send(Message::Text(text.into())).await
+}
+
+struct Session {
+    pool: PgPool,
+    user_id: Uuid,
+    connection_id: Uuid,
+    outbox: Outbox,
+    subscriptions: HashSet<Uuid>,
+    /// Proyecto de la tarea en la que está la conexión
+    presence_project: Option<Uuid>,
+}
+
+impl Session {
+    fn send(&self, message: ServerMessage) {
+        self.outbox.send(message);
+    }
+
+    fn error(&self, code: &'static str, message: impl Into<String>) {
+        self.send(ServerMessage::Error {
+            code,
+            message: message.into(),
+        });
+    }
+
+    async fn handle(&mut self, text: &str) {
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(e) => return self.error("invalid_message", e.to_string()),
+        };
+
+        let result = match message {
+            ClientMessage::Auth { .. } => {
+                self.error("invalid_message", "Already authenticated");
+                Ok(())
+            }
+            ClientMessage::Subscribe { project_id } => self.subscribe(project_id).await,
+            ClientMessage::Unsubscribe { project_id } => {
+                self.unsubscribe(project_id);
+                Ok(())
+            }
+            ClientMessage::Presence { task_id: None, .. } => {
+                self.clear_presence();
+                Ok(())
+            }
+            ClientMessage::Presence {
+                task_id: Some(task_id),
+                activity,
+            } => self.set_presence(task_id, activity.unwrap_or(Activity::Viewing)).await,
+            ClientMessage::Ping => {
+                self.send(ServerMessage::Pong);
+                Ok(())
+            }
+        };
+
+        if let Err(e) = result {
+            eprintln!("⚠️ Error en la conexión {}: {}", self.connection_id, e);
+            self.error("internal_error", "Internal server error");
+        }
+    }
+
+    /// Solo se puede suscribir a proyectos que el usuario ve por la API
+    async fn subscribe(&mut self, project_id: Uuid) -> Result<(), sqlx::Error> {
+        let user_id = self.user_id;
+
+        if !self.subscriptions.contains(&project_id) {
+            let visible: bool = db::as_user(&self.pool, user_id, async |conn| {
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM get_project($1, $2))")
+                    .bind(user_id)
+                    .bind(project_id)
+                    .fetch_one(conn)
+                    .await
+            })
+            .await?;
+
+            if !visible {
+                self.error("not_found", "Project not found");
+                return Ok(());
+            }
+        }
+
+        self.subscriptions.insert(project_id);
+        let presence = HUB.join(project_id, self.connection_id, user_id, self.outbox.clone());
+        self.send(ServerMessage::Subscribed { project_id, presence });
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, project_id: Uuid) {
+        if self.presence_project == Some(project_id) {
+            self.presence_project = None;
+        }
+        if self.subscriptions.remove(&project_id) {
+            HUB.leave(project_id, self.connection_id);
+        }
+        self.send(ServerMessage::Unsubscribed { project_id });
+    }
+
+    fn clear_presence(&mut self) {
+        if let Some(project_id) = self.presence_project.take() {
+            HUB.set_presence(project_id, self.connection_id, None);
+        }
+    }
+
+    /// La tarea debe ser visible para el usuario y estar en un proyecto suscrito
+    async fn set_presence(&mut self, task_id: Uuid, activity: Activity) -> Result<(), sqlx::Error> {
+        let user_id = self.user_id;
+
+        let project_id: Option<Option<Uuid>> = db::as_user(&self.pool, user_id, async |conn| {
+            sqlx::query_scalar("SELECT project_id FROM get_task($1, $2)")
+                .bind(user_id)
+                .bind(task_id)
+                .fetch_optional(conn)
+                .await
+        })
+        .await?;
+
+        let Some(project_id) = project_id else {
+            self.error("not_found", "Task not found");
+            return Ok(());
+        };
+
+        let Some(project_id) = project_id.filter(|project_id| self.subscriptions.contains(project_id)) else {
+            self.error("not_subscribed", "Subscribe to the task's project first");
+            return Ok(());
+        };
+
+        if self.presence_project != Some(project_id) {
+            self.clear_presence();
+        }
+
+        let presence = Presence {
+            connection_id: self.connection_id,
+            user_id,
+            task_id,
+            activity,
+            since: Utc::now(),
+        };
+        HUB.set_presence(project_id, self.connection_id, Some(presence));
+        self.presence_project = Some(project_id);
+        Ok(())
+    }
+
+    /// Reenvía los cambios de las tareas de los proyectos suscritos, con los campos
+    /// guardados. Hoy un proyecto solo lo ve su dueño, así que basta con sus eventos. La
+    /// notificación trae el proyecto de la tarea, así que solo se consulta la tarea si
+    /// está (o estaba) en un proyecto suscrito.
+    async fn on_change(&self, event: &ChangeEvent) {
+        if event.user_id != self.user_id || event.entity != "task" {
+            return;
+        }
+
+        let subscribed = |project_id: Option<Uuid>| {
+            project_id.is_some_and(|project_id| self.subscriptions.contains(&project_id))
+        };
+        if !subscribed(event.project_id) && !subscribed(event.previous_project_id) {
+            return;
+        }
+
+        if event.action == "deleted" {
+            self.send(ServerMessage::TaskChanged {
+                project_id: event.project_id,
+                task_id: event.entity_id,
+                action: event.action.clone(),
+                task: None,
+            });
+            return;
+        }
+
+        let (user_id, task_id) = (self.user_id, event.entity_id);
+        let task = db::as_user(&self.pool, user_id, async |conn| {
+            sqlx::query_as::<_, (Option<Uuid>, serde_json::Value)>("SELECT t.project_id, to_jsonb(t) FROM get_task($1, $2) t")
+                .bind(user_id)
+                .bind(task_id)
+                .fetch_optional(conn)
+                .await
+        })
+        .await;
+
+        match task {
+            Ok(Some((project_id, task))) => {
+                self.send(ServerMessage::TaskChanged {
+                    project_id,
+                    task_id,
+                    action: event.action.clone(),
+                    task: Some(task),
+                });
+            }
+            // Se eliminó entretanto; llega su propio evento
+            Ok(None) => {}
+            Err(e) => {
+                eprintln!("⚠️ No se pudo leer la tarea {} para la conexión {}: {}", task_id, self.connection_id, e);
+                self.send(ServerMessage::Resync);
+            }
+        }
+    }
+
+    /// Sale de todos los proyectos; los demás ven desaparecer su presencia
+    fn close(&mut self) {
+        for project_id in self.subscriptions.drain() {
+            HUB.leave(project_id, self.connection_id);
+        }
+    }
+}
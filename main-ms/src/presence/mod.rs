@@ -0,0 +1,215 @@
+// presence/mod.rs
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+};
+use tokio::sync::{
+    mpsc::{self, error::TrySendError},
+    Notify,
+};
+use uuid::Uuid;
+
+mod connection;
+
+pub use connection::{run, Credentials};
+
+/// Mensajes en cola por conexión. Un cliente que no lee al ritmo en que llegan se
+/// desconecta en lugar de acumularlos en memoria.
+const OUTBOX_CAPACITY: usize = 256;
+
+/// Qué hace una conexión con una tarea
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Activity {
+    Viewing,
+    Editing,
+}
+
+/// Presencia de una conexión en una tarea de un proyecto
+#[derive(Debug, Clone, Serialize)]
+pub struct Presence {
+    pub connection_id: Uuid,
+    pub user_id: Uuid,
+    pub task_id: Uuid,
+    pub activity: Activity,
+    pub since: DateTime<Utc>,
+}
+
+/// Mensajes del servidor; el campo `type` indica cuál es
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Ready {
+        connection_id: Uuid,
+        user_id: Uuid,
+    },
+    Subscribed {
+        project_id: Uuid,
+        /// Quiénes están en el proyecto en este momento
+        presence: Vec<Presence>,
+    },
+    Unsubscribed {
+        project_id: Uuid,
+    },
+    /// Una conexión empezó a ver o editar una tarea; sin `task_id`, la dejó (o se desconectó)
+    Presence {
+        project_id: Uuid,
+        connection_id: Uuid,
+        user_id: Uuid,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        task_id: Option<Uuid>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        activity: Option<Activity>,
+    },
+    /// Una tarea de un proyecto suscrito se creó, cambió (`task` trae sus campos) o se
+    /// eliminó (`task` es `null`). Si salió del proyecto, `project_id` es el nuevo.
+    TaskChanged {
+        project_id: Option<Uuid>,
+        task_id: Uuid,
+        action: String,
+        task: Option<serde_json::Value>,
+    },
+    /// Pudieron perderse cambios: el cliente debe volver a pedir las tareas que muestra
+    Resync,
+    Pong,
+    Error {
+        code: &'static str,
+        message: String,
+    },
+}
+
+/// Cola de mensajes de una conexión, propios o de otras conexiones. Si se llena se avisa
+/// a la conexión (`overflowed`), que se cierra; el mensaje que no cupo se descarta.
+#[derive(Clone)]
+struct Outbox {
+    sender: mpsc::Sender<ServerMessage>,
+    overflow: Arc<Notify>,
+}
+
+impl Outbox {
+    fn channel() -> (Outbox, mpsc::Receiver<ServerMessage>) {
+        let (sender, receiver) = mpsc::channel(OUTBOX_CAPACITY);
+        let outbox = Outbox {
+            sender,
+            overflow: Arc::new(Notify::new()),
+        };
+        (outbox, receiver)
+    }
+
+    /// No espera: una conexión cerrada deja de recibir y la limpia su propia tarea
+    fn send(&self, message: ServerMessage) {
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(message) {
+            self.overflow.notify_one();
+        }
+    }
+
+    /// Termina cuando un mensaje no cupo en la cola
+    async fn overflowed(&self) {
+        self.overflow.notified().await
+    }
+}
+
+struct Member {
+    user_id: Uuid,
+    outbox: Outbox,
+    presence: Option<Presence>,
+}
+
+/// Conexiones suscritas a cada proyecto, con su presencia. Vive en memoria: cada réplica
+/// conoce solo sus propias conexiones.
+#[derive(Default)]
+struct Hub {
+    projects: Mutex<HashMap<Uuid, HashMap<Uuid, Member>>>,
+}
+
+static HUB: LazyLock<Hub> = LazyLock::new(Hub::default);
+
+impl Hub {
+    /// Suscribe la conexión al proyecto y devuelve la presencia actual
+    fn join(&self, project_id: Uuid, connection_id: Uuid, user_id: Uuid, outbox: Outbox) -> Vec<Presence> {
+        let mut projects = self.projects.lock().unwrap();
+        let members = projects.entry(project_id).or_default();
+
+        members.entry(connection_id).or_insert(Member {
+            user_id,
+            outbox,
+            presence: None,
+        });
+
+        members.values().filter_map(|member| member.presence.clone()).collect()
+    }
+
+    /// Quita la conexión del proyecto y avisa a los demás si estaba en alguna tarea
+    fn leave(&self, project_id: Uuid, connection_id: Uuid) {
+        let mut projects = self.projects.lock().unwrap();
+        let Some(members) = projects.get_mut(&project_id) else {
+            return;
+        };
+
+        if let Some(member) = members.remove(&connection_id) {
+            if member.presence.is_some() {
+                broadcast(members, cleared(project_id, connection_id, member.user_id));
+            }
+        }
+
+        if members.is_empty() {
+            projects.remove(&project_id);
+        }
+    }
+
+    /// Cambia la presencia de la conexión en el proyecto (`None` la quita) y la envía a las
+    /// demás conexiones suscritas
+    fn set_presence(&self, project_id: Uuid, connection_id: Uuid, presence: Option<Presence>) {
+        let mut projects = self.projects.lock().unwrap();
+        let Some(members) = projects.get_mut(&project_id) else {
+            return;
+        };
+        let Some(member) = members.get_mut(&connection_id) else {
+            return;
+        };
+
+        if member.presence.is_none() && presence.is_none() {
+            return;
+        }
+
+        let message = match &presence {
+            Some(presence) => ServerMessage::Presence {
+                project_id,
+                connection_id,
+                user_id: presence.user_id,
+                task_id: Some(presence.task_id),
+                activity: Some(presence.activity),
+            },
+            None => cleared(project_id, connection_id, member.user_id),
+        };
+        member.presence = presence;
+
+        broadcast(members, message);
+    }
+}
+
+fn cleared(project_id: Uuid, connection_id: Uuid, user_id: Uuid) -> ServerMessage {
+    ServerMessage::Presence {
+        project_id,
+        connection_id,
+        user_id,
+        task_id: None,
+        activity: None,
+    }
+}
+
+/// Envía a todas las conexiones del proyecto salvo la que originó el mensaje
+fn broadcast(members: &HashMap<Uuid, Member>, message: ServerMessage) {
+    let origin = match &message {
+        ServerMessage::Presence { connection_id, .. } => Some(*connection_id),
+        _ => None,
+    };
+
+    for (connection_id, member) in members {
+        if Some(*connection_id) != origin {
+            member.outbox.send(message.clone());
+        }
+    }
+}
@@ -0,0 +1,156 @@
+// reminders/channels.rs
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde_json::json;
+use sqlx::PgPool;
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use crate::config::Config;
+use crate::db;
+use crate::mailer::{self, Email, MailError, Mailer};
+use crate::models::{NotificationEvent, ReminderChannel};
+
+use super::DueReminder;
+
+/// Tiempo máximo de espera de una llamada al webhook
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum DeliveryError {
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+    #[error("webhook request failed: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("webhook responded with status {0}")]
+    WebhookStatus(StatusCode),
+    #[error("mail error: {0}")]
+    Mail(#[from] MailError),
+    #[error("reminder has no email address")]
+    MissingEmail,
+    #[error("channel is not configured")]
+    NotConfigured,
+}
+
+/// Un medio de entrega de recordatorios. Para agregar uno nuevo basta con implementar este
+/// trait, agregar la variante a `ReminderChannel` (y a la restricción
+/// `task_reminders_channels_check`) y registrarlo en `Channels::from_config`.
+#[async_trait]
+pub trait Channel: Send + Sync {
+    async fn deliver(&self, reminder: &DueReminder) -> Result<(), DeliveryError>;
+}
+
+/// Canales disponibles en este proceso
+pub struct Channels(HashMap<ReminderChannel, Box<dyn Channel>>);
+
+impl Channels {
+    pub fn from_config(pool: PgPool, config: &Config) -> Self {
+        let mut channels: HashMap<ReminderChannel, Box<dyn Channel>> = HashMap::new();
+
+        channels.insert(ReminderChannel::InApp, Box::new(InAppChannel { pool }));
+        channels.insert(
+            ReminderChannel::Email,
+            Box::new(EmailChannel {
+                mailer: mailer::from_config(&config.mail),
+            }),
+        );
+
+        if config.reminders.webhook_enabled() {
+            let client = Client::builder()
+                .timeout(WEBHOOK_TIMEOUT)
+                .build()
+                .expect("No se pudo crear el cliente HTTP del webhook");
+
+            channels.insert(
+                ReminderChannel::Webhook,
+                Box::new(WebhookChannel {
+                    client,
+                    url: config.reminders.webhook_url.clone(),
+                }),
+            );
+        }
+
+        Self(channels)
+    }
+
+    pub async fn deliver(&self, channel: ReminderChannel, reminder: &DueReminder) -> Result<(), DeliveryError> {
+        match self.0.get(&channel) {
+            Some(channel) => channel.deliver(reminder).await,
+            None => Err(DeliveryError::NotConfigured),
+        }
+    }
+}
+
+/// Crea una notificación para el dueño de la tarea, salvo que haya desactivado las de
+/// recordatorios (el recordatorio cuenta igual como entregado)
+struct InAppChannel {
+    pool: PgPool,
+}
+
+#[async_trait]
+impl Channel for InAppChannel {
+    async fn deliver(&self, reminder: &DueReminder) -> Result<(), DeliveryError> {
+        db::as_user(&self.pool, reminder.user_id, async |conn| {
+            sqlx::query("SELECT create_notification($1, $2, $3, $4, $5)")
+                .bind(reminder.user_id)
+                .bind(NotificationEvent::Reminder.as_str())
+                .bind(&reminder.task_title)
+                .bind(reminder.summary())
+                .bind(reminder.task_id)
+                .execute(conn)
+                .await
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Envía el recordatorio como JSON a `REMINDER_WEBHOOK_URL`; cualquier respuesta que no
+/// sea 2xx cuenta como fallo y se reintenta
+struct WebhookChannel {
+    client: Client,
+    url: String,
+}
+
+#[async_trait]
+impl Channel for WebhookChannel {
+    async fn deliver(&self, reminder: &DueReminder) -> Result<(), DeliveryError> {
+        let payload = json!({
+            "event": "reminder.fired",
+            "reminder_id": reminder.id,
+            "user_id": reminder.user_id,
+            "task_id": reminder.task_id,
+            "task_title": reminder.task_title,
+            "due_date": reminder.due_date,
+            "fire_at": reminder.fire_at,
+        });
+
+        let response = self.client.post(&self.url).json(&payload).send().await?;
+        if !response.status().is_success() {
+            return Err(DeliveryError::WebhookStatus(response.status()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Envía un correo al email que se guardó al crear el recordatorio
+struct EmailChannel {
+    mailer: Arc<dyn Mailer>,
+}
+
+#[async_trait]
+impl Channel for EmailChannel {
+    async fn deliver(&self, reminder: &DueReminder) -> Result<(), DeliveryError> {
+        let to = reminder.email.clone().ok_or(DeliveryError::MissingEmail)?;
+
+        let email = Email {
+            to,
+            subject: format!("Recordatorio: {}", reminder.task_title),
+            body: reminder.summary(),
+        };
+        self.mailer.send(&email).await?;
+
+        Ok(())
+    }
+}
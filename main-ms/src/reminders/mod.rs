@@ -0,0 +1,221 @@
+// reminders/mod.rs
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use uuid::Uuid;
+
+use crate::config::{Config, RemindersConfig};
+use crate::db;
+use crate::models::{ReminderChannel, ReminderStatus, TaskStatus};
+
+mod channels;
+mod overdue;
+
+use channels::Channels;
+
+/// Recordatorios que se toman en cada consulta
+const BATCH_SIZE: i32 = 50;
+
+/// Tiempo que un planificador tiene para entregar un recordatorio antes de que otro pueda
+/// tomarlo de nuevo
+const LEASE: Duration = Duration::from_secs(300);
+
+/// Espera máxima entre reintentos
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Recordatorio vencido, con los datos de su tarea
+#[derive(Debug, sqlx::FromRow)]
+pub struct DueReminder {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub task_id: Uuid,
+    pub task_title: String,
+    pub task_status: TaskStatus,
+    pub due_date: Option<DateTime<Utc>>,
+    pub fire_at: Option<DateTime<Utc>>,
+    pub channels: Vec<String>,
+    pub delivered_channels: Vec<String>,
+    pub email: Option<String>,
+    pub attempts: i32,
+}
+
+impl DueReminder {
+    /// Texto de la notificación y del correo
+    pub fn summary(&self) -> String {
+        match self.due_date {
+            Some(due_date) => format!(
+                "La tarea \"{}\" vence el {}",
+                self.task_title,
+                due_date.format("%Y-%m-%d %H:%M UTC")
+            ),
+            None => format!("Recordatorio de la tarea \"{}\"", self.task_title),
+        }
+    }
+}
+
+/// Inicia el planificador de recordatorios en segundo plano, salvo que esté desactivado
+/// (`REMINDERS_ENABLED=false`). También avisa de las tareas vencidas. El estado vive en la
+/// base de datos, así que tras un reinicio retoma los pendientes.
+pub fn spawn(pool: PgPool, config: &'static Config) {
+    if !config.reminders.enabled {
+        println!("⏰ Planificador de recordatorios desactivado");
+        return;
+    }
+
+    let scheduler = Scheduler {
+        channels: Channels::from_config(pool.clone(), config),
+        pool,
+        config: &config.reminders,
+    };
+
+    println!(
+        "⏰ Planificador de recordatorios activo (cada {} s)",
+        config.reminders.poll_interval_secs
+    );
+    tokio::spawn(scheduler.run());
+}
+
+struct Scheduler {
+    pool: PgPool,
+    channels: Channels,
+    config: &'static RemindersConfig,
+}
+
+impl Scheduler {
+    async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.config.poll_interval_secs));
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(e) = self.fire_due().await {
+                eprintln!("⚠️ No se pudieron consultar los recordatorios vencidos: {}", e);
+            }
+
+            if let Err(e) = self.notify_overdue().await {
+                eprintln!("⚠️ No se pudieron consultar las tareas vencidas: {}", e);
+            }
+        }
+    }
+
+    /// Dispara los recordatorios vencidos, por tandas hasta que no quede ninguno
+    async fn fire_due(&self) -> Result<(), sqlx::Error> {
+        loop {
+            let claimed = sqlx::query_as::<_, (Uuid, Uuid)>(
+                "SELECT reminder_id, user_id FROM claim_due_reminders($1, $2, $3)",
+            )
+            .bind(BATCH_SIZE)
+            .bind(LEASE)
+            .bind(Duration::from_secs(self.config.missed_after_secs))
+            .fetch_all(&self.pool)
+            .await?;
+
+            for &(reminder_id, user_id) in &claimed {
+                // Un error en uno no detiene a los demás; el lease vence y se reintenta
+                if let Err(e) = self.fire(user_id, reminder_id).await {
+                    eprintln!("⚠️ No se pudo disparar el recordatorio {}: {}", reminder_id, e);
+                }
+            }
+
+            if claimed.len() < BATCH_SIZE as usize {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn fire(&self, user_id: Uuid, reminder_id: Uuid) -> Result<(), sqlx::Error> {
+        let reminder = db::as_user(&self.pool, user_id, async |conn| {
+            sqlx::query_as::<_, DueReminder>(
+                r#"
+                SELECT r.id, r.user_id, r.task_id, t.title AS task_title, t.status AS task_status,
+                       t.due_date, r.fire_at, r.channels, r.delivered_channels, r.email, r.attempts
+                FROM get_task_reminder($1, $2) r
+                CROSS JOIN LATERAL get_task($1, r.task_id) t
+                "#,
+            )
+            .bind(user_id)
+            .bind(reminder_id)
+            .fetch_optional(conn)
+            .await
+        })
+        .await?;
+
+        // Se borró (o se borró su tarea) después de tomarlo
+        let Some(reminder) = reminder else {
+            return Ok(());
+        };
+
+        if reminder.task_status == TaskStatus::Done {
+            return self
+                .record(&reminder, ReminderStatus::Cancelled, &reminder.delivered_channels, None, None)
+                .await;
+        }
+
+        let mut delivered = reminder.delivered_channels.clone();
+        let mut errors = Vec::new();
+
+        for channel in &reminder.channels {
+            if delivered.contains(channel) {
+                continue;
+            }
+
+            let result = match channel.parse::<ReminderChannel>() {
+                Ok(channel) => self.channels.deliver(channel, &reminder).await.map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(()) => delivered.push(channel.clone()),
+                Err(e) => errors.push(format!("{}: {}", channel, e)),
+            }
+        }
+
+        if errors.is_empty() {
+            return self.record(&reminder, ReminderStatus::Fired, &delivered, None, None).await;
+        }
+
+        let error = errors.join("; ");
+        eprintln!("⚠️ Recordatorio {} (intento {}): {}", reminder.id, reminder.attempts, error);
+
+        if reminder.attempts >= self.config.max_attempts as i32 {
+            self.record(&reminder, ReminderStatus::Failed, &delivered, Some(&error), None)
+                .await
+        } else {
+            let retry_at = Utc::now() + retry_delay(reminder.attempts);
+            self.record(&reminder, ReminderStatus::Pending, &delivered, Some(&error), Some(retry_at))
+                .await
+        }
+    }
+
+    async fn record(
+        &self,
+        reminder: &DueReminder,
+        status: ReminderStatus,
+        delivered_channels: &[String],
+        error: Option<&str>,
+        retry_at: Option<DateTime<Utc>>,
+    ) -> Result<(), sqlx::Error> {
+        db::as_user(&self.pool, reminder.user_id, async |conn| {
+            sqlx::query("SELECT record_reminder_delivery($1, $2, $3, $4, $5, $6)")
+                .bind(reminder.user_id)
+                .bind(reminder.id)
+                .bind(status)
+                .bind(delivered_channels)
+                .bind(error)
+                .bind(retry_at)
+                .execute(conn)
+                .await
+        })
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Espera antes del siguiente intento: 1 minuto, y el doble en cada fallo hasta una hora
+fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    Duration::from_secs(60 * 2u64.pow(exponent)).min(MAX_RETRY_DELAY)
+}
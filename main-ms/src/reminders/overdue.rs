@@ -0,0 +1,56 @@
+// reminders/overdue.rs
+use std::time::Duration;
+use uuid::Uuid;
+
+use crate::db;
+use crate::models::NotificationEvent;
+
+use super::{Scheduler, BATCH_SIZE};
+
+impl Scheduler {
+    /// Crea una notificación `task_overdue` por cada tarea que venció sin completarse. Cada
+    /// tarea se avisa una vez por fecha límite; `create_notification` la descarta si el
+    /// usuario desactivó ese tipo de evento.
+    pub(super) async fn notify_overdue(&self) -> Result<(), sqlx::Error> {
+        loop {
+            let claimed = sqlx::query_as::<_, (Uuid, Uuid)>("SELECT task_id, user_id FROM claim_overdue_tasks($1, $2)")
+                .bind(BATCH_SIZE)
+                .bind(Duration::from_secs(self.config.missed_after_secs))
+                .fetch_all(&self.pool)
+                .await?;
+
+            for &(task_id, user_id) in &claimed {
+                // Ya quedó marcada como avisada: si esto falla no se reintenta
+                if let Err(e) = self.notify(user_id, task_id).await {
+                    eprintln!("⚠️ No se pudo avisar de la tarea vencida {}: {}", task_id, e);
+                }
+            }
+
+            if claimed.len() < BATCH_SIZE as usize {
+                return Ok(());
+            }
+        }
+    }
+
+    async fn notify(&self, user_id: Uuid, task_id: Uuid) -> Result<(), sqlx::Error> {
+        db::as_user(&self.pool, user_id, async |conn| {
+            sqlx::query(
+                r#"
+                SELECT create_notification($1, $2, t.title,
+                       format('La tarea "%s" venció el %s', t.title,
+                              to_char(t.due_date AT TIME ZONE 'UTC', 'YYYY-MM-DD HH24:MI "UTC"')),
+                       t.id)
+                FROM get_task($1, $3) t
+                "#,
+            )
+            .bind(user_id)
+            .bind(NotificationEvent::TaskOverdue.as_str())
+            .bind(task_id)
+            .execute(conn)
+            .await
+        })
+        .await?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,45 @@
+// routes/access_tokens.rs
+use axum::{
+    http::HeaderMap,
+    response::Response,
+    routing::{delete, get},
+    Router,
+};
+use serde_json::Value;
+use reqwest::Method;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::{auth_service, extract_token::authorization_header};
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(base, get(list).post(create))
+        .route(&format!("{}/{{token_id}}", base), delete(revoke))
+}
+
+async fn create(headers: HeaderMap, Json(payload): Json<Value>) -> Result<Response, AppError> {
+    forward_authenticated(Method::POST, "/api/tokens".to_string(), &headers, Some(&payload)).await
+}
+
+async fn list(headers: HeaderMap) -> Result<Response, AppError> {
+    forward_authenticated(Method::GET, "/api/tokens".to_string(), &headers, None).await
+}
+
+async fn revoke(headers: HeaderMap, Path(token_id): Path<Uuid>) -> Result<Response, AppError> {
+    forward_authenticated(Method::DELETE, format!("/api/tokens/{}", token_id), &headers, None).await
+}
+
+// La gestión de tokens requiere la sesión del usuario; auth-ms rechaza los tokens de acceso personal
+async fn forward_authenticated(
+    method: Method,
+    path: String,
+    headers: &HeaderMap,
+    payload: Option<&Value>,
+) -> Result<Response, AppError> {
+    let authorization = authorization_header(headers)?;
+
+    auth_service::forward_request(method, &path, Some(authorization), payload).await
+}
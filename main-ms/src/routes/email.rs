@@ -0,0 +1,26 @@
+// routes/email.rs
+use axum::{
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::auth_service;
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(&format!("{}/verify", base), post(verify_email))
+        .route(&format!("{}/resend", base), post(resend_verification))
+}
+
+async fn verify_email(Json(payload): Json<Value>) -> Result<Response, AppError> {
+    auth_service::forward("/api/email/verify", None, &payload).await
+}
+
+async fn resend_verification(Json(payload): Json<Value>) -> Result<Response, AppError> {
+    auth_service::forward("/api/email/resend", None, &payload).await
+}
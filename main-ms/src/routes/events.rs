@@ -0,0 +1,153 @@
+// routes/events.rs
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+    routing::get,
+    Router,
+};
+use futures_util::stream::{self, Stream};
+use sqlx::PgPool;
+use std::{collections::VecDeque, convert::Infallible};
+use tokio::sync::broadcast::{self, error::RecvError};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::events::{self, ChangeEvent, Signal};
+use crate::utils::auth::CurrentUser;
+
+/// Eventos que se leen de la base de datos por consulta al retomar
+const REPLAY_BATCH: i32 = 500;
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new().route(base, get(get_events))
+}
+
+// Endpoint SSE con los cambios de tareas, proyectos, etiquetas y vínculos tarea-etiqueta del
+// usuario. Con `Last-Event-ID` envía primero los eventos posteriores a ese id; sin él, solo
+// los nuevos.
+async fn get_events(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, AppError> {
+    let last_event_id = headers
+        .get("last-event-id")
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|id| *id >= 0)
+                .ok_or_else(|| AppError::invalid_field("Last-Event-ID", "must be a non-negative integer"))
+        })
+        .transpose()?;
+
+    // Suscribirse antes de consultar para no perder lo que ocurra entre medio
+    let signals = events::subscribe();
+
+    let (last_id, catching_up) = match last_event_id {
+        Some(id) => (id, true),
+        None => {
+            let id: i64 = db::as_user(&pool, user_id, async |conn| {
+                sqlx::query_scalar("SELECT get_last_change_event_id($1)")
+                    .bind(user_id)
+                    .fetch_one(conn)
+                    .await
+            })
+            .await?;
+            (id, false)
+        }
+    };
+
+    let state = EventStream {
+        pool,
+        user_id,
+        signals,
+        last_id,
+        catching_up,
+        pending: VecDeque::new(),
+    };
+
+    let stream = stream::unfold(state, |mut state| async move {
+        let event = state.next().await?;
+        Some((Ok(to_sse(&event)), state))
+    });
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Estado de un stream: los eventos se envían en orden y sin repetir (`id > last_id`). Si
+/// pudo perderse alguno (el suscriptor se atrasó o se cayó el listener), se leen de la
+/// base de datos desde `last_id`.
+struct EventStream {
+    pool: PgPool,
+    user_id: Uuid,
+    signals: broadcast::Receiver<Signal>,
+    last_id: i64,
+    /// Quedan eventos en la base de datos por leer
+    catching_up: bool,
+    pending: VecDeque<ChangeEvent>,
+}
+
+impl EventStream {
+    /// Siguiente evento; `None` termina el stream
+    async fn next(&mut self) -> Option<ChangeEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                if event.id > self.last_id {
+                    self.last_id = event.id;
+                    return Some(event);
+                }
+                continue;
+            }
+
+            if self.catching_up {
+                if let Err(e) = self.replay().await {
+                    // El cliente reconecta con Last-Event-ID y retoma desde aquí
+                    eprintln!("⚠️ No se pudieron leer los eventos del usuario {}: {}", self.user_id, e);
+                    return None;
+                }
+                continue;
+            }
+
+            match self.signals.recv().await {
+                Ok(Signal::Event(event)) => {
+                    if event.user_id == self.user_id {
+                        self.pending.push_back(ChangeEvent::clone(&event));
+                    }
+                }
+                Ok(Signal::Resync) | Err(RecvError::Lagged(_)) => self.catching_up = true,
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+
+    async fn replay(&mut self) -> Result<(), sqlx::Error> {
+        let (user_id, last_id) = (self.user_id, self.last_id);
+
+        let events = db::as_user(&self.pool, user_id, async |conn| {
+            sqlx::query_as::<_, ChangeEvent>("SELECT * FROM get_change_events($1, $2, $3)")
+                .bind(user_id)
+                .bind(last_id)
+                .bind(REPLAY_BATCH)
+                .fetch_all(conn)
+                .await
+        })
+        .await?;
+
+        self.catching_up = events.len() == REPLAY_BATCH as usize;
+        self.pending.extend(events);
+        Ok(())
+    }
+}
+
+/// El tipo del evento SSE es `{entidad}.{acción}`, por ejemplo `task.updated`
+fn to_sse(event: &ChangeEvent) -> Event {
+    Event::default()
+        .id(event.id.to_string())
+        .event(format!("{}.{}", event.entity, event.action))
+        .json_data(event)
+        .expect("ChangeEvent siempre se puede serializar")
+}
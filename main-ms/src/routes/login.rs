@@ -1,15 +1,21 @@
 // routes/login.rs
 use axum::{
-    extract::State,
-    response::{Json, IntoResponse},
+    extract::{ConnectInfo, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
     routing::post,
     Router,
 };
 use serde::{Deserialize, Serialize};
-use reqwest::{Client, StatusCode};
+use reqwest::Client;
 use sqlx::PgPool;
 use uuid::Uuid;
-use std::env;
+use std::net::SocketAddr;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::auth_service;
 
 #[derive(Debug, Deserialize)]
 struct LoginRequest {
@@ -20,111 +26,129 @@ struct LoginRequest {
 #[derive(Debug, Serialize)]
 struct LoginResponse {
     token: String,
+    refresh_token: String,
+    expires_in: i64,
     user_id: Uuid,
+    email_verified: bool,
     message: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    error: String,
+#[derive(Debug, Deserialize)]
+struct TwoFactorLoginRequest {
+    challenge_token: String,
+    code: String,
+}
+
+/// Respuesta de auth-ms cuando el usuario tiene 2FA activo: se reenvía tal cual
+#[derive(Debug, Serialize, Deserialize)]
+struct TwoFactorChallenge {
+    two_factor_required: bool,
+    challenge_token: String,
+    expires_in: i64,
 }
 
 #[derive(Debug, Deserialize)]
 struct AuthResponse {
     user: UserResponse,
     token: String,
+    refresh_token: String,
+    expires_in: i64,
 }
 
 #[derive(Debug, Deserialize)]
-struct AuthErrorResponse {
-    error: String,
+#[serde(untagged)]
+enum AuthServiceResponse {
+    Authenticated(AuthResponse),
+    TwoFactorRequired(TwoFactorChallenge),
 }
 
 #[derive(Debug, Deserialize)]
 struct UserResponse {
     id: Uuid,
-    email: String,
-    created_at: String,
+    email_verified: bool,
 }
 
 pub fn routes(base: &str) -> Router<PgPool> {
-    Router::new().route(&format!("{}", base), post(login))
+    Router::new()
+        .route(base, post(login))
+        .route(&format!("{}/2fa", base), post(login_two_factor))
 }
 
 async fn login(
     State(_pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(payload): Json<LoginRequest>,
-) -> impl IntoResponse {
-    // Obtener la URL del microservicio de autenticación desde las variables de entorno
-    let auth_service_url = env::var("AUTH_MICROSERVICE_URL")
-        .expect("AUTH_MICROSERVICE_URL must be set in .env file");
+) -> Result<Response, AppError> {
+    let body = serde_json::json!({
+        "email": payload.email,
+        "password": payload.password
+    });
+
+    authenticate("/api/login", addr, &headers, &body).await
+}
+
+// Segundo paso del login cuando el usuario tiene 2FA activo
+async fn login_two_factor(
+    State(_pool): State<PgPool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(payload): Json<TwoFactorLoginRequest>,
+) -> Result<Response, AppError> {
+    let body = serde_json::json!({
+        "challenge_token": payload.challenge_token,
+        "code": payload.code
+    });
+
+    authenticate("/api/login/2fa", addr, &headers, &body).await
+}
+
+async fn authenticate(
+    path: &str,
+    addr: SocketAddr,
+    headers: &HeaderMap,
+    body: &serde_json::Value,
+) -> Result<Response, AppError> {
+    // URL del microservicio de autenticación (validada al arrancar)
+    let auth_service_url = &Config::get().auth_service.url;
     
-    let login_url = format!("{}/api/login", auth_service_url);
+    let login_url = format!("{}{}", auth_service_url, path);
+
+    // auth-ms limita los intentos por dirección del cliente: se agrega la nuestra a la cadena
+    let forwarded_for = match headers.get("X-Forwarded-For").and_then(|h| h.to_str().ok()) {
+        Some(chain) => format!("{}, {}", chain, addr.ip()),
+        None => addr.ip().to_string(),
+    };
 
     // Autenticar usuario en el microservicio de autenticación
     let client = Client::new();
     let response = client
         .post(&login_url)
         .header("Content-Type", "application/json")
-        .json(&serde_json::json!({
-            "email": payload.email,
-            "password": payload.password
-        }))
+        .header("X-Forwarded-For", forwarded_for)
+        .json(body)
         .send()
         .await;
 
-    let response = match response {
-        Ok(resp) => resp,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { 
-                    error: format!("Error calling auth service: {}", e) 
-                })
-            ).into_response()
-        }
-    };
+    let response = response?;
 
-    // Verificar el status code de la respuesta
-    let status = response.status();
-    
-    if status.is_success() {
-        // Si es exitoso, parsear la respuesta
-        match response.json::<AuthResponse>().await {
-            Ok(auth_response) => {
-                let response = LoginResponse {
-                    token: auth_response.token,
-                    user_id: auth_response.user.id,
-                    message: "Login exitoso".to_string(),
-                };
-                Json(response).into_response()
-            }
-            Err(e) => {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { 
-                        error: format!("Error parsing auth response: {}", e) 
-                    })
-                ).into_response()
-            }
-        }
-    } else {
-        // Si hay error, parsear el mensaje de error
-        match response.json::<AuthErrorResponse>().await {
-            Ok(error_response) => {
-                let status_code = StatusCode::from_u16(status.as_u16())
-                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-                
-                (status_code, Json(ErrorResponse { error: error_response.error })).into_response()
-            }
-            Err(e) => {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { 
-                        error: format!("Error parsing auth error response: {}", e) 
-                    })
-                ).into_response()
-            }
+    if !response.status().is_success() {
+        // Un login bloqueado (429) conserva `Retry-After`
+        return Err(auth_service::error_from_response(response).await);
+    }
+
+    match response.json::<AuthServiceResponse>().await? {
+        AuthServiceResponse::TwoFactorRequired(challenge) => Ok(Json(challenge).into_response()),
+        AuthServiceResponse::Authenticated(auth_response) => {
+            let response = LoginResponse {
+                token: auth_response.token,
+                refresh_token: auth_response.refresh_token,
+                expires_in: auth_response.expires_in,
+                user_id: auth_response.user.id,
+                email_verified: auth_response.user.email_verified,
+                message: "Login exitoso".to_string(),
+            };
+            Ok(Json(response).into_response())
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,58 @@
+// routes/logout.rs
+use axum::{
+    http::HeaderMap,
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use reqwest::Client;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::{auth_service, extract_token::authorization_header};
+
+#[derive(Debug, Serialize, Deserialize)]
+struct LogoutResponse {
+    message: String,
+    revoked_sessions: i32,
+}
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(base, post(logout))
+        .route(&format!("{}-all", base), post(logout_all))
+}
+
+async fn logout(headers: HeaderMap) -> Result<Json<LogoutResponse>, AppError> {
+    forward_to_auth_service("/api/logout", &headers).await
+}
+
+async fn logout_all(headers: HeaderMap) -> Result<Json<LogoutResponse>, AppError> {
+    forward_to_auth_service("/api/logout-all", &headers).await
+}
+
+// Reenvía la petición al microservicio de autenticación con el mismo token
+async fn forward_to_auth_service(path: &str, headers: &HeaderMap) -> Result<Json<LogoutResponse>, AppError> {
+    let authorization = authorization_header(headers)?;
+
+    // URL del microservicio de autenticación (validada al arrancar)
+    let auth_service_url = &Config::get().auth_service.url;
+
+    let client = Client::new();
+    let response = client
+        .post(format!("{}{}", auth_service_url, path))
+        .header("Authorization", authorization)
+        .send()
+        .await;
+
+    let response = response?;
+
+    if !response.status().is_success() {
+        return Err(auth_service::error_from_response(response).await);
+    }
+
+    let logout_response = response.json::<LogoutResponse>().await?;
+    Ok(Json(logout_response))
+}
@@ -1,7 +1,6 @@
 use axum::{
     extract::State,
-    http::{HeaderMap, StatusCode},
-    response::Json,
+    http::HeaderMap,
     routing::get,
     Router,
 };
@@ -11,6 +10,11 @@ use reqwest::Client;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::extract::Json;
+
 pub fn routes(base: &str) -> Router<PgPool> {
     Router::new().route(&format!("{}/", base), get(get_me))
 }
@@ -18,26 +22,25 @@ pub fn routes(base: &str) -> Router<PgPool> {
 async fn get_me(
     State(pool): State<PgPool>,
     headers: HeaderMap,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<Json<serde_json::Value>, AppError> {
     println!("🔍 [DEBUG] Iniciando endpoint /me");
     
     // Verificar el header de autorización
     let auth_header = headers
         .get("Authorization")
         .and_then(|h| h.to_str().ok())
-        .ok_or((StatusCode::UNAUTHORIZED, Json(json!({"error": "No token provided"}))))?;
+        .ok_or_else(|| AppError::Unauthorized("No token provided".to_string()))?;
 
     println!("🔍 [DEBUG] Authorization header encontrado");
 
     let token = auth_header
         .strip_prefix("Bearer ")
-        .ok_or((StatusCode::UNAUTHORIZED, Json(json!({"error": "Invalid token format"}))))?;
+        .ok_or_else(|| AppError::Unauthorized("Invalid token format".to_string()))?;
 
     println!("🔍 [DEBUG] Token extraído: {}", token);
 
     // Consultar el microservicio de autenticación
-    let auth_url = std::env::var("AUTH_MICROSERVICE_URL")
-        .unwrap_or_else(|_| "http://localhost:3000".to_string());
+    let auth_url = &Config::get().auth_service.url;
     
     println!("🔍 [DEBUG] URL del microservicio de auth: {}", auth_url);
 
@@ -52,20 +55,14 @@ async fn get_me(
         .await
         .map_err(|e| {
             println!("❌ [ERROR] Error al contactar auth service: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to contact auth service: {}", e)})),
-            )
+            AppError::from(e)
         })?;
 
     println!("🔍 [DEBUG] Response status del auth service: {}", auth_response.status());
 
     if !auth_response.status().is_success() {
         println!("❌ [ERROR] Token inválido según auth service. Status: {}", auth_response.status());
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({"error": "Invalid token"})),
-        ));
+        return Err(AppError::Unauthorized("Invalid token".to_string()));
     }
 
     let mut auth_data: serde_json::Value = auth_response
@@ -73,10 +70,7 @@ async fn get_me(
         .await
         .map_err(|e| {
             println!("❌ [ERROR] Error al parsear response del auth service: {}", e);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to parse auth response: {}", e)})),
-            )
+            AppError::from(e)
         })?;
 
     println!("🔍 [DEBUG] Datos del auth service: {:?}", auth_data);
@@ -84,14 +78,14 @@ async fn get_me(
     // Extraer user_id del response del microservicio
     let user_id_str = auth_data["id"]
         .as_str()
-        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Invalid user ID in auth response"}))))?;
+        .ok_or_else(|| AppError::AuthServiceUnavailable("Invalid user ID in auth response".to_string()))?;
 
     println!("🔍 [DEBUG] User ID del auth response: {}", user_id_str);
 
     let user_id_uuid = Uuid::parse_str(user_id_str)
         .map_err(|e| {
             println!("❌ [ERROR] Error al parsear UUID: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(json!({"error": "Invalid UUID format"})))
+            AppError::AuthServiceUnavailable(format!("Invalid UUID format: {}", e))
         })?;
 
     println!("🔍 [DEBUG] User ID parseado como UUID: {}", user_id_uuid);
@@ -100,18 +94,16 @@ async fn get_me(
     println!("🔍 [DEBUG] Consultando base de datos para user_id: {}", user_id_uuid);
     
     // Usar query en lugar de query! para evitar verificación en tiempo de compilación
-    let profile_row = sqlx::query(
-        "SELECT id, user_id, name, created_at FROM get_user_profile($1)"
-    )
-    .bind(user_id_uuid)
-    .fetch_optional(&pool)
+    let profile_row = db::as_user(&pool, user_id_uuid, async |conn| {
+        sqlx::query("SELECT id, user_id, name, created_at FROM get_user_profile($1)")
+            .bind(user_id_uuid)
+            .fetch_optional(conn)
+            .await
+    })
     .await
     .map_err(|e| {
         println!("❌ [ERROR] Error en consulta a la base de datos: {}", e);
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": format!("Database error: {}", e)})),
-        )
+        AppError::from(e)
     })?;
 
     println!("🔍 [DEBUG] Resultado de la consulta a BD: {:?}", profile_row);
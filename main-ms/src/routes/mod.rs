@@ -1,14 +1,28 @@
-use axum::Router;
+use axum::{Extension, Router};
 use sqlx::PgPool;
 
+use crate::utils::auth::ScopedResource;
+
 // Rutas
 pub mod tasks;
 pub mod tags;
 pub mod me;
 pub mod register;
 pub mod login;
+pub mod refresh;
+pub mod logout;
+pub mod password;
+pub mod email;
+pub mod two_factor;
+pub mod access_tokens;
 pub mod projects;
 pub mod task_tags;
+pub mod task_dependencies;
+pub mod reminders;
+pub mod notifications;
+pub mod events;
+pub mod ws;
+pub mod sync;
 
 pub fn app() -> Router<PgPool> {
     Router::new()
@@ -17,12 +31,27 @@ pub fn app() -> Router<PgPool> {
 
 fn api_routes() -> Router<PgPool> {
     Router::new()
-        .merge(tasks::routes("/tasks"))
-        .merge(tags::routes("/tags"))
+        // El recurso determina el scope que necesita un token de acceso personal
+        .merge(tasks::routes("/tasks").route_layer(Extension(ScopedResource("tasks"))))
+        .merge(tags::routes("/tags").route_layer(Extension(ScopedResource("tags"))))
         .merge(register::routes("/auth/register"))
         .merge(login::routes("/auth/login"))
+        .merge(refresh::routes("/auth/refresh"))
+        .merge(logout::routes("/auth/logout"))
+        .merge(password::routes("/auth/password"))
+        .merge(email::routes("/auth/email"))
+        .merge(two_factor::routes("/auth/2fa"))
+        .merge(access_tokens::routes("/auth/tokens"))
         .merge(me::routes("/me"))
-        .merge(projects::routes("/projects"))
-        .merge(task_tags::routes("/task_tags"))
+        .merge(projects::routes("/projects").route_layer(Extension(ScopedResource("projects"))))
+        .merge(task_tags::routes("/task_tags").route_layer(Extension(ScopedResource("tasks"))))
+        .merge(task_dependencies::routes("/tasks").route_layer(Extension(ScopedResource("tasks"))))
+        .merge(reminders::task_routes("/tasks").route_layer(Extension(ScopedResource("tasks"))))
+        .merge(reminders::routes("/reminders").route_layer(Extension(ScopedResource("tasks"))))
+        // Sin scope: la bandeja es de las aplicaciones, no de los tokens de acceso personal
+        .merge(notifications::routes("/notifications"))
+        .merge(events::routes("/events"))
+        .merge(ws::routes("/ws"))
+        .merge(sync::routes("/sync"))
         
 }
\ No newline at end of file
@@ -0,0 +1,41 @@
+// notifications/delete.rs
+use axum::extract::State;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct DeleteNotificationResponse {
+    pub message: String,
+    pub deleted: bool,
+}
+
+// Endpoint para eliminar una notificación
+pub async fn delete_notification(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<DeleteNotificationResponse>, AppError> {
+    let deleted: bool = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT delete_notification($1, $2)")
+            .bind(user_id)
+            .bind(notification_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    if !deleted {
+        return Err(AppError::not_found("Notification not found"));
+    }
+
+    Ok(Json(DeleteNotificationResponse {
+        message: "Notification deleted successfully".to_string(),
+        deleted: true,
+    }))
+}
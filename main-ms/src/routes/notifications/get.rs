@@ -0,0 +1,100 @@
+// notifications/get.rs
+use axum::{extract::State, response::Response};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::Query;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, IfNoneMatch};
+
+#[derive(Debug, Deserialize)]
+pub struct GetNotificationsQuery {
+    /// Solo las no leídas
+    pub unread: Option<bool>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct NotificationResponse {
+    pub id: Uuid,
+    pub event_type: String,
+    pub title: String,
+    pub body: Option<String>,
+    /// `null` si la tarea ya no existe
+    pub task_id: Option<Uuid>,
+    pub read_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct NotificationsResponse {
+    pub notifications: Vec<NotificationResponse>,
+    pub message: String,
+    /// No leídas en total, para el contador de la campana
+    pub unread_count: i64,
+    pub total_count: i64,
+    pub page: i32,
+    pub limit: i32,
+    pub total_pages: i32,
+}
+
+// Endpoint para listar las notificaciones: primero las no leídas, y en cada grupo las más
+// recientes primero
+pub async fn get_notifications(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Query(query): Query<GetNotificationsQuery>,
+) -> Result<Response, AppError> {
+    let unread_only = query.unread.unwrap_or(false);
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let (notifications, total_count, unread_count) = db::as_user(&pool, user_id, async |conn| {
+        let (total_count, unread_count): (i64, i64) =
+            sqlx::query_as("SELECT total_count, unread_count FROM get_user_notifications_count($1)")
+                .bind(user_id)
+                .fetch_one(&mut *conn)
+                .await?;
+
+        let notifications = sqlx::query_as::<_, NotificationResponse>(
+            "SELECT id, event_type, title, body, task_id, read_at, created_at FROM get_user_notifications($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(unread_only)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(conn)
+        .await?;
+
+        Ok((notifications, total_count, unread_count))
+    })
+    .await?;
+
+    // Con `unread=true` se pagina sobre las no leídas
+    let listed_count = if unread_only { unread_count } else { total_count };
+    let total_pages = ((listed_count as f64) / (limit as f64)).ceil() as i32;
+
+    let message = if notifications.is_empty() {
+        "No notifications found"
+    } else {
+        "Notifications retrieved successfully"
+    };
+
+    let response = NotificationsResponse {
+        notifications,
+        message: message.to_string(),
+        unread_count,
+        total_count: listed_count,
+        page,
+        limit,
+        total_pages,
+    };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
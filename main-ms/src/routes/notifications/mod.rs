@@ -0,0 +1,24 @@
+// notifications/mod.rs
+use axum::{
+    routing::{get, post, delete},
+    Router
+};
+use sqlx::PgPool;
+
+mod get;
+mod read;
+mod delete;
+mod preferences;
+
+/// Bandeja de notificaciones (`base` es `/notifications`)
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(base, get(get::get_notifications))
+        .route(&format!("{}/read-all", base), post(read::mark_all_read))
+        .route(
+            &format!("{}/preferences", base),
+            get(preferences::get_preferences).patch(preferences::update_preferences),
+        )
+        .route(&format!("{}/{{notification_id}}/read", base), post(read::mark_read))
+        .route(&format!("{}/{{notification_id}}", base), delete(delete::delete_notification))
+}
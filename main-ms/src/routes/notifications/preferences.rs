@@ -0,0 +1,85 @@
+// notifications/preferences.rs
+use axum::extract::State;
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{BTreeMap, HashMap};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::Json;
+use crate::models::NotificationEvent;
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct NotificationPreferencesResponse {
+    /// Todos los tipos de evento, con `true` si generan notificaciones
+    pub preferences: BTreeMap<&'static str, bool>,
+}
+
+// Endpoint para consultar qué tipos de evento generan notificaciones
+pub async fn get_preferences(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<NotificationPreferencesResponse>, AppError> {
+    Ok(Json(load_preferences(&pool, user_id).await?))
+}
+
+// Endpoint para activar o desactivar tipos de evento, por ejemplo
+// `{"task_overdue": false}`; los tipos que no se envían no cambian
+pub async fn update_preferences(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<HashMap<String, bool>>,
+) -> Result<Json<NotificationPreferencesResponse>, AppError> {
+    let mut validator = Validator::new();
+    validator.check(!payload.is_empty(), "preferences", "at least one event type is required");
+
+    let mut changes = Vec::new();
+    for (event_type, &enabled) in &payload {
+        if let Some(event) = validator.parse::<NotificationEvent>(event_type, Some(event_type)) {
+            changes.push((event, enabled));
+        }
+    }
+    validator.finish()?;
+
+    db::as_user(&pool, user_id, async |conn| {
+        for &(event, enabled) in &changes {
+            sqlx::query("SELECT set_notification_preference($1, $2, $3)")
+                .bind(user_id)
+                .bind(event.as_str())
+                .bind(enabled)
+                .execute(&mut *conn)
+                .await?;
+        }
+
+        Ok(())
+    })
+    .await?;
+
+    Ok(Json(load_preferences(&pool, user_id).await?))
+}
+
+/// Un tipo sin preferencia guardada está activo
+async fn load_preferences(pool: &PgPool, user_id: Uuid) -> Result<NotificationPreferencesResponse, AppError> {
+    let stored: Vec<(String, bool)> = db::as_user(pool, user_id, async |conn| {
+        sqlx::query_as("SELECT event_type, enabled FROM get_notification_preferences($1)")
+            .bind(user_id)
+            .fetch_all(conn)
+            .await
+    })
+    .await?;
+
+    let preferences = NotificationEvent::ALL
+        .iter()
+        .map(|event| {
+            let enabled = stored
+                .iter()
+                .find(|(event_type, _)| event_type == event.as_str())
+                .is_none_or(|&(_, enabled)| enabled);
+            (event.as_str(), enabled)
+        })
+        .collect();
+
+    Ok(NotificationPreferencesResponse { preferences })
+}
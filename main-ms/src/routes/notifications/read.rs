@@ -0,0 +1,67 @@
+// notifications/read.rs
+use axum::extract::State;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct MarkReadResponse {
+    pub message: String,
+    pub notification_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkAllReadResponse {
+    pub message: String,
+    /// Notificaciones que estaban sin leer
+    pub updated_count: i32,
+}
+
+// Endpoint para marcar una notificación como leída; si ya lo estaba no cambia
+pub async fn mark_read(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path(notification_id): Path<Uuid>,
+) -> Result<Json<MarkReadResponse>, AppError> {
+    let found: bool = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT mark_notification_read($1, $2)")
+            .bind(user_id)
+            .bind(notification_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    if !found {
+        return Err(AppError::not_found("Notification not found"));
+    }
+
+    Ok(Json(MarkReadResponse {
+        message: "Notification marked as read".to_string(),
+        notification_id,
+    }))
+}
+
+// Endpoint para marcar todas las notificaciones como leídas
+pub async fn mark_all_read(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+) -> Result<Json<MarkAllReadResponse>, AppError> {
+    let updated_count: i32 = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT mark_all_notifications_read($1)")
+            .bind(user_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    Ok(Json(MarkAllReadResponse {
+        message: "All notifications marked as read".to_string(),
+        updated_count,
+    }))
+}
@@ -0,0 +1,34 @@
+// routes/password.rs
+use axum::{
+    http::HeaderMap,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::{auth_service, extract_token::authorization_header};
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(&format!("{}/change", base), post(change_password))
+        .route(&format!("{}/forgot", base), post(forgot_password))
+        .route(&format!("{}/reset", base), post(reset_password))
+}
+
+async fn change_password(headers: HeaderMap, Json(payload): Json<Value>) -> Result<Response, AppError> {
+    let authorization = authorization_header(&headers)?;
+
+    auth_service::forward("/api/password/change", Some(authorization), &payload).await
+}
+
+async fn forgot_password(Json(payload): Json<Value>) -> Result<Response, AppError> {
+    auth_service::forward("/api/password/forgot", None, &payload).await
+}
+
+async fn reset_password(Json(payload): Json<Value>) -> Result<Response, AppError> {
+    auth_service::forward("/api/password/reset", None, &payload).await
+}
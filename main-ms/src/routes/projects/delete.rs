@@ -1,82 +1,102 @@
 // projects/delete.rs
-use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
-};
-use serde::Serialize;
+use axum::extract::State;
+use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Json, Path, Query};
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{version_tag, IfMatch};
+
+/// Qué hacer con las tareas del proyecto: `restrict` (por defecto, no elimina si tiene
+/// tareas), `cascade` (las elimina) o `inbox` (las deja sin proyecto)
+#[derive(Debug, Deserialize)]
+pub struct DeleteProjectQuery {
+    pub strategy: Option<String>,
+}
 
 #[derive(Debug, Serialize)]
 pub struct DeleteProjectResponse {
     pub message: String,
     pub deleted: bool,
+    pub strategy: String,
+    /// Con `cascade`, también las subtareas de otros proyectos que se borran con su tarea padre
+    pub tasks_deleted: i32,
+    pub tasks_moved_to_inbox: i32,
 }
 
+// Con `If-Match`, solo elimina el proyecto si no cambió desde esa versión
 pub async fn delete_project(
     State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    IfMatch(if_match): IfMatch,
     Path(project_id): Path<Uuid>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // Extraer el token de los headers usando la función externa
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
+    Query(query): Query<DeleteProjectQuery>,
+) -> Result<Json<DeleteProjectResponse>, AppError> {
+    remove_project(&pool, user_id, project_id, query, if_match).await.map(Json)
+}
 
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+/// Elimina el proyecto según la estrategia; con `if_match`, solo si su versión es una de esas
+pub(crate) async fn remove_project(
+    pool: &PgPool,
+    user_id: Uuid,
+    project_id: Uuid,
+    query: DeleteProjectQuery,
+    if_match: Option<Vec<i32>>,
+) -> Result<DeleteProjectResponse, AppError> {
+    let strategy = query.strategy.unwrap_or_else(|| "restrict".to_string());
+
+    let mut validator = Validator::new();
+    validator.check(
+        ["restrict", "cascade", "inbox"].contains(&strategy.as_str()),
+        "strategy",
+        "must be one of: restrict, cascade, inbox",
+    );
+    validator.finish()?;
 
     // Ejecutar la función de la base de datos para eliminar el proyecto
-    match sqlx::query_scalar(
-        "SELECT delete_project($1, $2)"
-    )
-    .bind(user_id)
-    .bind(project_id)
-    .fetch_one(&pool)
-    .await
-    {
-        Ok(deleted) => {
-            if deleted {
-                let response = DeleteProjectResponse {
-                    message: "Project deleted successfully".to_string(),
-                    deleted: true,
-                };
-                (StatusCode::OK, Json(response)).into_response()
-            } else {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(ErrorResponse {
-                        error: "Project not found or you don't have permission to delete it".to_string(),
-                    }),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error deleting project: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
+    let result = db::as_user(pool, user_id, async |conn| {
+        sqlx::query_as::<_, (bool, Option<i32>, i32)>(
+            "SELECT deleted, affected_tasks, current_version FROM delete_project($1, $2, $3, $4)",
+        )
+        .bind(user_id)
+        .bind(project_id)
+        .bind(&strategy)
+        .bind(&if_match)
+        .fetch_optional(conn)
+        .await
+    })
+    .await?;
+
+    match result {
+        Some((true, affected_tasks, _)) => {
+            let affected_tasks = affected_tasks.unwrap_or(0);
+            let (tasks_deleted, tasks_moved_to_inbox) = match strategy.as_str() {
+                "cascade" => (affected_tasks, 0),
+                "inbox" => (0, affected_tasks),
+                _ => (0, 0),
+            };
+            Ok(DeleteProjectResponse {
+                message: "Project deleted successfully".to_string(),
+                deleted: true,
+                strategy,
+                tasks_deleted,
+                tasks_moved_to_inbox,
+            })
         }
+        Some((false, None, version)) => Err(AppError::PreconditionFailed(format!(
+            "Resource has been modified; current ETag is {}",
+            version_tag(version)
+        ))),
+        Some((false, Some(task_count), _)) => Err(AppError::Conflict {
+            code: "project_has_tasks",
+            message: format!(
+                "Project has {} task(s). Use strategy=cascade to delete them or strategy=inbox to keep them without a project",
+                task_count
+            ),
+        }),
+        None => Err(AppError::not_found("Project not found or you don't have permission to delete it")),
     }
-}
\ No newline at end of file
+}
@@ -1,15 +1,14 @@
 // projects/get.rs
-use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
-};
+use axum::{extract::State, response::Response};
 use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, version_tag, IfNoneMatch};
 
 #[derive(Debug, Serialize, sqlx::FromRow)]
 pub struct Project {
@@ -18,6 +17,7 @@ pub struct Project {
     pub name: String,
     pub color: Option<String>,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub version: i32,
 }
 
 #[derive(Debug, Serialize)]
@@ -32,109 +32,40 @@ pub struct ProjectResponse {
 
 pub async fn get_projects(
     State(pool): State<PgPool>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // Extraer el token de los headers usando la función externa
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
-
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+) -> Result<Response, AppError> {
     // Ejecutar la función de la base de datos para obtener todos los proyectos del usuario
-    match sqlx::query_as::<_, Project>(
-        "SELECT * FROM get_user_projects($1)"
-    )
-    .bind(user_id)
-    .fetch_all(&pool)
-    .await
-    {
-        Ok(projects) => {
-            let response = ProjectsResponse { projects };
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Err(e) => {
-            let error_message = format!("Error fetching projects: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
+    let projects = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_as::<_, Project>("SELECT * FROM get_user_projects($1)")
+            .bind(user_id)
+            .fetch_all(conn)
+            .await
+    })
+    .await?;
+
+    let response = ProjectsResponse { projects };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
 }
 
 pub async fn get_project_by_id(
     State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
     Path(project_id): Path<Uuid>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // Extraer el token de los headers usando la función externa
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
-
+) -> Result<Response, AppError> {
     // Ejecutar la función de la base de datos para obtener un proyecto específico
-    match sqlx::query_as::<_, Project>(
-        "SELECT * FROM get_project($1, $2)"
-    )
-    .bind(user_id)
-    .bind(project_id)
-    .fetch_optional(&pool)
-    .await
-    {
-        Ok(Some(project)) => {
-            let response = ProjectResponse { project: Some(project) };
-            (StatusCode::OK, Json(response)).into_response()
-        }
-        Ok(None) => {
-            (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    error: "Project not found or you don't have permission to view it".to_string(),
-                }),
-            )
-                .into_response()
-        }
-        Err(e) => {
-            let error_message = format!("Error fetching project: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
-}
\ No newline at end of file
+    let project = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_as::<_, Project>("SELECT * FROM get_project($1, $2)")
+            .bind(user_id)
+            .bind(project_id)
+            .fetch_optional(conn)
+            .await
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Project not found or you don't have permission to view it"))?;
+
+    let etag = version_tag(project.version);
+    Ok(json_with_etag(etag, &if_none_match, ProjectResponse { project: Some(project) }))
+}
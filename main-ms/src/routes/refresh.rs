@@ -0,0 +1,59 @@
+// routes/refresh.rs
+use axum::{
+    extract::State,
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use reqwest::Client;
+use sqlx::PgPool;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::auth_service;
+
+#[derive(Debug, Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshResponse {
+    token: String,
+    refresh_token: String,
+    expires_in: i64,
+}
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new().route(base, post(refresh))
+}
+
+async fn refresh(
+    State(_pool): State<PgPool>,
+    Json(payload): Json<RefreshRequest>,
+) -> Result<Json<RefreshResponse>, AppError> {
+    // URL del microservicio de autenticación (validada al arrancar)
+    let auth_service_url = &Config::get().auth_service.url;
+
+    let refresh_url = format!("{}/api/token/refresh", auth_service_url);
+
+    // Rotar el refresh token en el microservicio de autenticación
+    let client = Client::new();
+    let response = client
+        .post(&refresh_url)
+        .json(&serde_json::json!({
+            "refresh_token": payload.refresh_token
+        }))
+        .send()
+        .await;
+
+    let response = response?;
+
+    if !response.status().is_success() {
+        return Err(auth_service::error_from_response(response).await);
+    }
+
+    let tokens = response.json::<RefreshResponse>().await?;
+    Ok(Json(tokens))
+}
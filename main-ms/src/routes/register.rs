@@ -1,16 +1,19 @@
 // routes/register.rs
 use axum::{
     extract::State,
-    response::{Json, IntoResponse},
     routing::post,
     Router,
 };
 use serde::{Deserialize, Serialize};
-use reqwest::{Client, StatusCode};
+use reqwest::Client;
 use sqlx::PgPool;
 use uuid::Uuid;
-use std::env;
 
+use crate::config::Config;
+use crate::db;
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::auth_service;
 
 #[derive(Debug, Deserialize)]
 struct RegisterRequest {
@@ -26,37 +29,26 @@ struct RegisterResponse {
     message: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    error: String,
-}
-
 #[derive(Debug, Deserialize)]
 struct AuthResponse {
     user: UserResponse,
 }
 
-#[derive(Debug, Deserialize)]
-struct AuthErrorResponse {
-    error: String,
-}
-
 #[derive(Debug, Deserialize)]
 struct UserResponse {
     id: Uuid,
 }
 
 pub fn routes(base: &str) -> Router<PgPool> {
-    Router::new().route(&format!("{}", base), post(register))
+    Router::new().route(base, post(register))
 }
 
 async fn register(
     State(pool): State<PgPool>,
     Json(payload): Json<RegisterRequest>,
-) -> impl IntoResponse {
-    // Obtener la URL del microservicio de autenticación desde las variables de entorno
-    let auth_service_url = env::var("AUTH_MICROSERVICE_URL")
-        .expect("AUTH_MICROSERVICE_URL must be set in .env file");
+) -> Result<Json<RegisterResponse>, AppError> {
+    // URL del microservicio de autenticación (validada al arrancar)
+    let auth_service_url = &Config::get().auth_service.url;
     
     let register_url = format!("{}/api/register", auth_service_url);
 
@@ -72,80 +64,28 @@ async fn register(
         .send()
         .await;
 
-    let response = match response {
-        Ok(resp) => resp,
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { 
-                    error: format!("Error calling auth service: {}", e) 
-                })
-            ).into_response()
-        }
-    };
+    let response = response?;
 
-    // Verificar el status code de la respuesta
-    let status = response.status();
-    
-    if status.is_success() {
-        // Si es exitoso, parsear la respuesta
-        match response.json::<AuthResponse>().await {
-            Ok(auth_response) => {
-                let user_id = auth_response.user.id;
-
-                // Llamar al stored procedure con user_id y name
-                match sqlx::query_scalar(
-                    "SELECT create_user_profile($1, $2)"
-                )
-                .bind(user_id)
-                .bind(&payload.name)
-                .fetch_one(&pool)
-                .await
-                {
-                    Ok(profile_id) => {
-                        let response = RegisterResponse {
-                            user_id,
-                            profile_id,
-                            message: "Usuario registrado y perfil creado exitosamente".to_string(),
-                        };
-                        Json(response).into_response()
-                    }
-                    Err(e) => {
-                        (
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json(ErrorResponse { 
-                                error: format!("Error creating user profile: {}", e) 
-                            })
-                        ).into_response()
-                    }
-                }
-            }
-            Err(e) => {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { 
-                        error: format!("Error parsing auth response: {}", e) 
-                    })
-                ).into_response()
-            }
-        }
-    } else {
-        // Si hay error, parsear el mensaje de error
-        match response.json::<AuthErrorResponse>().await {
-            Ok(error_response) => {
-                let status_code = StatusCode::from_u16(status.as_u16())
-                    .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
-                
-                (status_code, Json(ErrorResponse { error: error_response.error })).into_response()
-            }
-            Err(e) => {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { 
-                        error: format!("Error parsing auth error response: {}", e) 
-                    })
-                ).into_response()
-            }
-        }
+    if !response.status().is_success() {
+        return Err(auth_service::error_from_response(response).await);
     }
-}
\ No newline at end of file
+
+    let auth_response = response.json::<AuthResponse>().await?;
+    let user_id = auth_response.user.id;
+
+    // Llamar al stored procedure con user_id y name
+    let profile_id = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT create_user_profile($1, $2)")
+            .bind(user_id)
+            .bind(&payload.name)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    Ok(Json(RegisterResponse {
+        user_id,
+        profile_id,
+        message: "Usuario registrado y perfil creado exitosamente".to_string(),
+    }))
+}
@@ -0,0 +1,41 @@
+// reminders/delete.rs
+use axum::extract::State;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct DeleteReminderResponse {
+    pub message: String,
+    pub deleted: bool,
+}
+
+// Endpoint para eliminar un recordatorio, ya sea pendiente o del historial
+pub async fn delete_reminder(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path(reminder_id): Path<Uuid>,
+) -> Result<Json<DeleteReminderResponse>, AppError> {
+    let deleted: bool = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT delete_task_reminder($1, $2)")
+            .bind(user_id)
+            .bind(reminder_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    if !deleted {
+        return Err(AppError::not_found("Reminder not found"));
+    }
+
+    Ok(Json(DeleteReminderResponse {
+        message: "Reminder deleted successfully".to_string(),
+        deleted: true,
+    }))
+}
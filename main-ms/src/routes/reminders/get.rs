@@ -0,0 +1,143 @@
+// reminders/get.rs
+use axum::{extract::State, response::Response};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Path, Query};
+use crate::models::ReminderStatus;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, IfNoneMatch};
+
+#[derive(Debug, Deserialize)]
+pub struct GetRemindersQuery {
+    pub status: Option<String>,
+    pub task_id: Option<Uuid>,
+    pub page: Option<i32>,
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ReminderResponse {
+    pub id: Uuid,
+    pub task_id: Uuid,
+    pub remind_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub offset_minutes: Option<i32>,
+    /// Cuándo se dispara; `null` si es relativo a una fecha límite que la tarea no tiene
+    pub fire_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub channels: Vec<String>,
+    pub status: ReminderStatus,
+    pub delivered_channels: Vec<String>,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub fired_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskRemindersResponse {
+    pub task_id: Uuid,
+    pub reminders: Vec<ReminderResponse>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RemindersResponse {
+    pub reminders: Vec<ReminderResponse>,
+    pub message: String,
+    pub total_count: i64,
+    pub page: i32,
+    pub limit: i32,
+    pub total_pages: i32,
+}
+
+// Endpoint para listar los recordatorios de todas las tareas, por ejemplo los disparados
+// (`status=fired`) o los perdidos (`status=missed`)
+pub async fn get_reminders(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Query(query): Query<GetRemindersQuery>,
+) -> Result<Response, AppError> {
+    let mut validator = Validator::new();
+    let status: Option<ReminderStatus> = validator.parse("status", query.status.as_deref());
+    validator.finish()?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+    let offset = (page - 1) * limit;
+
+    let (reminders, total_count) = db::as_user(&pool, user_id, async |conn| {
+        let total_count: i64 = sqlx::query_scalar("SELECT count FROM get_user_reminders_count($1, $2, $3)")
+            .bind(user_id)
+            .bind(status)
+            .bind(query.task_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let reminders = sqlx::query_as::<_, ReminderResponse>("SELECT * FROM get_user_reminders($1, $2, $3, $4, $5)")
+            .bind(user_id)
+            .bind(status)
+            .bind(query.task_id)
+            .bind(limit)
+            .bind(offset)
+            .fetch_all(conn)
+            .await?;
+
+        Ok((reminders, total_count))
+    })
+    .await?;
+
+    let total_pages = ((total_count as f64) / (limit as f64)).ceil() as i32;
+
+    let message = if reminders.is_empty() {
+        "No reminders found"
+    } else {
+        "Reminders retrieved successfully"
+    };
+
+    let response = RemindersResponse {
+        reminders,
+        message: message.to_string(),
+        total_count,
+        page,
+        limit,
+        total_pages,
+    };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
+
+// Endpoint para listar los recordatorios de una tarea, en el orden en que se disparan
+pub async fn get_task_reminders(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Path(task_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let reminders = db::as_user(&pool, user_id, async |conn| {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM get_task($1, $2))")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if !exists {
+            return Ok(None);
+        }
+
+        sqlx::query_as::<_, ReminderResponse>("SELECT * FROM get_task_reminders($1, $2)")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_all(conn)
+            .await
+            .map(Some)
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    let response = TaskRemindersResponse { task_id, reminders };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
@@ -0,0 +1,24 @@
+// reminders/mod.rs
+use axum::{
+    routing::{get, post, delete},
+    Router
+};
+use sqlx::PgPool;
+
+mod get;
+mod post;
+mod delete;
+
+/// Listado de recordatorios de todas las tareas (`base` es `/reminders`)
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(base, get(get::get_reminders))
+        .route(&format!("{}/{{reminder_id}}", base), delete(delete::delete_reminder))
+}
+
+/// Recordatorios de una tarea (`base` es `/tasks`)
+pub fn task_routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(&format!("{}/{{task_id}}/reminders", base), post(post::create_reminder))
+        .route(&format!("{}/{{task_id}}/reminders", base), get(get::get_task_reminders))
+}
@@ -0,0 +1,114 @@
+// reminders/post.rs
+use axum::{
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::config::Config;
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Json, Path};
+use crate::models::ReminderChannel;
+use crate::utils::auth::CurrentUser;
+use crate::utils::auth_service;
+use crate::utils::extract_token::authorization_header;
+
+/// Anticipación máxima de un recordatorio relativo (un año)
+const MAX_OFFSET_MINUTES: i32 = 525_600;
+
+/// Un recordatorio se dispara en `remind_at` o `offset_minutes` antes de la fecha límite de
+/// la tarea; hay que indicar exactamente uno de los dos
+#[derive(Debug, Deserialize)]
+pub struct CreateReminderRequest {
+    pub remind_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub offset_minutes: Option<i32>,
+    /// `in_app` (por defecto), `webhook` y/o `email`
+    pub channels: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateReminderResponse {
+    pub reminder_id: Uuid,
+    pub message: String,
+}
+
+// Endpoint para crear un recordatorio de una tarea
+pub async fn create_reminder(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    headers: HeaderMap,
+    Path(task_id): Path<Uuid>,
+    Json(payload): Json<CreateReminderRequest>,
+) -> Result<(StatusCode, Json<CreateReminderResponse>), AppError> {
+    let mut validator = Validator::new();
+
+    match (payload.remind_at, payload.offset_minutes) {
+        (None, None) => validator.error("remind_at", "either remind_at or offset_minutes is required"),
+        (Some(_), Some(_)) => validator.error("remind_at", "cannot be combined with offset_minutes"),
+        (Some(remind_at), None) => {
+            validator.check(remind_at > chrono::Utc::now(), "remind_at", "must be in the future");
+        }
+        (None, Some(offset_minutes)) => validator.check(
+            (0..=MAX_OFFSET_MINUTES).contains(&offset_minutes),
+            "offset_minutes",
+            format!("must be between 0 and {}", MAX_OFFSET_MINUTES),
+        ),
+    }
+
+    let requested = payload.channels.unwrap_or_else(|| vec![ReminderChannel::InApp.to_string()]);
+    validator.check(!requested.is_empty(), "channels", "must not be empty");
+
+    let mut channels: Vec<ReminderChannel> = Vec::new();
+    for channel in &requested {
+        if let Some(channel) = validator.parse::<ReminderChannel>("channels", Some(channel)) {
+            if !channels.contains(&channel) {
+                channels.push(channel);
+            }
+        }
+    }
+
+    if channels.contains(&ReminderChannel::Webhook) && !Config::get().reminders.webhook_enabled() {
+        validator.error("channels", "webhook is not configured on this server");
+    }
+    validator.finish()?;
+
+    // El correo va al email de la cuenta, que solo conoce auth-ms
+    let email = if channels.contains(&ReminderChannel::Email) {
+        Some(auth_service::account_email(authorization_header(&headers)?).await?)
+    } else {
+        None
+    };
+
+    let channels: Vec<&str> = channels.into_iter().map(ReminderChannel::as_str).collect();
+
+    let reminder_id: Uuid = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT create_task_reminder($1, $2, $3, $4, $5, $6)")
+            .bind(user_id)
+            .bind(task_id)
+            .bind(payload.remind_at)
+            .bind(payload.offset_minutes)
+            .bind(&channels)
+            .bind(email)
+            .fetch_one(conn)
+            .await
+    })
+    .await
+    .map_err(|e| {
+        if db::violated_constraint(&e) == Some("task_reminders_task_fkey") {
+            AppError::not_found("Task not found")
+        } else {
+            AppError::from(e)
+        }
+    })?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateReminderResponse {
+            reminder_id,
+            message: "Reminder created successfully".to_string(),
+        }),
+    ))
+}
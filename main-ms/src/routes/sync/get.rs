@@ -0,0 +1,273 @@
+// sync/get.rs
+use axum::{extract::State, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Query};
+use crate::utils::auth::CurrentUser;
+
+const DEFAULT_LIMIT: i32 = 500;
+const MAX_LIMIT: i32 = 1000;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    /// Cursor de la sincronización anterior; sin él se devuelve todo el estado
+    pub since: Option<i64>,
+    /// Eventos que se leen por página (no entidades)
+    pub limit: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Tombstones {
+    pub tasks: Vec<Uuid>,
+    pub projects: Vec<Uuid>,
+    pub tags: Vec<Uuid>,
+    pub task_tags: Vec<TaskTagKey>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskTagKey {
+    pub task_id: Uuid,
+    pub tag_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    /// Se envía como `since` en la siguiente sincronización
+    pub cursor: i64,
+    /// Quedan cambios: pedir de nuevo con el nuevo cursor
+    pub has_more: bool,
+    /// El cliente debe reemplazar todo su estado local con esta respuesta
+    pub full: bool,
+    pub tasks: Vec<Value>,
+    pub projects: Vec<Value>,
+    pub tags: Vec<Value>,
+    pub task_tags: Vec<Value>,
+    pub deleted: Tombstones,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct SyncRow {
+    entity: String,
+    entity_id: Uuid,
+    related_id: Option<Uuid>,
+    data: Option<Value>,
+}
+
+// Endpoint de sincronización: sin `since`, el estado completo y el cursor desde el que
+// seguir; con `since`, las entidades que cambiaron después, con su estado actual, y las
+// eliminadas. Un cursor anterior a los eventos que se conservan responde 410 y el cliente
+// debe volver a sincronizar sin `since`.
+pub async fn get_changes(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Query(query): Query<SyncQuery>,
+) -> Result<Json<SyncResponse>, AppError> {
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+
+    if let Some(since) = query.since {
+        if since < 0 {
+            return Err(AppError::invalid_field("since", "must be a non-negative integer"));
+        }
+    }
+
+    // `None`: el cursor es anterior a los eventos que se conservan
+    let response = db::as_user(&pool, user_id, async |conn| {
+        let Some(since) = query.since else {
+            // El cursor se lee antes que el estado: un cambio entre medio se vuelve a enviar,
+            // pero no se pierde. Nunca es menor que el horizonte, o un usuario sin eventos
+            // recientes recibiría un cursor ya vencido.
+            let cursor: i64 = sqlx::query_scalar("SELECT GREATEST(get_last_change_event_id($1), get_change_events_horizon())")
+                .bind(user_id)
+                .fetch_one(&mut *conn)
+                .await?;
+
+            let rows = sqlx::query_as::<_, SyncRow>(
+                "SELECT entity, entity_id, related_id, data FROM get_sync_snapshot($1)",
+            )
+            .bind(user_id)
+            .fetch_all(&mut *conn)
+            .await?;
+
+            return Ok(Some(build_response(cursor, false, true, rows)));
+        };
+
+        let horizon: i64 = sqlx::query_scalar("SELECT get_change_events_horizon()")
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if since < horizon {
+            return Ok(None);
+        }
+
+        let rows = sqlx::query_as::<_, (i64, String, Uuid, Option<Uuid>, Option<Value>)>(
+            "SELECT event_id, entity, entity_id, related_id, data FROM get_sync_changes($1, $2, $3)",
+        )
+        .bind(user_id)
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        let cursor = delta_cursor(since, rows.iter().map(|row| row.0));
+
+        let has_more: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM get_change_events($1, $2, 1))")
+            .bind(user_id)
+            .bind(cursor)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        let rows = rows
+            .into_iter()
+            .map(|(_, entity, entity_id, related_id, data)| SyncRow {
+                entity,
+                entity_id,
+                related_id,
+                data,
+            })
+            .collect();
+
+        Ok(Some(build_response(cursor, has_more, false, rows)))
+    })
+    .await?;
+
+    response.map(Json).ok_or_else(|| AppError::InvalidRequest {
+        status: StatusCode::GONE,
+        code: "sync_cursor_expired",
+        message: "The sync cursor is too old; sync again without since".to_string(),
+    })
+}
+
+/// Cursor de una página de cambios: el último evento leído o, si no hubo ninguno, el mismo
+/// `since`, para que una sincronización sin cambios no retroceda
+fn delta_cursor(since: i64, event_ids: impl IntoIterator<Item = i64>) -> i64 {
+    event_ids.into_iter().max().unwrap_or(since).max(since)
+}
+
+/// Separa las filas por entidad; las que no tienen `data` son eliminaciones
+fn build_response(cursor: i64, has_more: bool, full: bool, rows: Vec<SyncRow>) -> SyncResponse {
+    let mut response = SyncResponse {
+        cursor,
+        has_more,
+        full,
+        tasks: Vec::new(),
+        projects: Vec::new(),
+        tags: Vec::new(),
+        task_tags: Vec::new(),
+        deleted: Tombstones::default(),
+    };
+
+    for row in rows {
+        match (row.entity.as_str(), row.data) {
+            ("task", Some(data)) => response.tasks.push(data),
+            ("project", Some(data)) => response.projects.push(data),
+            ("tag", Some(data)) => response.tags.push(data),
+            ("task_tag", Some(data)) => response.task_tags.push(data),
+            ("task", None) => response.deleted.tasks.push(row.entity_id),
+            ("project", None) => response.deleted.projects.push(row.entity_id),
+            ("tag", None) => response.deleted.tags.push(row.entity_id),
+            ("task_tag", None) => response.deleted.task_tags.push(TaskTagKey {
+                task_id: row.entity_id,
+                tag_id: row.related_id,
+            }),
+            _ => {}
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn row(entity: &str, data: Option<Value>) -> SyncRow {
+        SyncRow {
+            entity: entity.to_string(),
+            entity_id: Uuid::new_v4(),
+            related_id: None,
+            data,
+        }
+    }
+
+    #[test]
+    fn delta_cursor_is_the_last_event_read() {
+        assert_eq!(delta_cursor(10, [12, 15, 11]), 15);
+    }
+
+    #[test]
+    fn delta_cursor_keeps_since_without_events() {
+        assert_eq!(delta_cursor(42, []), 42);
+        assert_eq!(delta_cursor(0, []), 0);
+    }
+
+    #[test]
+    fn delta_cursor_never_goes_back() {
+        assert_eq!(delta_cursor(20, [5]), 20);
+    }
+
+    #[test]
+    fn build_response_splits_rows_by_entity() {
+        let rows = vec![
+            row("task", Some(json!({ "title": "Tarea" }))),
+            row("project", Some(json!({ "name": "Proyecto" }))),
+            row("tag", Some(json!({ "name": "etiqueta" }))),
+            row("task_tag", Some(json!({}))),
+        ];
+
+        let response = build_response(7, true, false, rows);
+
+        assert_eq!(response.cursor, 7);
+        assert!(response.has_more);
+        assert!(!response.full);
+        assert_eq!(response.tasks, vec![json!({ "title": "Tarea" })]);
+        assert_eq!(response.projects.len(), 1);
+        assert_eq!(response.tags.len(), 1);
+        assert_eq!(response.task_tags.len(), 1);
+        assert!(response.deleted.tasks.is_empty());
+    }
+
+    #[test]
+    fn build_response_turns_rows_without_data_into_tombstones() {
+        let task = row("task", None);
+        let task_id = task.entity_id;
+        let tag_id = Uuid::new_v4();
+        let task_tag = SyncRow {
+            related_id: Some(tag_id),
+            ..row("task_tag", None)
+        };
+        let task_tag_task_id = task_tag.entity_id;
+
+        let response = build_response(1, false, true, vec![task, row("project", None), row("tag", None), task_tag]);
+
+        assert!(response.tasks.is_empty());
+        assert_eq!(response.deleted.tasks, vec![task_id]);
+        assert_eq!(response.deleted.projects.len(), 1);
+        assert_eq!(response.deleted.tags.len(), 1);
+        assert_eq!(response.deleted.task_tags.len(), 1);
+        assert_eq!(response.deleted.task_tags[0].task_id, task_tag_task_id);
+        assert_eq!(response.deleted.task_tags[0].tag_id, Some(tag_id));
+    }
+
+    #[test]
+    fn build_response_ignores_unknown_entities() {
+        let response = build_response(3, false, false, vec![row("comment", Some(json!({})))]);
+
+        assert_eq!(response.cursor, 3);
+        assert!(response.tasks.is_empty() && response.projects.is_empty());
+        assert!(response.tags.is_empty() && response.task_tags.is_empty());
+    }
+
+    #[test]
+    fn cursor_serializes_as_a_number() {
+        let value = serde_json::to_value(build_response(99, false, false, Vec::new())).unwrap();
+
+        assert_eq!(value["cursor"], json!(99));
+        assert_eq!(value["deleted"]["task_tags"], json!([]));
+    }
+}
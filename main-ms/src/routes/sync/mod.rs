@@ -0,0 +1,11 @@
+// sync/mod.rs
+use axum::{routing::get, Router};
+use sqlx::PgPool;
+
+mod get;
+mod post;
+
+/// Sincronización incremental para clientes sin conexión (`base` es `/sync`)
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new().route(base, get(get::get_changes).post(post::apply_mutations))
+}
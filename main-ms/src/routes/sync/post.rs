@@ -0,0 +1,529 @@
+// sync/post.rs
+use axum::{extract::State, http::StatusCode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::Json;
+use crate::routes::tasks::{patch::WrittenPatch, post::CreateTaskResponse, task_write_error};
+use crate::routes::{projects, tags, task_tags, tasks};
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::updated_version;
+
+const MAX_MUTATIONS: usize = 100;
+
+/// Campos de `data` con ids que pueden referirse a lo creado antes en el mismo lote
+const REFERENCE_FIELDS: [&str; 2] = ["project_id", "parent_task_id"];
+
+#[derive(Debug, Deserialize)]
+pub struct SyncMutationsRequest {
+    /// Cada cambio se interpreta por separado: uno mal formado no invalida los demás
+    pub mutations: Vec<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Entity {
+    Task,
+    Project,
+    Tag,
+    TaskTag,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+/// Cambio hecho sin conexión. `id`, `task_id`, `tag_id`, `data.project_id` y
+/// `data.parent_task_id` aceptan `"$<client_id>"` para referirse a lo creado por un cambio
+/// anterior del mismo lote.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Mutation {
+    client_id: Option<String>,
+    entity: Entity,
+    op: Operation,
+    id: Option<String>,
+    task_id: Option<String>,
+    tag_id: Option<String>,
+    /// Versión sobre la que se hizo el cambio; si ya no es la actual, hay conflicto
+    base_version: Option<i32>,
+    #[serde(default)]
+    data: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MutationStatus {
+    Applied,
+    /// La entidad cambió después de `base_version`; `current` trae su estado actual
+    Conflict,
+    NotFound,
+    Invalid,
+    /// Válido pero no se puede aplicar (por ejemplo, un proyecto con tareas)
+    Rejected,
+    Error,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MutationResult {
+    pub index: usize,
+    pub client_id: Option<String>,
+    pub status: MutationStatus,
+    /// La entidad afectada; en una creación, el id asignado
+    pub id: Option<Uuid>,
+    /// Lo que respondería el endpoint equivalente, también en un error
+    pub response: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current: Option<Value>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncMutationsResponse {
+    pub results: Vec<MutationResult>,
+    pub applied: usize,
+}
+
+// Endpoint que aplica en orden los cambios hechos sin conexión, cada uno con las mismas
+// funciones que su endpoint (validaciones, If-Match, permisos). No es atómico: cada resultado
+// dice qué pasó con su cambio. Después el cliente sincroniza con su cursor anterior para
+// recibir el estado resultante.
+pub async fn apply_mutations(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<SyncMutationsRequest>,
+) -> Result<Json<SyncMutationsResponse>, AppError> {
+    let mut validator = Validator::new();
+    validator.check(!payload.mutations.is_empty(), "mutations", "must not be empty");
+    validator.check(
+        payload.mutations.len() <= MAX_MUTATIONS,
+        "mutations",
+        format!("must have at most {} items", MAX_MUTATIONS),
+    );
+    validator.finish()?;
+
+    let mut created: HashMap<String, Uuid> = HashMap::new();
+    let mut results = Vec::with_capacity(payload.mutations.len());
+
+    for (index, value) in payload.mutations.into_iter().enumerate() {
+        let client_id = value.get("client_id").and_then(Value::as_str).map(str::to_string);
+
+        let mutation = match serde_json::from_value::<Mutation>(value) {
+            Ok(mutation) => mutation,
+            Err(e) => {
+                let error = AppError::InvalidRequest {
+                    status: StatusCode::BAD_REQUEST,
+                    code: "invalid_mutation",
+                    message: e.to_string(),
+                };
+                results.push(failed(index, client_id, None, error));
+                continue;
+            }
+        };
+
+        let result = match apply(&pool, user_id, &mutation, &created).await {
+            Ok((id, response)) => {
+                if let (Entity::Task | Entity::Project | Entity::Tag, Operation::Create, Some(client_id)) =
+                    (mutation.entity, mutation.op, &mutation.client_id)
+                {
+                    created.insert(client_id.clone(), id);
+                }
+
+                MutationResult {
+                    index,
+                    client_id,
+                    status: MutationStatus::Applied,
+                    id: Some(id),
+                    response,
+                    current: None,
+                }
+            }
+            Err(error) => {
+                let id = target(&mutation, &created);
+                let mut result = failed(index, client_id, id, error);
+                if let (MutationStatus::Conflict, Some(id)) = (result.status, id) {
+                    result.current = current(&pool, user_id, mutation.entity, id).await;
+                }
+                result
+            }
+        };
+
+        results.push(result);
+    }
+
+    let applied = results.iter().filter(|result| result.status == MutationStatus::Applied).count();
+
+    Ok(Json(SyncMutationsResponse { results, applied }))
+}
+
+/// Aplica el cambio con las funciones de su endpoint. Devuelve la entidad afectada (en una
+/// creación, la nueva) y lo que habría respondido el endpoint.
+async fn apply(
+    pool: &PgPool,
+    user_id: Uuid,
+    mutation: &Mutation,
+    created: &HashMap<String, Uuid>,
+) -> Result<(Uuid, Value), AppError> {
+    let if_match = mutation.base_version.map(|version| vec![version]);
+
+    let mut data = mutation.data.clone();
+    resolve_references(created, &mut data)?;
+
+    match (mutation.entity, mutation.op) {
+        (Entity::TaskTag, Operation::Update) => {
+            Err(AppError::invalid_field("op", "task_tag only supports create and delete"))
+        }
+        (Entity::Tag, Operation::Update | Operation::Delete) if mutation.base_version.is_some() => {
+            Err(AppError::invalid_field("base_version", "tags are not versioned"))
+        }
+        (Entity::TaskTag, op) => {
+            let task_id = required(created, "task_id", mutation.task_id.as_deref())?;
+            let tag_id = required(created, "tag_id", mutation.tag_id.as_deref())?;
+
+            let response = match op {
+                Operation::Delete => to_value(task_tags::delete::detach_tag(pool, user_id, task_id, tag_id).await?),
+                _ => to_value(task_tags::post::attach_tag(pool, user_id, task_id, tag_id).await?),
+            };
+            Ok((task_id, response))
+        }
+        (Entity::Task, Operation::Create) => {
+            let parent_task_id = take::<Option<Uuid>>(&mut data, "parent_task_id")?.flatten();
+            let task_id = tasks::post::insert_task(pool, user_id, parse(data)?, parent_task_id).await?;
+            Ok((task_id, to_value(CreateTaskResponse::new(task_id, parent_task_id))))
+        }
+        (Entity::Project, Operation::Create) => {
+            let response = projects::post::insert_project(pool, user_id, parse(data)?).await?;
+            Ok((response.project_id, to_value(response)))
+        }
+        (Entity::Tag, Operation::Create) => {
+            let response = tags::post::insert_tag(pool, user_id, parse(data)?).await?;
+            Ok((response.tag_id, to_value(response)))
+        }
+        (entity, op) => {
+            let id = required(created, "id", mutation.id.as_deref())?;
+
+            let response = match (entity, op) {
+                (Entity::Task, Operation::Update) => update_task(pool, user_id, id, data, if_match).await?,
+                (Entity::Task, _) => to_value(tasks::delete::remove_task(pool, user_id, id, if_match).await?),
+                (Entity::Project, Operation::Update) => {
+                    let (response, _) = projects::put::change_project(pool, user_id, id, parse(data)?, if_match).await?;
+                    to_value(response)
+                }
+                (Entity::Project, _) => {
+                    to_value(projects::delete::remove_project(pool, user_id, id, parse(data)?, if_match).await?)
+                }
+                (_, Operation::Update) => to_value(tags::put::change_tag(pool, user_id, id, parse(data)?).await?),
+                _ => to_value(tags::delete::remove_tag(pool, user_id, id).await?),
+            };
+            Ok((id, response))
+        }
+    }
+}
+
+/// El merge patch de `PATCH /tasks/{id}` y, si `data` trae `parent_task_id`, el cambio de
+/// tarea padre de `PUT /tasks/{id}/parent`. Ambos van en una transacción y se comparan con
+/// la misma `base_version`.
+async fn update_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    task_id: Uuid,
+    mut data: Map<String, Value>,
+    if_match: Option<Vec<i32>>,
+) -> Result<Value, AppError> {
+    let parent_task_id = take::<Option<Uuid>>(&mut data, "parent_task_id")?;
+    let patch = match parent_task_id {
+        Some(_) if data.is_empty() => None,
+        _ => Some(tasks::patch::parse_patch(data)?),
+    };
+
+    let written = db::as_user(pool, user_id, async |conn| {
+        let mut if_match = if_match;
+        let mut written = WrittenPatch {
+            result: None,
+            warnings: Vec::new(),
+            next_occurrence_id: None,
+        };
+
+        if let Some(ref patch) = patch {
+            written = tasks::patch::write_patch(conn, user_id, task_id, patch, if_match).await?;
+            let Some((true, version)) = written.result else {
+                return Ok(written);
+            };
+            if_match = Some(vec![version]);
+        }
+
+        if let Some(parent_task_id) = parent_task_id {
+            written.result = tasks::subtasks::write_parent(conn, user_id, task_id, parent_task_id, if_match).await?;
+        }
+        Ok(written)
+    })
+    .await
+    .map_err(task_write_error)?;
+
+    updated_version(written.result, "Task not found or you don't have permission to update it")?;
+    Ok(to_value(written.into_response()))
+}
+
+/// Entidad de un cambio que falló, si se pudo identificar (en un vínculo, la tarea)
+fn target(mutation: &Mutation, created: &HashMap<String, Uuid>) -> Option<Uuid> {
+    let id = match (mutation.entity, mutation.op) {
+        (Entity::TaskTag, _) => mutation.task_id.as_deref(),
+        (_, Operation::Create) => None,
+        _ => mutation.id.as_deref(),
+    };
+    resolve(created, "id", id?).ok()
+}
+
+/// Un UUID o `"$<client_id>"` de una creación anterior del lote
+fn resolve(created: &HashMap<String, Uuid>, field: &str, value: &str) -> Result<Uuid, AppError> {
+    match value.strip_prefix('$') {
+        Some(client_id) => created
+            .get(client_id)
+            .copied()
+            .ok_or_else(|| AppError::invalid_field(field, format!("no earlier mutation created {}", value))),
+        None => value
+            .parse()
+            .map_err(|_| AppError::invalid_field(field, "must be a UUID or a $client_id reference")),
+    }
+}
+
+fn required(created: &HashMap<String, Uuid>, field: &str, value: Option<&str>) -> Result<Uuid, AppError> {
+    let value = value.ok_or_else(|| AppError::invalid_field(field, "is required for this operation"))?;
+    resolve(created, field, value)
+}
+
+/// Reemplaza en `data` las referencias de `REFERENCE_FIELDS` por los ids creados; `null`
+/// se conserva
+fn resolve_references(created: &HashMap<String, Uuid>, data: &mut Map<String, Value>) -> Result<(), AppError> {
+    for field in REFERENCE_FIELDS {
+        if let Some(Value::String(value)) = data.get(field) {
+            let id = resolve(created, &format!("data.{}", field), value)?;
+            data.insert(field.to_string(), Value::String(id.to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// Saca un campo de `data` que no pertenece al cuerpo del endpoint; ausente es `None`
+fn take<T: DeserializeOwned>(data: &mut Map<String, Value>, field: &str) -> Result<Option<T>, AppError> {
+    data.remove(field)
+        .map(|value| serde_json::from_value(value).map_err(|e| invalid_body(format!("data.{}: {}", field, e))))
+        .transpose()
+}
+
+fn parse<T: DeserializeOwned>(data: Map<String, Value>) -> Result<T, AppError> {
+    serde_json::from_value(Value::Object(data)).map_err(|e| invalid_body(format!("data: {}", e)))
+}
+
+fn invalid_body(message: String) -> AppError {
+    AppError::InvalidRequest {
+        status: StatusCode::BAD_REQUEST,
+        code: "invalid_body",
+        message,
+    }
+}
+
+fn to_value<T: Serialize>(response: T) -> Value {
+    serde_json::to_value(response).expect("las respuestas de los endpoints siempre se pueden serializar")
+}
+
+/// Resultado de un cambio que no se aplicó, con el cuerpo de error que respondería su endpoint
+fn failed(index: usize, client_id: Option<String>, id: Option<Uuid>, error: AppError) -> MutationResult {
+    let status = match error {
+        AppError::Validation(_) | AppError::InvalidRequest { .. } => MutationStatus::Invalid,
+        AppError::NotFound(_) => MutationStatus::NotFound,
+        AppError::PreconditionFailed(_) => MutationStatus::Conflict,
+        AppError::Conflict { .. } => MutationStatus::Rejected,
+        _ => MutationStatus::Error,
+    };
+    let (_, body) = error.into_body();
+
+    MutationResult {
+        index,
+        client_id,
+        status,
+        id,
+        response: to_value(body),
+        current: None,
+    }
+}
+
+/// Estado actual de la entidad en conflicto, como lo devuelve la sincronización
+async fn current(pool: &PgPool, user_id: Uuid, entity: Entity, id: Uuid) -> Option<Value> {
+    let query = match entity {
+        Entity::Task => "SELECT to_jsonb(t) - 'user_id' FROM get_task($1, $2) t",
+        Entity::Project => "SELECT to_jsonb(p) - 'user_id' FROM get_project($1, $2) p",
+        _ => return None,
+    };
+
+    db::as_user(pool, user_id, async |conn| {
+        sqlx::query_scalar::<_, Value>(query)
+            .bind(user_id)
+            .bind(id)
+            .fetch_optional(conn)
+            .await
+    })
+    .await
+    .unwrap_or_else(|e| {
+        eprintln!("⚠️ No se pudo leer el estado actual de {}: {}", id, e);
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn created() -> (HashMap<String, Uuid>, Uuid) {
+        let id = Uuid::new_v4();
+        (HashMap::from([("nueva".to_string(), id)]), id)
+    }
+
+    fn mutation(value: Value) -> Mutation {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn resolve_accepts_uuids_and_references() {
+        let (created, id) = created();
+        let other = Uuid::new_v4();
+
+        assert_eq!(resolve(&created, "id", &other.to_string()).unwrap(), other);
+        assert_eq!(resolve(&created, "id", "$nueva").unwrap(), id);
+    }
+
+    #[test]
+    fn resolve_rejects_unknown_references_and_garbage() {
+        let (created, _) = created();
+
+        assert!(matches!(resolve(&created, "id", "$otra"), Err(AppError::Validation(_))));
+        assert!(matches!(resolve(&created, "id", "nueva"), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn required_needs_a_value() {
+        let (created, id) = created();
+
+        assert!(matches!(required(&created, "task_id", None), Err(AppError::Validation(_))));
+        assert_eq!(required(&created, "task_id", Some("$nueva")).unwrap(), id);
+    }
+
+    #[test]
+    fn resolve_references_replaces_project_and_parent() {
+        let (created, id) = created();
+        let mut data = json!({
+            "title": "$nueva",
+            "project_id": "$nueva",
+            "parent_task_id": "$nueva",
+        })
+        .as_object()
+        .cloned()
+        .unwrap();
+
+        resolve_references(&created, &mut data).unwrap();
+
+        assert_eq!(data["project_id"], json!(id.to_string()));
+        assert_eq!(data["parent_task_id"], json!(id.to_string()));
+        assert_eq!(data["title"], json!("$nueva"));
+    }
+
+    #[test]
+    fn resolve_references_keeps_null() {
+        let (created, _) = created();
+        let mut data = json!({ "parent_task_id": null }).as_object().cloned().unwrap();
+
+        resolve_references(&created, &mut data).unwrap();
+
+        assert_eq!(data["parent_task_id"], Value::Null);
+    }
+
+    #[test]
+    fn resolve_references_rejects_unknown_references() {
+        let (created, _) = created();
+        let mut data = json!({ "parent_task_id": "$otra" }).as_object().cloned().unwrap();
+
+        assert!(matches!(resolve_references(&created, &mut data), Err(AppError::Validation(_))));
+    }
+
+    #[test]
+    fn take_removes_the_field() {
+        let id = Uuid::new_v4();
+        let mut data = json!({ "parent_task_id": id, "title": "Tarea" }).as_object().cloned().unwrap();
+
+        let parent = take::<Option<Uuid>>(&mut data, "parent_task_id").unwrap();
+
+        assert_eq!(parent, Some(Some(id)));
+        assert!(!data.contains_key("parent_task_id"));
+        assert_eq!(take::<Option<Uuid>>(&mut data, "parent_task_id").unwrap(), None);
+    }
+
+    #[test]
+    fn take_distinguishes_null_from_absent() {
+        let mut data = json!({ "parent_task_id": null }).as_object().cloned().unwrap();
+
+        assert_eq!(take::<Option<Uuid>>(&mut data, "parent_task_id").unwrap(), Some(None));
+    }
+
+    #[test]
+    fn take_rejects_wrong_types() {
+        let mut data = json!({ "parent_task_id": 5 }).as_object().cloned().unwrap();
+
+        assert!(matches!(
+            take::<Option<Uuid>>(&mut data, "parent_task_id"),
+            Err(AppError::InvalidRequest { code: "invalid_body", .. })
+        ));
+    }
+
+    #[test]
+    fn target_of_a_failed_mutation() {
+        let (created, id) = created();
+
+        let update = mutation(json!({ "entity": "task", "op": "update", "id": "$nueva" }));
+        let create = mutation(json!({ "entity": "task", "op": "create", "id": "$nueva" }));
+        let link = mutation(json!({ "entity": "task_tag", "op": "create", "task_id": "$nueva", "tag_id": "$otra" }));
+        let unknown = mutation(json!({ "entity": "tag", "op": "delete", "id": "$otra" }));
+
+        assert_eq!(target(&update, &created), Some(id));
+        assert_eq!(target(&create, &created), None);
+        assert_eq!(target(&link, &created), Some(id));
+        assert_eq!(target(&unknown, &created), None);
+    }
+
+    #[test]
+    fn failed_maps_errors_to_statuses() {
+        let status = |error| failed(0, None, None, error).status;
+
+        assert_eq!(status(AppError::invalid_field("id", "bad")), MutationStatus::Invalid);
+        assert_eq!(status(invalid_body("data: bad".to_string())), MutationStatus::Invalid);
+        assert_eq!(status(AppError::NotFound("gone".to_string())), MutationStatus::NotFound);
+        assert_eq!(status(AppError::PreconditionFailed("stale".to_string())), MutationStatus::Conflict);
+        assert_eq!(
+            status(AppError::Conflict {
+                code: "project_not_empty",
+                message: "busy".to_string(),
+            }),
+            MutationStatus::Rejected
+        );
+        assert_eq!(status(AppError::Internal("boom".to_string())), MutationStatus::Error);
+    }
+
+    #[test]
+    fn failed_keeps_the_endpoint_error_body() {
+        let result = failed(3, Some("nueva".to_string()), None, AppError::NotFound("Task not found".to_string()));
+
+        assert_eq!(result.index, 3);
+        assert_eq!(result.client_id.as_deref(), Some("nueva"));
+        assert!(result.current.is_none());
+        assert_eq!(result.response["error"], json!("Task not found"));
+        assert_eq!(result.response["code"], json!("not_found"));
+    }
+}
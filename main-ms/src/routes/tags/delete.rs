@@ -1,15 +1,13 @@
 // tags/delete.rs
-use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
-};
+use axum::extract::State;
 use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
 
 #[derive(Debug, Serialize)]
 pub struct DeleteTagResponse {
@@ -19,64 +17,29 @@ pub struct DeleteTagResponse {
 
 pub async fn delete_tag(
     State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
     Path(tag_id): Path<Uuid>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // Extraer el token de los headers usando la función externa
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+) -> Result<Json<DeleteTagResponse>, AppError> {
+    remove_tag(&pool, user_id, tag_id).await.map(Json)
+}
 
+pub(crate) async fn remove_tag(pool: &PgPool, user_id: Uuid, tag_id: Uuid) -> Result<DeleteTagResponse, AppError> {
     // Ejecutar la función de la base de datos para eliminar el tag
-    match sqlx::query_scalar(
-        "SELECT delete_tag($1, $2)"
-    )
-    .bind(user_id)
-    .bind(tag_id)
-    .fetch_one(&pool)
-    .await
-    {
-        Ok(deleted) => {
-            if deleted {
-                let response = DeleteTagResponse {
-                    message: "Tag deleted successfully".to_string(),
-                    deleted: true,
-                };
-                (StatusCode::OK, Json(response)).into_response()
-            } else {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(ErrorResponse {
-                        error: "Tag not found or you don't have permission to delete it".to_string(),
-                    }),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error deleting tag: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
+    let deleted: bool = db::as_user(pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT delete_tag($1, $2)")
+            .bind(user_id)
+            .bind(tag_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    if !deleted {
+        return Err(AppError::not_found("Tag not found or you don't have permission to delete it"));
     }
-}
\ No newline at end of file
+
+    Ok(DeleteTagResponse {
+        message: "Tag deleted successfully".to_string(),
+        deleted: true,
+    })
+}
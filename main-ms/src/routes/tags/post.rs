@@ -1,15 +1,17 @@
 // tags/post.rs
 use axum::{
     extract::State,
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
+    http::StatusCode,
+    response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::Json;
+use crate::utils::auth::CurrentUser;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateTagRequest {
@@ -26,80 +28,54 @@ pub struct CreateTagResponse {
 
 pub async fn create_tag(
     State(pool): State<PgPool>,
-    headers: HeaderMap,
+    CurrentUser(user_id): CurrentUser,
     Json(payload): Json<CreateTagRequest>,
-) -> impl IntoResponse {
-    // Extraer el token de los headers usando la función externa
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
+) -> Result<impl IntoResponse, AppError> {
+    let response = insert_tag(&pool, user_id, payload).await?;
+    Ok((StatusCode::CREATED, Json(response)))
+}
 
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+/// Valida y crea una etiqueta
+pub(crate) async fn insert_tag(pool: &PgPool, user_id: Uuid, payload: CreateTagRequest) -> Result<CreateTagResponse, AppError> {
+    let mut validator = Validator::new();
 
     // Validar que el normalized_name no esté vacío
-    if payload.normalized_name.trim().is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Tag normalized name is required".to_string(),
-            }),
-        )
-            .into_response();
-    }
+    validator.check(!payload.normalized_name.trim().is_empty(), "normalized_name", "is required");
 
     // Validar formato del color si se proporciona (opcional: código HEX)
     if let Some(ref color) = payload.color {
-        if !color.is_empty() && !color.starts_with('#') {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Color must be in HEX format (e.g., #FF5733)".to_string(),
-                }),
-            )
-                .into_response();
-        }
+        validator.check(
+            color.is_empty() || color.starts_with('#'),
+            "color",
+            "must be in HEX format (e.g., #FF5733)",
+        );
     }
+    validator.finish()?;
 
     // Ejecutar la función de la base de datos
-    match sqlx::query_scalar(
-        "SELECT create_tag($1, $2, $3, $4)"
-    )
-    .bind(user_id)
-    .bind(&payload.normalized_name)
-    .bind(payload.display_name)
-    .bind(payload.color)
-    .fetch_one(&pool)
+    let tag_id = db::as_user(pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT create_tag($1, $2, $3, $4)")
+            .bind(user_id)
+            .bind(&payload.normalized_name)
+            .bind(payload.display_name)
+            .bind(payload.color)
+            .fetch_one(conn)
+            .await
+    })
     .await
-    {
-        Ok(tag_id) => {
-            let response = CreateTagResponse {
-                tag_id,
-                message: "Tag created successfully".to_string(),
-            };
-            (StatusCode::CREATED, Json(response)).into_response()
+    .map_err(|e| {
+        if db::is_unique_violation(&e) {
+            AppError::Conflict {
+                code: "tag_exists",
+                message: format!("A tag named '{}' already exists", payload.normalized_name),
+            }
+        } else {
+            AppError::from(e)
         }
-        Err(e) => {
-            let error_message = format!("Error creating tag: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
-}
\ No newline at end of file
+    })?;
+
+    Ok(CreateTagResponse {
+        tag_id,
+        message: "Tag created successfully".to_string(),
+    })
+}
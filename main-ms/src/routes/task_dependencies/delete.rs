@@ -0,0 +1,42 @@
+// task_dependencies/delete.rs
+use axum::extract::State;
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct RemoveTaskDependencyResponse {
+    pub message: String,
+    pub removed: bool,
+}
+
+pub async fn remove_task_dependency(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path((task_id, blocked_by_task_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<RemoveTaskDependencyResponse>, AppError> {
+    // Ejecutar la función de la base de datos
+    let removed: bool = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT remove_task_dependency($1, $2, $3)")
+            .bind(user_id)
+            .bind(task_id)
+            .bind(blocked_by_task_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
+
+    if !removed {
+        return Err(AppError::not_found("Dependency not found, or you don't have permission"));
+    }
+
+    Ok(Json(RemoveTaskDependencyResponse {
+        message: "Dependency removed successfully".to_string(),
+        removed: true,
+    }))
+}
@@ -0,0 +1,118 @@
+// task_dependencies/get.rs
+use axum::{extract::State, response::Response};
+use serde::Serialize;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::Path;
+use crate::models::TaskStatus;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, IfNoneMatch};
+
+/// Resumen de una tarea relacionada
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct DependencyTask {
+    pub id: Uuid,
+    pub title: String,
+    pub status: TaskStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskDependencies {
+    /// Alguna de las tareas de `blocked_by` no está completada
+    pub blocked: bool,
+    /// Tareas que bloquean a esta
+    pub blocked_by: Vec<DependencyTask>,
+    /// Tareas que esta bloquea
+    pub blocking: Vec<DependencyTask>,
+}
+
+impl TaskDependencies {
+    pub fn is_empty(&self) -> bool {
+        self.blocked_by.is_empty() && self.blocking.is_empty()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskDependenciesResponse {
+    pub task_id: Uuid,
+    #[serde(flatten)]
+    pub dependencies: TaskDependencies,
+}
+
+// Endpoint para obtener las tareas que bloquean a una tarea y las que esta bloquea
+pub async fn get_task_dependencies(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Path(task_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let dependencies = db::as_user(&pool, user_id, async |conn| {
+        let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM get_task($1, $2))")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+        if !exists {
+            return Ok(None);
+        }
+        load_dependencies(conn, user_id, task_id).await.map(Some)
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    let response = TaskDependenciesResponse { task_id, dependencies };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
+
+pub async fn load_dependencies(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_id: Uuid,
+) -> Result<TaskDependencies, sqlx::Error> {
+    let blocked_by = sqlx::query_as::<_, DependencyTask>("SELECT * FROM get_task_blockers($1, $2)")
+        .bind(user_id)
+        .bind(task_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let blocking = sqlx::query_as::<_, DependencyTask>("SELECT * FROM get_blocked_tasks($1, $2)")
+        .bind(user_id)
+        .bind(task_id)
+        .fetch_all(conn)
+        .await?;
+
+    Ok(TaskDependencies {
+        blocked: blocked_by.iter().any(|task| task.status != TaskStatus::Done),
+        blocked_by,
+        blocking,
+    })
+}
+
+/// Avisos para la respuesta de una actualización que deja la tarea en `status`. Con
+/// `TASK_BLOCKED_STATUS_CHANGE=reject` el cambio a doing o done ya se habrá rechazado, pero
+/// una tarea que ya estaba en curso puede haber quedado bloqueada después.
+pub async fn blocked_status_warnings(
+    conn: &mut PgConnection,
+    task_id: Uuid,
+    status: TaskStatus,
+) -> Result<Vec<String>, sqlx::Error> {
+    if status == TaskStatus::Todo {
+        return Ok(Vec::new());
+    }
+
+    let blocked: bool = sqlx::query_scalar("SELECT task_is_blocked($1)")
+        .bind(task_id)
+        .fetch_one(conn)
+        .await?;
+
+    Ok(if blocked {
+        vec!["Task is blocked by unfinished tasks".to_string()]
+    } else {
+        Vec::new()
+    })
+}
@@ -0,0 +1,19 @@
+// task_dependencies/mod.rs
+use axum::{
+    routing::{get, post, delete},
+    Router
+};
+use sqlx::PgPool;
+
+mod get;
+mod post;
+mod delete;
+
+pub use get::{blocked_status_warnings, load_dependencies, TaskDependencies};
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(&format!("{}/{{task_id}}/dependencies", base), get(get::get_task_dependencies))
+        .route(&format!("{}/{{task_id}}/dependencies/{{blocked_by_task_id}}", base), post(post::add_task_dependency))
+        .route(&format!("{}/{{task_id}}/dependencies/{{blocked_by_task_id}}", base), delete(delete::remove_task_dependency))
+}
@@ -0,0 +1,59 @@
+// task_dependencies/post.rs
+use axum::{extract::State, http::StatusCode};
+use serde::Serialize;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+
+#[derive(Debug, Serialize)]
+pub struct AddTaskDependencyResponse {
+    pub message: String,
+    pub added: bool,
+}
+
+// Endpoint para indicar que `task_id` está bloqueada por `blocked_by_task_id`. Es
+// idempotente: si la dependencia ya existía responde 200 con `added: false`.
+pub async fn add_task_dependency(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path((task_id, blocked_by_task_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<AddTaskDependencyResponse>), AppError> {
+    // Ejecutar la función de la base de datos
+    let added: bool = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT add_task_dependency($1, $2, $3)")
+            .bind(user_id)
+            .bind(task_id)
+            .bind(blocked_by_task_id)
+            .fetch_one(conn)
+            .await
+    })
+    .await
+    .map_err(|e| match db::violated_constraint(&e) {
+        Some("task_dependencies_task_fkey" | "task_dependencies_blocker_fkey") => {
+            AppError::not_found("Task not found or you don't have permission")
+        }
+        Some("task_dependencies_not_self" | "task_dependencies_cycle") => AppError::Conflict {
+            code: "dependency_cycle",
+            message: "Dependency would create a cycle".to_string(),
+        },
+        _ => AppError::from(e),
+    })?;
+
+    let (status, message) = if added {
+        (StatusCode::CREATED, "Dependency added successfully")
+    } else {
+        (StatusCode::OK, "Dependency already exists")
+    };
+
+    Ok((
+        status,
+        Json(AddTaskDependencyResponse {
+            message: message.to_string(),
+            added,
+        }),
+    ))
+}
@@ -6,8 +6,8 @@ use axum::{
 use sqlx::PgPool;
 
 mod get;
-mod post;
-mod delete;
+pub(crate) mod post;
+pub(crate) mod delete;
 
 // Importar el nuevo módulo para múltiples tags
 mod get_multiple;
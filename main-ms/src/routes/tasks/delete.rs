@@ -1,14 +1,13 @@
-use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
-};
+use axum::extract::State;
 use serde::Serialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{updated_version, IfMatch};
 
 #[derive(Debug, Serialize)]
 pub struct DeleteTaskResponse {
@@ -16,67 +15,38 @@ pub struct DeleteTaskResponse {
     pub deleted: bool,
 }
 
-
+// Con `If-Match`, solo elimina la tarea si no cambió desde esa versión
 pub async fn delete_task(
     State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    IfMatch(if_match): IfMatch,
     Path(task_id): Path<Uuid>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
-    // Extraer el token de los headers
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+) -> Result<Json<DeleteTaskResponse>, AppError> {
+    remove_task(&pool, user_id, task_id, if_match).await.map(Json)
+}
 
+/// Elimina la tarea; con `if_match`, solo si su versión es una de esas
+pub(crate) async fn remove_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    task_id: Uuid,
+    if_match: Option<Vec<i32>>,
+) -> Result<DeleteTaskResponse, AppError> {
     // Ejecutar la función de la base de datos para eliminar la tarea
-    match sqlx::query_scalar(
-        "SELECT delete_task($1, $2)"
-    )
-    .bind(user_id)
-    .bind(task_id)
-    .fetch_one(&pool)
-    .await
-    {
-        Ok(deleted) => {
-            if deleted {
-                let response = DeleteTaskResponse {
-                    message: "Task deleted successfully".to_string(),
-                    deleted: true,
-                };
-                (StatusCode::OK, Json(response)).into_response()
-            } else {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(ErrorResponse {
-                        error: "Task not found or you don't have permission to delete it".to_string(),
-                    }),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error deleting task: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
-}
\ No newline at end of file
+    let result = db::as_user(pool, user_id, async |conn| {
+        sqlx::query_as::<_, (bool, i32)>("SELECT deleted, current_version FROM delete_task($1, $2, $3)")
+            .bind(user_id)
+            .bind(task_id)
+            .bind(&if_match)
+            .fetch_optional(conn)
+            .await
+    })
+    .await?;
+
+    updated_version(result, "Task not found or you don't have permission to delete it")?;
+
+    Ok(DeleteTaskResponse {
+        message: "Task deleted successfully".to_string(),
+        deleted: true,
+    })
+}
@@ -1,14 +1,17 @@
-use axum::{
-    extract::{Path, Query, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
-};
+use axum::{extract::State, response::Response};
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row}; // Añadir Row aquí
+use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Path, Query};
+use crate::models::{TaskPriority, TaskStatus};
+use crate::utils::auth::CurrentUser;
+use crate::routes::task_dependencies::{load_dependencies, TaskDependencies};
+use crate::utils::etag::{body_tag, json_with_etag, version_tag, version_tag_with, IfNoneMatch};
+
+use super::subtasks::{attach_progress, attach_subtrees};
 
 #[derive(Debug, Deserialize)]
 pub struct GetTasksQuery {
@@ -19,6 +22,11 @@ pub struct GetTasksQuery {
     pub sort_by: Option<String>,
     pub page: Option<i32>,
     pub limit: Option<i32>,
+    /// `flat` (todas las tareas), `top_level` (solo las que no son subtareas) o `nested`
+    /// (las de nivel superior, cada una con sus subtareas anidadas)
+    pub hierarchy: Option<String>,
+    /// Solo las tareas bloqueadas por otras sin completar (`true`) o solo las demás (`false`)
+    pub blocked: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -28,11 +36,63 @@ pub struct TaskResponse {
     pub project_id: Option<Uuid>,
     pub title: String,
     pub description: Option<String>,
-    pub status: String,
-    pub priority: String,
+    pub status: TaskStatus,
+    pub priority: TaskPriority,
     pub due_date: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    pub version: i32,
+    pub parent_task_id: Option<Uuid>,
+    /// Regla RRULE normalizada, si la tarea se repite
+    pub recurrence_rule: Option<String>,
+    /// La repetición anterior, cuya finalización generó esta tarea
+    pub recurrence_source_id: Option<Uuid>,
+    /// Solo si la tarea tiene subtareas
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress: Option<TaskProgress>,
+    /// Solo en `GET /tasks/{id}/tree` y en los listados con `hierarchy=nested`
+    #[sqlx(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subtasks: Option<Vec<TaskResponse>>,
+}
+
+/// Progreso de una tarea según sus subtareas directas
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct TaskProgress {
+    pub total: i32,
+    pub completed: i32,
+    /// Porcentaje completado, redondeado hacia abajo
+    pub percent: i32,
+}
+
+impl TaskProgress {
+    pub fn new(total: i32, completed: i32) -> Self {
+        let percent = if total == 0 { 0 } else { completed * 100 / total };
+        Self { total, completed, percent }
+    }
+
+    /// `None` si no hay subtareas
+    pub fn from_subtasks(subtasks: &[TaskResponse]) -> Option<Self> {
+        if subtasks.is_empty() {
+            return None;
+        }
+
+        let completed = subtasks
+            .iter()
+            .filter(|task| task.status == TaskStatus::Done)
+            .count();
+        Some(Self::new(subtasks.len() as i32, completed as i32))
+    }
+}
+
+/// Una tarea con sus dependencias (`GET /tasks/{id}`)
+#[derive(Debug, Serialize)]
+pub struct TaskDetailResponse {
+    #[serde(flatten)]
+    pub task: TaskResponse,
+    #[serde(flatten)]
+    pub dependencies: TaskDependencies,
 }
 
 #[derive(Debug, Serialize)]
@@ -48,245 +108,130 @@ pub struct TasksResponse {
 // Endpoint para obtener todas las tareas del usuario con filtros opcionales
 pub async fn get_tasks(
     State(pool): State<PgPool>,
-    headers: HeaderMap,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
     Query(query): Query<GetTasksQuery>,
-) -> impl IntoResponse {
-    // Extraer el token de los headers
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
+) -> Result<Response, AppError> {
+    let mut validator = Validator::new();
 
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+    let status: Option<TaskStatus> = validator.parse("status", query.status.as_deref());
+    let priority: Option<TaskPriority> = validator.parse("priority", query.priority.as_deref());
 
-    // Validar status si se proporciona
-    if let Some(ref status) = query.status {
-        if !["todo", "doing", "done"].contains(&status.as_str()) {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid status. Must be one of: todo, doing, done".to_string(),
-                }),
-            )
-                .into_response();
-        }
+    if let Some(ref sort_by) = query.sort_by {
+        validator.check(
+            ["title", "priority", "due_date"].contains(&sort_by.as_str()),
+            "sort_by",
+            "must be one of: title, priority, due_date",
+        );
     }
 
-    // Validar priority si se proporciona
-    if let Some(ref priority) = query.priority {
-        if !["low", "med", "high", "urgent"].contains(&priority.as_str()) {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid priority. Must be one of: low, med, high, urgent".to_string(),
-                }),
-            )
-                .into_response();
-        }
-    }
+    let hierarchy = query.hierarchy.as_deref().unwrap_or("flat");
+    validator.check(
+        ["flat", "top_level", "nested"].contains(&hierarchy),
+        "hierarchy",
+        "must be one of: flat, top_level, nested",
+    );
+    validator.finish()?;
 
-    if let Some(ref sort_by) = query.sort_by {
-        if !["title", "priority", "due_date"].contains(&sort_by.as_str()) {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid sort_by. Must be one of: title, priority, due_date".to_string(),
-                }),
-            )
-                .into_response();
-        }
-    }
+    let top_level_only = hierarchy != "flat";
 
     let page = query.page.unwrap_or(1).max(1);
     let limit = query.limit.unwrap_or(10).clamp(1, 100);
     let offset = (page - 1) * limit;
 
-    let total_count_result = sqlx::query(
-        "SELECT COUNT(*) as count FROM get_user_tasks_count($1, $2::task_status, $3::task_priority, $4, $5)"
-    )
-    .bind(user_id)
-    .bind(&query.status)
-    .bind(&query.priority)
-    .bind(query.project_id)
-    .bind(&query.search)
-    .fetch_one(&pool)
-    .await;
-
-    let total_count: i64 = match total_count_result {
-        Ok(row) => row.try_get("count").unwrap_or(0),
-        Err(_) => 0,
-    };
+    // La función ya retorna el conteo: un COUNT(*) sobre ella siempre daría 1
+    let total_count: i64 = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT count FROM get_user_tasks_count($1, $2, $3, $4, $5, $6, $7)")
+            .bind(user_id)
+            .bind(status)
+            .bind(priority)
+            .bind(query.project_id)
+            .bind(&query.search)
+            .bind(top_level_only)
+            .bind(query.blocked)
+            .fetch_one(conn)
+            .await
+    })
+    .await?;
 
     let total_pages = ((total_count as f64) / (limit as f64)).ceil() as i32;
 
-    match sqlx::query(
-        "SELECT json_agg(t) as tasks FROM get_user_tasks_paginated($1, $2::task_status, $3::task_priority, $4, $5, $6, $7, $8) t"
-    )
-    .bind(user_id)
-    .bind(&query.status)
-    .bind(&query.priority)
-    .bind(query.project_id)
-    .bind(&query.search)
-    .bind(&query.sort_by)
-    .bind(limit)
-    .bind(offset)
-    .fetch_one(&pool)
-    .await
-    {
-        Ok(row) => {
-            let tasks_json: Option<serde_json::Value> = row.try_get("tasks").unwrap_or(None);
-            
-            match tasks_json {
-                Some(serde_json::Value::Array(arr)) if !arr.is_empty() => {
-                    match serde_json::from_value::<Vec<TaskResponse>>(serde_json::Value::Array(arr)) {
-                        Ok(tasks) => {
-                            let response = TasksResponse {
-                                tasks,
-                                message: "Tasks retrieved successfully".to_string(),
-                                total_count,
-                                page,
-                                limit,
-                                total_pages,
-                            };
-                            (StatusCode::OK, Json(response)).into_response()
-                        }
-                        Err(e) => {
-                            let error_message = format!("Error parsing tasks: {}", e);
-                            eprintln!("{}", error_message);
-                            
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ErrorResponse { error: error_message }),
-                            )
-                                .into_response()
-                        }
-                    }
-                }
-                _ => {
-                    let response = TasksResponse {
-                        tasks: Vec::new(),
-                        message: "No tasks found".to_string(),
-                        total_count,
-                        page,
-                        limit,
-                        total_pages,
-                    };
-                    (StatusCode::OK, Json(response)).into_response()
-                }
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error retrieving tasks: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
+    let tasks = db::as_user(&pool, user_id, async |conn| {
+        let mut tasks = sqlx::query_as::<_, TaskResponse>(
+            "SELECT * FROM get_user_tasks_paginated($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+        )
+        .bind(user_id)
+        .bind(status)
+        .bind(priority)
+        .bind(query.project_id)
+        .bind(&query.search)
+        .bind(&query.sort_by)
+        .bind(limit)
+        .bind(offset)
+        .bind(top_level_only)
+        .bind(query.blocked)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        // Los filtros se aplican a las tareas del listado; sus subtareas se anidan todas
+        if hierarchy == "nested" {
+            attach_subtrees(conn, user_id, &mut tasks).await?;
+        } else {
+            attach_progress(conn, user_id, &mut tasks).await?;
         }
-    }
+        Ok(tasks)
+    })
+    .await?;
+
+    let message = if tasks.is_empty() {
+        "No tasks found"
+    } else {
+        "Tasks retrieved successfully"
+    };
+
+    let response = TasksResponse {
+        tasks,
+        message: message.to_string(),
+        total_count,
+        page,
+        limit,
+        total_pages,
+    };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
 }
 
 
-// Endpoint para obtener una tarea específica por ID
+// Endpoint para obtener una tarea específica por ID, con el progreso de sus subtareas, las
+// tareas que la bloquean y las que bloquea
 pub async fn get_task_by_id(
     State(pool): State<PgPool>,
-    headers: HeaderMap,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
     Path(task_id): Path<Uuid>,
-) -> impl IntoResponse {
-    // Extraer el token de los headers
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
+) -> Result<Response, AppError> {
+    let response = db::as_user(&pool, user_id, async |conn| {
+        let task = sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_task($1, $2)")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let Some(mut task) = task else {
+            return Ok(None);
+        };
+        attach_progress(conn, user_id, std::slice::from_mut(&mut task)).await?;
+        let dependencies = load_dependencies(conn, user_id, task_id).await?;
+        Ok(Some(TaskDetailResponse { task, dependencies }))
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    // Las dependencias y el progreso cambian sin que cambie la versión de la tarea
+    let etag = match (&response.task.progress, response.dependencies.is_empty()) {
+        (None, true) => version_tag(response.task.version),
+        (None, false) => version_tag_with(response.task.version, &response.dependencies)?,
+        (Some(progress), _) => version_tag_with(response.task.version, &(&response.dependencies, progress))?,
     };
-
-    // Usar query directamente y luego deserializar manualmente
-    match sqlx::query(
-        "SELECT json_agg(t) as task FROM get_task($1, $2) t"
-    )
-    .bind(user_id)
-    .bind(task_id)
-    .fetch_one(&pool)
-    .await
-    {
-        Ok(row) => {
-            let task_json: Option<serde_json::Value> = row.try_get("task").unwrap_or(None);
-            
-            match task_json {
-                Some(serde_json::Value::Array(arr)) if !arr.is_empty() => {
-                    match serde_json::from_value::<Vec<TaskResponse>>(serde_json::Value::Array(arr)) {
-                        Ok(mut tasks) => {
-                            if let Some(task) = tasks.pop() {
-                                (StatusCode::OK, Json(task)).into_response()
-                            } else {
-                                (
-                                    StatusCode::NOT_FOUND,
-                                    Json(ErrorResponse {
-                                        error: "Task not found".to_string(),
-                                    }),
-                                )
-                                    .into_response()
-                            }
-                        }
-                        Err(e) => {
-                            let error_message = format!("Error parsing task: {}", e);
-                            eprintln!("{}", error_message);
-                            
-                            (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(ErrorResponse { error: error_message }),
-                            )
-                                .into_response()
-                        }
-                    }
-                }
-                _ => {
-                    (
-                        StatusCode::NOT_FOUND,
-                        Json(ErrorResponse {
-                            error: "Task not found".to_string(),
-                        }),
-                    )
-                        .into_response()
-                }
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error retrieving task: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
+    Ok(json_with_etag(etag, &if_none_match, response))
 }
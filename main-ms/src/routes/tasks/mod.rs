@@ -1,26 +1,60 @@
 use axum::{
-    routing::{get, post, put, delete}, 
+    routing::{get, post, put, patch, delete}, 
     Router
 };
 use sqlx::PgPool;
 
+use crate::db;
+use crate::error::AppError;
+
 mod get;
-mod post;
-mod put;
-mod delete;
+pub(crate) mod post;
+pub(crate) mod put;
+pub(crate) mod patch;
+pub(crate) mod delete;
+pub(crate) mod subtasks;
+mod recurrence;
 
 pub fn routes(base: &str) -> Router<PgPool> {
     Router::new()
-        .route(&format!("{}", base), post(post::create_task))
-        .route(&format!("{}", base), get(get::get_tasks))
+        .route(base, post(post::create_task))
+        .route(base, get(get::get_tasks))
         .route(&format!("{}/{{task_id}}", base), get(get::get_task_by_id))
         .route(&format!("{}/{{task_id}}", base), put(put::update_task))
+        .route(&format!("{}/{{task_id}}", base), patch(patch::patch_task))
         .route(&format!("{}/{{task_id}}", base), delete(delete::delete_task))
+        .route(&format!("{}/{{task_id}}/subtasks", base), post(subtasks::create_subtask))
+        .route(&format!("{}/{{task_id}}/subtasks", base), get(subtasks::get_subtasks))
+        .route(&format!("{}/{{task_id}}/parent", base), put(subtasks::move_task))
+        .route(&format!("{}/{{task_id}}/tree", base), get(subtasks::get_task_tree))
+        .route(&format!("{}/{{task_id}}/occurrences", base), get(recurrence::get_occurrences))
         .route(&format!("{}/config/past-dates-enabled", base), get(get_past_dates_config))
 }
 
+/// Traduce los errores de la base de datos al escribir una tarea: claves foráneas y las
+/// reglas de jerarquía que comprueban los triggers
+pub(crate) fn task_write_error(e: sqlx::Error) -> AppError {
+    match db::violated_constraint(&e) {
+        Some("tasks_project_fkey") => AppError::invalid_field("project_id", "project not found"),
+        Some("tasks_parent_fkey") => AppError::invalid_field("parent_task_id", "parent task not found"),
+        Some("tasks_parent_not_self" | "tasks_parent_cycle") => AppError::Conflict {
+            code: "task_hierarchy_cycle",
+            message: "A task cannot be moved under itself or one of its subtasks".to_string(),
+        },
+        Some("tasks_open_subtasks") => AppError::Conflict {
+            code: "task_has_open_subtasks",
+            message: "Task has open subtasks; complete them first".to_string(),
+        },
+        Some("tasks_unfinished_blockers") => AppError::Conflict {
+            code: "task_blocked",
+            message: "Task is blocked by unfinished tasks; complete them first".to_string(),
+        },
+        _ => AppError::from(e),
+    }
+}
+
 async fn get_past_dates_config() -> impl axum::response::IntoResponse {
-    use std::env;
+    use crate::config::Config;
     use axum::Json;
     use serde::Serialize;
 
@@ -29,9 +63,7 @@ async fn get_past_dates_config() -> impl axum::response::IntoResponse {
         past_dates_enabled: bool,
     }
 
-    let past_dates_enabled = env::var("PAST_DATES_ENABLED")
-        .unwrap_or_else(|_| "false".to_string())
-        .to_lowercase() == "true";
+    let past_dates_enabled = Config::get().tasks.past_dates_enabled;
 
     Json(PastDatesConfig { past_dates_enabled })
 }
\ No newline at end of file
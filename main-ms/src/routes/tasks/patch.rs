@@ -0,0 +1,201 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{types::Json as JsonValue, PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Json, Path};
+use crate::models::{RecurrenceRule, TaskPriority, TaskStatus};
+use crate::routes::task_dependencies::blocked_status_warnings;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{updated_version, version_tag, IfMatch};
+
+use super::post::validate_title;
+use super::recurrence::create_next_occurrence;
+use super::task_write_error;
+use super::put::UpdateTaskResponse;
+
+/// Cuerpo de PATCH como JSON Merge Patch (RFC 7396): una clave ausente no cambia el campo
+/// y una clave con `null` lo borra. En cada campo, `None` es "ausente" y `Some(None)` es
+/// `null`; al serializar se omiten los ausentes, así que `patch_task` recibe las mismas claves.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct TaskPatch {
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<Option<Uuid>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub status: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub priority: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<Option<chrono::DateTime<chrono::Utc>>>,
+    #[serde(default, deserialize_with = "present", skip_serializing_if = "Option::is_none")]
+    pub recurrence_rule: Option<Option<String>>,
+}
+
+/// Solo se llama si la clave está presente: `null` pasa a ser `Some(None)`
+fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+impl TaskPatch {
+    fn is_empty(&self) -> bool {
+        self.title.is_none()
+            && self.project_id.is_none()
+            && self.description.is_none()
+            && self.status.is_none()
+            && self.priority.is_none()
+            && self.due_date.is_none()
+            && self.recurrence_rule.is_none()
+    }
+
+    /// Además de validar, deja `recurrence_rule` en su forma normalizada
+    fn validate(&mut self) -> Result<(), AppError> {
+        let mut validator = Validator::new();
+
+        if self.is_empty() {
+            validator.error("body", "at least one field must be provided for update");
+        }
+
+        // title, status y priority son obligatorios en la tarea: no se pueden borrar
+        match self.title {
+            Some(Some(ref title)) => validate_title(&mut validator, title),
+            Some(None) => validator.error("title", "cannot be null"),
+            None => {}
+        }
+
+        match self.status {
+            Some(Some(ref status)) => {
+                validator.parse::<TaskStatus>("status", Some(status));
+            }
+            Some(None) => validator.error("status", "cannot be null"),
+            None => {}
+        }
+
+        match self.priority {
+            Some(Some(ref priority)) => {
+                validator.parse::<TaskPriority>("priority", Some(priority));
+            }
+            Some(None) => validator.error("priority", "cannot be null"),
+            None => {}
+        }
+
+        if let Some(Some(ref mut recurrence_rule)) = self.recurrence_rule {
+            if let Some(rule) = validator.parse::<RecurrenceRule>("recurrence_rule", Some(recurrence_rule)) {
+                *recurrence_rule = rule.to_string();
+            }
+        }
+
+        validator.finish()
+    }
+}
+
+pub async fn patch_task(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    IfMatch(if_match): IfMatch,
+    Path(task_id): Path<Uuid>,
+    Json(body): Json<Map<String, Value>>,
+) -> Result<impl IntoResponse, AppError> {
+    let patch = parse_patch(body)?;
+
+    // Ejecutar la función de la base de datos
+    let written = db::as_user(&pool, user_id, async |conn| {
+        write_patch(conn, user_id, task_id, &patch, if_match).await
+    })
+    .await
+    .map_err(task_write_error)?;
+
+    let version = updated_version(written.result, "Task not found or you don't have permission to update it")?;
+    Ok(([(header::ETAG, version_tag(version))], Json(written.into_response())))
+}
+
+/// Interpreta y valida un merge patch
+pub(crate) fn parse_patch(body: Map<String, Value>) -> Result<TaskPatch, AppError> {
+    // Se extrae primero como objeto: serde también aceptaría un arreglo como `TaskPatch`,
+    // con los campos por posición
+    let mut patch: TaskPatch = serde_json::from_value(Value::Object(body)).map_err(|e| AppError::InvalidRequest {
+        status: StatusCode::UNPROCESSABLE_ENTITY,
+        code: "invalid_body",
+        message: format!("Invalid merge patch: {}", e),
+    })?;
+    patch.validate()?;
+    Ok(patch)
+}
+
+/// Resultado de `write_patch`; `result` es el de `patch_task` (ver `updated_version`)
+pub(crate) struct WrittenPatch {
+    pub result: Option<(bool, i32)>,
+    pub warnings: Vec<String>,
+    pub next_occurrence_id: Option<Uuid>,
+}
+
+impl WrittenPatch {
+    /// Respuesta de un patch que se aplicó
+    pub(crate) fn into_response(self) -> UpdateTaskResponse {
+        UpdateTaskResponse {
+            message: "Task updated successfully".to_string(),
+            updated: true,
+            warnings: self.warnings,
+            next_occurrence_id: self.next_occurrence_id,
+        }
+    }
+}
+
+/// Aplica un patch ya validado en la transacción de `conn`. Si completa una tarea
+/// recurrente crea también la siguiente repetición.
+pub(crate) async fn write_patch(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_id: Uuid,
+    patch: &TaskPatch,
+    if_match: Option<Vec<i32>>,
+) -> Result<WrittenPatch, sqlx::Error> {
+    // Ya validado: si el patch cambia el estado, es uno de los valores del enum
+    let status: Option<TaskStatus> = patch
+        .status
+        .as_ref()
+        .and_then(|status| status.as_deref())
+        .and_then(|status| status.parse().ok());
+
+    let result = sqlx::query_as::<_, (bool, i32)>("SELECT updated, current_version FROM patch_task($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(task_id)
+        .bind(JsonValue(patch))
+        .bind(if_match)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let (Some((true, _)), Some(status)) = (result, status) else {
+        return Ok(WrittenPatch {
+            result,
+            warnings: Vec::new(),
+            next_occurrence_id: None,
+        });
+    };
+
+    let warnings = blocked_status_warnings(conn, task_id, status).await?;
+    let next_occurrence_id = match status {
+        TaskStatus::Done => create_next_occurrence(conn, user_id, task_id).await?,
+        _ => None,
+    };
+    Ok(WrittenPatch {
+        result,
+        warnings,
+        next_occurrence_id,
+    })
+}
@@ -1,15 +1,20 @@
 use axum::{
     extract::State,
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
+    http::StatusCode,
+    response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
-use std::env;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::config::Config;
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::Json;
+use crate::models::{RecurrenceRule, TaskPriority, TaskStatus};
+use crate::utils::auth::CurrentUser;
+
+use super::task_write_error;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateTaskRequest {
@@ -19,6 +24,8 @@ pub struct CreateTaskRequest {
     pub status: Option<String>,
     pub priority: Option<String>,
     pub due_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// Regla RRULE (por ejemplo `FREQ=WEEKLY;BYDAY=MO,WE`); la fecha límite es el inicio
+    pub recurrence_rule: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -27,95 +34,48 @@ pub struct CreateTaskResponse {
     pub message: String,
 }
 
-pub async fn create_task(
-    State(pool): State<PgPool>,
-    headers: HeaderMap,
-    Json(payload): Json<CreateTaskRequest>,
-) -> impl IntoResponse {
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
-
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
+impl CreateTaskResponse {
+    pub(crate) fn new(task_id: Uuid, parent_task_id: Option<Uuid>) -> Self {
+        let message = match parent_task_id {
+            Some(_) => "Subtask created successfully",
+            None => "Task created successfully",
+        };
+        CreateTaskResponse {
+            task_id,
+            message: message.to_string(),
         }
-    };
-
-    if payload.title.trim().is_empty() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Title is required".to_string(),
-            }),
-        )
-            .into_response();
     }
+}
 
-    let title_length = payload.title.trim().chars().count();
-    if title_length < 3 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Title must be at least 3 characters long".to_string(),
-            }),
-        )
-            .into_response();
-    }
+pub async fn create_task(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Json(payload): Json<CreateTaskRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let task_id = insert_task(&pool, user_id, payload, None).await?;
+    Ok((StatusCode::CREATED, Json(CreateTaskResponse::new(task_id, None))))
+}
 
-    if title_length > 120 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Title must be no more than 120 characters long".to_string(),
-            }),
-        )
-            .into_response();
-    }
+/// Valida y crea una tarea; con `parent_task_id` se crea como subtarea de esa tarea
+pub(crate) async fn insert_task(
+    pool: &PgPool,
+    user_id: Uuid,
+    payload: CreateTaskRequest,
+    parent_task_id: Option<Uuid>,
+) -> Result<Uuid, AppError> {
+    let mut validator = Validator::new();
 
-    let status = match payload.status.as_deref() {
-        Some("todo") | Some("doing") | Some("done") => {
-            payload.status.clone().unwrap()
-        }
-        Some(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid status. Must be one of: todo, doing, done".to_string(),
-                }),
-            )
-                .into_response()
-        }
-        None => "todo".to_string(),
-    };
+    validate_title(&mut validator, &payload.title);
 
-    let priority = match payload.priority.as_deref() {
-        Some("low") | Some("med") | Some("high") | Some("urgent") => payload.priority.clone().unwrap(),
-        Some(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid priority. Must be one of: low, med, high, urgent".to_string(),
-                }),
-            )
-                .into_response()
-        }
-        None => "med".to_string(),
-    };
+    let status: Option<TaskStatus> = validator.parse("status", payload.status.as_deref());
+    let priority: Option<TaskPriority> = validator.parse("priority", payload.priority.as_deref());
+    let recurrence_rule: Option<RecurrenceRule> =
+        validator.parse("recurrence_rule", payload.recurrence_rule.as_deref());
+    validator.finish()?;
 
     let mut due_date = payload.due_date;
-    
-    let past_dates_enabled = env::var("PAST_DATES_ENABLED")
-        .unwrap_or_else(|_| "false".to_string())
-        .to_lowercase() == "true";
+
+    let past_dates_enabled = Config::get().tasks.past_dates_enabled;
 
     if !past_dates_enabled {
         if let Some(date) = due_date {
@@ -126,35 +86,39 @@ pub async fn create_task(
         }
     }
 
-    match sqlx::query_scalar(
-        "SELECT create_task($1, $2, $3, $4, $5::task_status, $6::task_priority, $7)"
-    )
-    .bind(user_id)
-    .bind(&payload.title)
-    .bind(payload.project_id)
-    .bind(payload.description)
-    .bind(status)
-    .bind(priority)
-    .bind(due_date)
-    .fetch_one(&pool)
+    db::as_user(pool, user_id, async |conn| {
+        sqlx::query_scalar("SELECT create_task($1, $2, $3, $4, $5, $6, $7, $8, $9)")
+            .bind(user_id)
+            .bind(&payload.title)
+            .bind(payload.project_id)
+            .bind(payload.description)
+            .bind(status.unwrap_or_default())
+            .bind(priority.unwrap_or_default())
+            .bind(due_date)
+            .bind(parent_task_id)
+            .bind(recurrence_rule.map(|rule| rule.to_string()))
+            .fetch_one(conn)
+            .await
+    })
     .await
-    {
-        Ok(task_id) => {
-            let response = CreateTaskResponse {
-                task_id,
-                message: "Task created successfully".to_string(),
-            };
-            (StatusCode::CREATED, Json(response)).into_response()
-        }
-        Err(e) => {
-            let error_message = format!("Error creating task: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
+    .map_err(|e| {
+        // La tarea padre viene en la ruta: si no existe, es la ruta la que no existe
+        if db::violated_constraint(&e) == Some("tasks_parent_fkey") {
+            AppError::not_found("Parent task not found")
+        } else {
+            task_write_error(e)
         }
+    })
+}
+
+/// Reglas del título compartidas por POST, PUT y PATCH
+pub(super) fn validate_title(validator: &mut Validator, title: &str) {
+    let title_length = title.trim().chars().count();
+    if title_length == 0 {
+        validator.error("title", "is required");
+    } else if title_length < 3 {
+        validator.error("title", "must be at least 3 characters long");
+    } else if title_length > 120 {
+        validator.error("title", "must be no more than 120 characters long");
     }
-}
\ No newline at end of file
+}
@@ -1,159 +1,105 @@
 use axum::{
-    extract::{Path, State},
-    http::{HeaderMap, StatusCode},
-    response::{Json, IntoResponse},
+    extract::State,
+    http::header,
+    response::IntoResponse,
 };
 use serde::{Deserialize, Serialize};
 use sqlx::PgPool;
 use uuid::Uuid;
 
-use crate::utils::token::extract_user_id;
-use crate::utils::extract_token::{extract_token_from_headers, ErrorResponse};
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Json, Path};
+use crate::models::{RecurrenceRule, TaskPriority, TaskStatus};
+use crate::routes::task_dependencies::blocked_status_warnings;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{updated_version, version_tag, IfMatch};
 
+use super::post::validate_title;
+use super::recurrence::create_next_occurrence;
+use super::task_write_error;
+
+/// PUT reemplaza la tarea completa: un campo omitido o `null` queda vacío, y `status` y
+/// `priority` vuelven a su valor por defecto. Para cambiar solo algunos campos está PATCH.
 #[derive(Debug, Deserialize)]
 pub struct UpdateTaskRequest {
-    pub title: Option<String>,
+    pub title: String,
     pub project_id: Option<Uuid>,
     pub description: Option<String>,
     pub status: Option<String>,
     pub priority: Option<String>,
     pub due_date: Option<chrono::DateTime<chrono::Utc>>,
+    pub recurrence_rule: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
 pub struct UpdateTaskResponse {
     pub message: String,
     pub updated: bool,
+    /// Por ejemplo, que la tarea quedó en curso con dependencias sin completar
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// La repetición que se creó al completar una tarea recurrente
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_occurrence_id: Option<Uuid>,
 }
 
 pub async fn update_task(
     State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    IfMatch(if_match): IfMatch,
     Path(task_id): Path<Uuid>,
-    headers: HeaderMap,
     Json(payload): Json<UpdateTaskRequest>,
-) -> impl IntoResponse {
-    // Extraer el token de los headers
-    let token = match extract_token_from_headers(&headers) {
-        Ok(token) => token,
-        Err(error_response) => return error_response.into_response(),
-    };
+) -> Result<impl IntoResponse, AppError> {
+    let mut validator = Validator::new();
 
-    // Extraer el user_id usando el token
-    let user_id = match extract_user_id(&token).await {
-        Ok(id) => id,
-        Err(_) => {
-            return (
-                StatusCode::UNAUTHORIZED,
-                Json(ErrorResponse {
-                    error: "Invalid or expired token".to_string(),
-                }),
-            )
-                .into_response()
-        }
-    };
+    validate_title(&mut validator, &payload.title);
 
-    // Validar que al menos un campo sea proporcionado para actualizar
-    if payload.title.is_none() 
-        && payload.project_id.is_none() 
-        && payload.description.is_none() 
-        && payload.status.is_none() 
-        && payload.priority.is_none() 
-        && payload.due_date.is_none() {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "At least one field must be provided for update".to_string(),
-            }),
-        )
-            .into_response();
-    }
+    let status: Option<TaskStatus> = validator.parse("status", payload.status.as_deref());
+    let priority: Option<TaskPriority> = validator.parse("priority", payload.priority.as_deref());
+    let recurrence_rule: Option<RecurrenceRule> =
+        validator.parse("recurrence_rule", payload.recurrence_rule.as_deref());
+    validator.finish()?;
 
-    // Validar el título si se proporciona
-    if let Some(ref title) = payload.title {
-        if title.trim().is_empty() {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Title cannot be empty".to_string(),
-                }),
-            )
-                .into_response();
-        }
-    }
+    let status = status.unwrap_or_default();
 
-    // Validar status si se proporciona
-let status = match payload.status.as_deref() {
-    Some("todo") | Some("doing") | Some("done") => {
-        payload.status.clone()
-    }
-    Some(_) => {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: "Invalid status. Must be one of: todo, doing, done".to_string(),
-            }),
-        )
-            .into_response()
-    }
-    None => None,
-};
+    // Ejecutar la función de la base de datos
+    let (result, warnings, next_occurrence_id) = db::as_user(&pool, user_id, async |conn| {
+        let result = sqlx::query_as::<_, (bool, i32)>("SELECT updated, current_version FROM update_task($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)")
+            .bind(user_id)
+            .bind(task_id)
+            .bind(payload.project_id)
+            .bind(payload.title)
+            .bind(payload.description)
+            .bind(status)
+            .bind(priority.unwrap_or_default())
+            .bind(payload.due_date)
+            .bind(recurrence_rule.map(|rule| rule.to_string()))
+            .bind(if_match)
+            .fetch_optional(&mut *conn)
+            .await?;
 
-    // Validar priority si se proporciona
-    let priority = match payload.priority.as_deref() {
-        Some("low") | Some("med") | Some("high") | Some("urgent") => payload.priority.clone(),
-        Some(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(ErrorResponse {
-                    error: "Invalid priority. Must be one of: low, med, high, urgent".to_string(),
-                }),
-            )
-                .into_response()
+        if !matches!(result, Some((true, _))) {
+            return Ok((result, Vec::new(), None));
         }
-        None => None,
-    };
 
-    // Ejecutar la función de la base de datos
-    match sqlx::query_scalar(
-        "SELECT update_task($1, $2, $3, $4, $5, $6::task_status, $7::task_priority, $8)"
-    )
-    .bind(user_id)
-    .bind(task_id)
-    .bind(payload.project_id)
-    .bind(payload.title)
-    .bind(payload.description)
-    .bind(status)
-    .bind(priority)
-    .bind(payload.due_date)
-    .fetch_one(&pool)
+        let warnings = blocked_status_warnings(conn, task_id, status).await?;
+        let next_occurrence_id = match status {
+            TaskStatus::Done => create_next_occurrence(conn, user_id, task_id).await?,
+            _ => None,
+        };
+        Ok((result, warnings, next_occurrence_id))
+    })
     .await
-    {
-        Ok(updated) => {
-            if updated {
-                let response = UpdateTaskResponse {
-                    message: "Task updated successfully".to_string(),
-                    updated: true,
-                };
-                (StatusCode::OK, Json(response)).into_response()
-            } else {
-                (
-                    StatusCode::NOT_FOUND,
-                    Json(ErrorResponse {
-                        error: "Task not found or you don't have permission to update it".to_string(),
-                    }),
-                )
-                    .into_response()
-            }
-        }
-        Err(e) => {
-            let error_message = format!("Error updating task: {}", e);
-            eprintln!("{}", error_message);
-            
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: error_message }),
-            )
-                .into_response()
-        }
-    }
-}
\ No newline at end of file
+    .map_err(task_write_error)?;
+
+    let version = updated_version(result, "Task not found or you don't have permission to update it")?;
+
+    let response = UpdateTaskResponse {
+        message: "Task updated successfully".to_string(),
+        updated: true,
+        warnings,
+        next_occurrence_id,
+    };
+    Ok(([(header::ETAG, version_tag(version))], Json(response)))
+}
@@ -0,0 +1,106 @@
+use axum::{extract::State, response::Response};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::{AppError, Validator};
+use crate::extract::{Path, Query};
+use crate::models::RecurrenceRule;
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, IfNoneMatch};
+
+use super::get::TaskResponse;
+
+#[derive(Debug, Deserialize)]
+pub struct OccurrencesQuery {
+    /// Cuántas repeticiones calcular (1 a 100, 5 por defecto)
+    pub count: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OccurrencesResponse {
+    pub task_id: Uuid,
+    pub recurrence_rule: Option<String>,
+    /// Vacío si la tarea no se repite o la serie ya terminó
+    pub occurrences: Vec<chrono::DateTime<chrono::Utc>>,
+}
+
+// Endpoint para previsualizar las próximas fechas de una tarea recurrente, las que tendrían
+// las repeticiones que se van generando al completarlas
+pub async fn get_occurrences(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Path(task_id): Path<Uuid>,
+    Query(query): Query<OccurrencesQuery>,
+) -> Result<Response, AppError> {
+    let count = query.count.unwrap_or(5);
+
+    let mut validator = Validator::new();
+    validator.check((1..=100).contains(&count), "count", "must be between 1 and 100");
+    validator.finish()?;
+
+    let task = db::as_user(&pool, user_id, async |conn| {
+        sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_task($1, $2)")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_optional(conn)
+            .await
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    let occurrences = match recurrence(&task) {
+        Some(rule) => rule.upcoming(anchor(&task), count as usize),
+        None => Vec::new(),
+    };
+
+    let response = OccurrencesResponse {
+        task_id,
+        recurrence_rule: task.recurrence_rule,
+        occurrences,
+    };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
+
+/// Crea la repetición siguiente a una tarea que se acaba de completar, en la misma
+/// transacción. `None` si la tarea no se repite, la serie terminó o ya se había generado.
+pub(super) async fn create_next_occurrence(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_id: Uuid,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    let task = sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_task($1, $2)")
+        .bind(user_id)
+        .bind(task_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    let Some((due_date, rule)) = task
+        .as_ref()
+        .and_then(|task| Some((recurrence(task)?, anchor(task))))
+        .and_then(|(rule, start)| rule.next(start))
+    else {
+        return Ok(None);
+    };
+
+    sqlx::query_scalar("SELECT create_next_occurrence($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(task_id)
+        .bind(due_date)
+        .bind(rule.to_string())
+        .fetch_one(conn)
+        .await
+}
+
+/// La regla guardada ya se validó al escribirla
+fn recurrence(task: &TaskResponse) -> Option<RecurrenceRule> {
+    task.recurrence_rule.as_deref()?.parse().ok()
+}
+
+/// La serie avanza desde la fecha límite; sin ella, desde ahora
+fn anchor(task: &TaskResponse) -> chrono::DateTime<chrono::Utc> {
+    task.due_date.unwrap_or_else(chrono::Utc::now)
+}
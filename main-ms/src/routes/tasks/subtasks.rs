@@ -0,0 +1,220 @@
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{PgConnection, PgPool};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+use crate::db;
+use crate::error::AppError;
+use crate::extract::{Json, Path};
+use crate::utils::auth::CurrentUser;
+use crate::utils::etag::{body_tag, json_with_etag, updated_version, version_tag, IfMatch, IfNoneMatch};
+
+use super::get::{TaskProgress, TaskResponse};
+use super::post::{insert_task, CreateTaskRequest, CreateTaskResponse};
+use super::put::UpdateTaskResponse;
+use super::task_write_error;
+
+#[derive(Debug, Serialize)]
+pub struct SubtasksResponse {
+    pub parent_task_id: Uuid,
+    pub subtasks: Vec<TaskResponse>,
+    pub progress: Option<TaskProgress>,
+}
+
+/// `parent_task_id` omitido o `null` deja la tarea en el nivel superior
+#[derive(Debug, Deserialize)]
+pub struct MoveTaskRequest {
+    pub parent_task_id: Option<Uuid>,
+}
+
+// Endpoint para crear una subtarea. Si no indica proyecto hereda el de la tarea padre.
+pub async fn create_subtask(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    Path(task_id): Path<Uuid>,
+    Json(payload): Json<CreateTaskRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let subtask_id = insert_task(&pool, user_id, payload, Some(task_id)).await?;
+    Ok((StatusCode::CREATED, Json(CreateTaskResponse::new(subtask_id, Some(task_id)))))
+}
+
+// Endpoint para listar las subtareas directas de una tarea, con el progreso de cada una
+pub async fn get_subtasks(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Path(task_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let subtasks = db::as_user(&pool, user_id, async |conn| {
+        if find_task(conn, user_id, task_id).await?.is_none() {
+            return Ok(None);
+        }
+
+        let mut subtasks = sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_subtasks($1, $2)")
+            .bind(user_id)
+            .bind(task_id)
+            .fetch_all(&mut *conn)
+            .await?;
+        attach_progress(conn, user_id, &mut subtasks).await?;
+        Ok(Some(subtasks))
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    let response = SubtasksResponse {
+        parent_task_id: task_id,
+        progress: TaskProgress::from_subtasks(&subtasks),
+        subtasks,
+    };
+    let etag = body_tag(&response)?;
+    Ok(json_with_etag(etag, &if_none_match, response))
+}
+
+// Endpoint para obtener una tarea con todas sus subtareas anidadas, a cualquier profundidad
+pub async fn get_task_tree(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    if_none_match: IfNoneMatch,
+    Path(task_id): Path<Uuid>,
+) -> Result<Response, AppError> {
+    let tree = db::as_user(&pool, user_id, async |conn| {
+        let Some(task) = find_task(conn, user_id, task_id).await? else {
+            return Ok(None);
+        };
+
+        let mut tree = [task];
+        attach_subtrees(conn, user_id, &mut tree).await?;
+        let [task] = tree;
+        Ok(Some(task))
+    })
+    .await?
+    .ok_or_else(|| AppError::not_found("Task not found"))?;
+
+    let etag = body_tag(&tree)?;
+    Ok(json_with_etag(etag, &if_none_match, tree))
+}
+
+// Endpoint para mover una tarea bajo otra (o al nivel superior). Acepta If-Match como PUT.
+pub async fn move_task(
+    State(pool): State<PgPool>,
+    CurrentUser(user_id): CurrentUser,
+    IfMatch(if_match): IfMatch,
+    Path(task_id): Path<Uuid>,
+    Json(payload): Json<MoveTaskRequest>,
+) -> Result<impl IntoResponse, AppError> {
+    let result = db::as_user(&pool, user_id, async |conn| {
+        write_parent(conn, user_id, task_id, payload.parent_task_id, if_match).await
+    })
+    .await
+    .map_err(task_write_error)?;
+
+    let version = updated_version(result, "Task not found or you don't have permission to update it")?;
+
+    let response = UpdateTaskResponse {
+        message: "Task moved successfully".to_string(),
+        updated: true,
+        warnings: Vec::new(),
+        next_occurrence_id: None,
+    };
+    Ok(([(header::ETAG, version_tag(version))], Json(response)))
+}
+
+/// Cambia la tarea padre en la transacción de `conn`; el resultado es el de `move_task`
+/// (ver `updated_version`)
+pub(crate) async fn write_parent(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    task_id: Uuid,
+    parent_task_id: Option<Uuid>,
+    if_match: Option<Vec<i32>>,
+) -> Result<Option<(bool, i32)>, sqlx::Error> {
+    sqlx::query_as::<_, (bool, i32)>("SELECT updated, current_version FROM move_task($1, $2, $3, $4)")
+        .bind(user_id)
+        .bind(task_id)
+        .bind(parent_task_id)
+        .bind(if_match)
+        .fetch_optional(conn)
+        .await
+}
+
+async fn find_task(conn: &mut PgConnection, user_id: Uuid, task_id: Uuid) -> Result<Option<TaskResponse>, sqlx::Error> {
+    sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_task($1, $2)")
+        .bind(user_id)
+        .bind(task_id)
+        .fetch_optional(conn)
+        .await
+}
+
+/// Completa `progress` en las tareas que tienen subtareas
+pub(super) async fn attach_progress(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    tasks: &mut [TaskResponse],
+) -> Result<(), sqlx::Error> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+
+    let task_ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let progress: HashMap<Uuid, TaskProgress> =
+        sqlx::query_as::<_, (Uuid, i32, i32)>("SELECT task_id, total, completed FROM get_subtask_progress($1, $2)")
+            .bind(user_id)
+            .bind(&task_ids)
+            .fetch_all(conn)
+            .await?
+            .into_iter()
+            .map(|(task_id, total, completed)| (task_id, TaskProgress::new(total, completed)))
+            .collect();
+
+    for task in tasks {
+        task.progress = progress.get(&task.id).copied();
+    }
+    Ok(())
+}
+
+/// Carga las subtareas de `tasks` a cualquier profundidad y las anida en `subtasks`, con el
+/// progreso de cada nivel
+pub(super) async fn attach_subtrees(
+    conn: &mut PgConnection,
+    user_id: Uuid,
+    tasks: &mut [TaskResponse],
+) -> Result<(), sqlx::Error> {
+    if tasks.is_empty() {
+        return Ok(());
+    }
+
+    let task_ids: Vec<Uuid> = tasks.iter().map(|task| task.id).collect();
+    let descendants = sqlx::query_as::<_, TaskResponse>("SELECT * FROM get_task_descendants($1, $2)")
+        .bind(user_id)
+        .bind(&task_ids)
+        .fetch_all(conn)
+        .await?;
+
+    // Vienen en orden de creación, así que cada lista de hijos queda ordenada
+    let mut children: HashMap<Uuid, Vec<TaskResponse>> = HashMap::new();
+    for task in descendants {
+        if let Some(parent_task_id) = task.parent_task_id {
+            children.entry(parent_task_id).or_default().push(task);
+        }
+    }
+
+    for task in tasks {
+        nest_subtasks(task, &mut children);
+    }
+    Ok(())
+}
+
+fn nest_subtasks(task: &mut TaskResponse, children: &mut HashMap<Uuid, Vec<TaskResponse>>) {
+    let mut subtasks = children.remove(&task.id).unwrap_or_default();
+    for subtask in &mut subtasks {
+        nest_subtasks(subtask, children);
+    }
+
+    task.progress = TaskProgress::from_subtasks(&subtasks);
+    task.subtasks = Some(subtasks);
+}
@@ -0,0 +1,39 @@
+// routes/two_factor.rs
+use axum::{
+    http::HeaderMap,
+    response::Response,
+    routing::post,
+    Router,
+};
+use serde_json::Value;
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::extract::Json;
+use crate::utils::{auth_service, extract_token::authorization_header};
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new()
+        .route(&format!("{}/setup", base), post(setup))
+        .route(&format!("{}/enable", base), post(enable))
+        .route(&format!("{}/disable", base), post(disable))
+}
+
+async fn setup(headers: HeaderMap) -> Result<Response, AppError> {
+    forward_authenticated("/api/2fa/setup", &headers, &Value::Null).await
+}
+
+async fn enable(headers: HeaderMap, Json(payload): Json<Value>) -> Result<Response, AppError> {
+    forward_authenticated("/api/2fa/enable", &headers, &payload).await
+}
+
+async fn disable(headers: HeaderMap, Json(payload): Json<Value>) -> Result<Response, AppError> {
+    forward_authenticated("/api/2fa/disable", &headers, &payload).await
+}
+
+// Todas las operaciones de 2FA requieren la sesión del usuario
+async fn forward_authenticated(path: &str, headers: &HeaderMap, payload: &Value) -> Result<Response, AppError> {
+    let authorization = authorization_header(headers)?;
+
+    auth_service::forward(path, Some(authorization), payload).await
+}
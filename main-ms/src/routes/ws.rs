@@ -0,0 +1,37 @@
+// routes/ws.rs
+use axum::{
+    extract::{ws::WebSocketUpgrade, State},
+    http::{header, HeaderMap},
+    response::Response,
+    routing::get,
+    Router,
+};
+use sqlx::PgPool;
+
+use crate::error::AppError;
+use crate::presence::{self, Credentials};
+use crate::utils::auth::authenticate_session;
+use crate::utils::extract_token::extract_token_from_headers;
+
+pub fn routes(base: &str) -> Router<PgPool> {
+    Router::new().route(base, get(connect))
+}
+
+// WebSocket de presencia y cambios en vivo de los proyectos. Con header `Authorization` se
+// autentica antes de aceptar la conexión; sin él, con el primer mensaje
+async fn connect(
+    State(pool): State<PgPool>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let credentials = match headers.contains_key(header::AUTHORIZATION) {
+        true => {
+            let token = extract_token_from_headers(&headers)?;
+            let user_id = authenticate_session(&token).await?;
+            Some(Credentials { token, user_id })
+        }
+        false => None,
+    };
+
+    Ok(ws.on_upgrade(move |socket| presence::run(socket, pool, credentials)))
+}
@@ -0,0 +1,86 @@
+// utils/auth.rs
+use axum::{
+    extract::FromRequestParts,
+    http::{request::Parts, Method},
+};
+use uuid::Uuid;
+
+use crate::error::AppError;
+use crate::utils::extract_token::extract_token_from_headers;
+use crate::utils::token::{verify_token, Principal};
+
+/// Recurso al que pertenece un grupo de rutas (`tasks`, `projects`, `tags`). Se agrega como
+/// `Extension` en `routes::api_routes` y determina el scope que necesita un token de acceso
+/// personal: `{recurso}:read` para GET/HEAD y `{recurso}:write` para el resto.
+#[derive(Debug, Clone, Copy)]
+pub struct ScopedResource(pub &'static str);
+
+/// Usuario autenticado de la petición.
+///
+/// Se usa como argumento de los handlers (`CurrentUser(user_id): CurrentUser`) para que la
+/// autenticación ocurra una sola vez y todos los rechazos tengan el mismo formato. Nuevos
+/// esquemas de autenticación se agregan en `authenticate`.
+#[derive(Debug, Clone, Copy)]
+pub struct CurrentUser(pub Uuid);
+
+impl<S> FromRequestParts<S> for CurrentUser
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        authenticate(parts).await.map(CurrentUser)
+    }
+}
+
+/// Autentica una sesión (JWT) sin pasar por el extractor, por ejemplo con el primer mensaje
+/// de un WebSocket. Como en las rutas sin `ScopedResource`, no acepta tokens de acceso
+/// personal.
+pub async fn authenticate_session(token: &str) -> Result<Uuid, AppError> {
+    let principal = verify_token(token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid or expired token".to_string()))?;
+
+    if principal.scopes.is_some() {
+        return Err(AppError::Forbidden("Access tokens cannot be used on this route".to_string()));
+    }
+
+    Ok(principal.user_id)
+}
+
+async fn authenticate(parts: &Parts) -> Result<Uuid, AppError> {
+    let token = extract_token_from_headers(&parts.headers)?;
+
+    let principal = verify_token(&token)
+        .await
+        .map_err(|_| AppError::Unauthorized("Invalid or expired token".to_string()))?;
+
+    authorize(parts, &principal).map_err(AppError::Forbidden)?;
+
+    Ok(principal.user_id)
+}
+
+/// Las sesiones tienen acceso completo; un token de acceso personal necesita el scope de la
+/// ruta. Las rutas sin `ScopedResource` no aceptan tokens de acceso personal.
+fn authorize(parts: &Parts, principal: &Principal) -> Result<(), String> {
+    let Some(ref scopes) = principal.scopes else {
+        return Ok(());
+    };
+
+    let Some(ScopedResource(resource)) = parts.extensions.get::<ScopedResource>().copied() else {
+        return Err("Access tokens cannot be used on this route".to_string());
+    };
+
+    let access = match parts.method {
+        Method::GET | Method::HEAD => "read",
+        _ => "write",
+    };
+    let required = format!("{}:{}", resource, access);
+
+    if scopes.contains(&required) {
+        Ok(())
+    } else {
+        Err(format!("Token lacks required scope: {}", required))
+    }
+}
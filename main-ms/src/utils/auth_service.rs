@@ -0,0 +1,94 @@
+// utils/auth_service.rs
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use reqwest::{Client, Method};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::config::Config;
+use crate::error::AppError;
+use crate::extract::Json;
+
+#[derive(Debug, Deserialize)]
+struct AuthErrorResponse {
+    error: String,
+}
+
+// Lo que se usa de la respuesta de `/api/me`
+#[derive(Debug, Deserialize)]
+struct AccountResponse {
+    email: String,
+}
+
+/// Reenvía una petición POST al microservicio de autenticación y devuelve su respuesta tal cual
+pub async fn forward(path: &str, authorization: Option<&str>, payload: &Value) -> Result<Response, AppError> {
+    forward_request(Method::POST, path, authorization, Some(payload)).await
+}
+
+/// Igual que `forward`, para cualquier método y con cuerpo opcional
+pub async fn forward_request(
+    method: Method,
+    path: &str,
+    authorization: Option<&str>,
+    payload: Option<&Value>,
+) -> Result<Response, AppError> {
+    // URL del microservicio de autenticación (validada al arrancar)
+    let auth_service_url = &Config::get().auth_service.url;
+
+    let client = Client::new();
+    let mut request = client.request(method, format!("{}{}", auth_service_url, path));
+
+    if let Some(payload) = payload {
+        request = request.json(payload);
+    }
+
+    if let Some(authorization) = authorization {
+        request = request.header("Authorization", authorization);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    let body = response.json::<Value>().await?;
+    Ok((status, Json(body)).into_response())
+}
+
+/// Email de la cuenta dueña del token, según auth-ms (`/api/me`)
+pub async fn account_email(authorization: &str) -> Result<String, AppError> {
+    let auth_service_url = &Config::get().auth_service.url;
+
+    let response = Client::new()
+        .get(format!("{}/api/me", auth_service_url))
+        .header("Authorization", authorization)
+        .send()
+        .await?;
+
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    let account = response.json::<AccountResponse>().await?;
+    Ok(account.email)
+}
+
+/// Convierte una respuesta de error de auth-ms en `AppError`, conservando su status y
+/// `Retry-After` (login bloqueado)
+pub async fn error_from_response(response: reqwest::Response) -> AppError {
+    let status = response.status();
+    let retry_after = response.headers().get(header::RETRY_AFTER).cloned();
+
+    match response.json::<AuthErrorResponse>().await {
+        Ok(error_response) => AppError::AuthService {
+            status,
+            message: error_response.error,
+            retry_after,
+        },
+        Err(e) => AppError::AuthServiceUnavailable(format!("Error parsing auth error response ({}): {}", status, e)),
+    }
+}
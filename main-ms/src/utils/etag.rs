@@ -0,0 +1,247 @@
+// utils/etag.rs
+//! ETags de tareas y proyectos (control de concurrencia optimista).
+//!
+//! Un recurso individual usa su columna `version` como ETag fuerte (`"3"`); PUT y PATCH
+//! comparan `If-Match` con esa versión dentro de la misma transacción que el UPDATE. Si la
+//! respuesta incluye datos de otras filas se añade un resumen de ellos (`"3-9f86d081..."`),
+//! que `If-Match` ignora. Los listados usan un ETag débil calculado sobre el cuerpo, solo
+//! para `If-None-Match`.
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::AppError;
+use crate::extract::Json;
+
+/// ETag de una fila a partir de su versión
+pub fn version_tag(version: i32) -> String {
+    format!("\"{}\"", version)
+}
+
+/// ETag de una fila cuya respuesta incluye datos de otras (por ejemplo, las dependencias de
+/// una tarea): cambia con la versión y con esos datos
+pub fn version_tag_with<T: Serialize>(version: i32, related: &T) -> Result<String, AppError> {
+    Ok(format!("\"{}-{}\"", version, &digest(related)?[..16]))
+}
+
+/// ETag débil de un listado: cambia si cambia cualquier byte de la respuesta
+pub fn body_tag<T: Serialize>(body: &T) -> Result<String, AppError> {
+    Ok(format!("W/\"{}\"", &digest(body)?[..32]))
+}
+
+fn digest<T: Serialize>(value: &T) -> Result<String, AppError> {
+    let bytes = serde_json::to_vec(value)
+        .map_err(|e| AppError::Internal(format!("Error serializing response for ETag: {}", e)))?;
+    Ok(format!("{:x}", Sha256::digest(&bytes)))
+}
+
+/// Versiones aceptadas por `If-Match`. `None` si el header no está o es `*` (basta con que
+/// el recurso exista). Los ETags débiles o que no son versiones nunca coinciden, como pide
+/// la comparación fuerte de RFC 9110.
+#[derive(Debug, Clone, Default)]
+pub struct IfMatch(pub Option<Vec<i32>>);
+
+impl<S> FromRequestParts<S> for IfMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = header_str(parts, header::IF_MATCH)? else {
+            return Ok(IfMatch(None));
+        };
+
+        if value.trim() == "*" {
+            return Ok(IfMatch(None));
+        }
+
+        let versions = value
+            .split(',')
+            .filter_map(|tag| {
+                tag.trim()
+                    .strip_prefix('"')
+                    .and_then(|tag| tag.strip_suffix('"'))
+                    .and_then(|tag| tag.split('-').next())
+                    .and_then(|version| version.parse().ok())
+            })
+            .collect();
+
+        Ok(IfMatch(Some(versions)))
+    }
+}
+
+/// ETags que el cliente ya tiene (`If-None-Match`)
+#[derive(Debug, Clone, Default)]
+pub struct IfNoneMatch(Option<String>);
+
+impl IfNoneMatch {
+    /// Comparación débil: `W/"x"` y `"x"` son equivalentes
+    pub fn matches(&self, etag: &str) -> bool {
+        let Some(ref value) = self.0 else {
+            return false;
+        };
+
+        let etag = etag.trim_start_matches("W/");
+        value.trim() == "*"
+            || value
+                .split(',')
+                .any(|tag| tag.trim().trim_start_matches("W/") == etag)
+    }
+}
+
+impl<S> FromRequestParts<S> for IfNoneMatch
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(IfNoneMatch(header_str(parts, header::IF_NONE_MATCH)?.map(str::to_string)))
+    }
+}
+
+fn header_str(parts: &Parts, name: header::HeaderName) -> Result<Option<&str>, AppError> {
+    parts
+        .headers
+        .get(&name)
+        .map(|value| {
+            value.to_str().map_err(|_| AppError::InvalidRequest {
+                status: StatusCode::BAD_REQUEST,
+                code: "invalid_header",
+                message: format!("Invalid {} header", name),
+            })
+        })
+        .transpose()
+}
+
+/// Respuesta con `ETag`, o `304 Not Modified` si el cliente ya tiene esa versión
+pub fn json_with_etag<T: Serialize>(etag: String, if_none_match: &IfNoneMatch, body: T) -> Response {
+    if if_none_match.matches(&etag) {
+        return (StatusCode::NOT_MODIFIED, [(header::ETAG, etag)]).into_response();
+    }
+
+    ([(header::ETAG, etag)], Json(body)).into_response()
+}
+
+/// Interpreta el resultado de una actualización condicionada (`update_task`, `patch_task`,
+/// `update_project`) y devuelve la versión nueva
+pub fn updated_version(result: Option<(bool, i32)>, not_found: &str) -> Result<i32, AppError> {
+    match result {
+        Some((true, version)) => Ok(version),
+        Some((false, version)) => Err(AppError::PreconditionFailed(format!(
+            "Resource has been modified; current ETag is {}",
+            version_tag(version)
+        ))),
+        None => Err(AppError::not_found(not_found)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    fn parts(name: header::HeaderName, value: &str) -> Parts {
+        Request::builder().header(name, value).body(()).unwrap().into_parts().0
+    }
+
+    async fn if_match(value: &str) -> Option<Vec<i32>> {
+        IfMatch::from_request_parts(&mut parts(header::IF_MATCH, value), &()).await.unwrap().0
+    }
+
+    fn if_none_match(value: Option<&str>) -> IfNoneMatch {
+        IfNoneMatch(value.map(str::to_string))
+    }
+
+    #[tokio::test]
+    async fn if_match_reads_versions() {
+        assert_eq!(if_match("\"3\"").await, Some(vec![3]));
+        assert_eq!(if_match("\"3\", \"5\"").await, Some(vec![3, 5]));
+        assert_eq!(if_match("\"7-9f86d081884c7d65\"").await, Some(vec![7]));
+    }
+
+    #[tokio::test]
+    async fn if_match_wildcard_or_missing_only_requires_existence() {
+        assert_eq!(if_match("*").await, None);
+
+        let mut parts = Request::builder().body(()).unwrap().into_parts().0;
+        assert_eq!(IfMatch::from_request_parts(&mut parts, &()).await.unwrap().0, None);
+    }
+
+    #[tokio::test]
+    async fn if_match_ignores_weak_and_foreign_tags() {
+        assert_eq!(if_match("W/\"3\"").await, Some(vec![]));
+        assert_eq!(if_match("\"abc\", 4, W/\"5\", \"6\"").await, Some(vec![6]));
+    }
+
+    #[tokio::test]
+    async fn rejects_non_ascii_headers() {
+        let mut parts = Request::builder()
+            .header(header::IF_MATCH, header::HeaderValue::from_bytes(b"\"\xff\"").unwrap())
+            .body(())
+            .unwrap()
+            .into_parts()
+            .0;
+
+        assert!(IfMatch::from_request_parts(&mut parts, &()).await.is_err());
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        let tag = body_tag(&vec![1, 2, 3]).unwrap();
+        let strong = tag.trim_start_matches("W/");
+
+        assert!(if_none_match(Some(&tag)).matches(&tag));
+        assert!(if_none_match(Some(strong)).matches(&tag));
+        assert!(if_none_match(Some(&format!("\"other\", {}", tag))).matches(&tag));
+        assert!(if_none_match(Some("*")).matches(&tag));
+        assert!(!if_none_match(Some("\"other\"")).matches(&tag));
+        assert!(!if_none_match(None).matches(&tag));
+    }
+
+    #[test]
+    fn version_tags() {
+        assert_eq!(version_tag(3), "\"3\"");
+
+        let tag = version_tag_with(3, &["a"]).unwrap();
+        assert!(tag.starts_with("\"3-") && tag.ends_with('"'));
+        assert_eq!(tag.len(), "\"3-\"".len() + 16);
+        assert_ne!(tag, version_tag_with(3, &["b"]).unwrap());
+        assert_eq!(tag, version_tag_with(3, &["a"]).unwrap());
+    }
+
+    #[test]
+    fn body_tags_change_with_the_body() {
+        let tag = body_tag(&vec![1]).unwrap();
+
+        assert!(tag.starts_with("W/\"") && tag.ends_with('"'));
+        assert_ne!(tag, body_tag(&vec![2]).unwrap());
+    }
+
+    #[test]
+    fn json_with_etag_returns_not_modified_for_known_tags() {
+        let tag = version_tag(2);
+
+        let response = json_with_etag(tag.clone(), &if_none_match(Some("\"2\"")), "body");
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers()[header::ETAG], tag.as_str());
+
+        let response = json_with_etag(tag, &if_none_match(Some("\"1\"")), "body");
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn updated_version_maps_outcomes() {
+        assert_eq!(updated_version(Some((true, 4)), "Task").unwrap(), 4);
+        assert!(matches!(
+            updated_version(Some((false, 4)), "Task"),
+            Err(AppError::PreconditionFailed(message)) if message.contains("\"4\"")
+        ));
+        assert!(matches!(updated_version(None, "Task"), Err(AppError::NotFound(_))));
+    }
+}
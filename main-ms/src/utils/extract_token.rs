@@ -1,34 +1,27 @@
 // utils/extract_token.rs
-use axum::{
-    http::{HeaderMap, StatusCode, header},
-    response::{Json, IntoResponse},
-};
-use serde::Serialize;
+use axum::http::{HeaderMap, header};
 
-#[derive(Debug, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-}
+use crate::error::AppError;
 
-pub fn extract_token_from_headers(headers: &HeaderMap) -> Result<String, impl IntoResponse> {
+pub fn extract_token_from_headers(headers: &HeaderMap) -> Result<String, AppError> {
     let auth_header = headers
         .get(header::AUTHORIZATION)
         .and_then(|value| value.to_str().ok())
-        .ok_or((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "Missing authorization header".to_string(),
-            }),
-        ))?;
+        .ok_or_else(|| AppError::Unauthorized("Missing authorization header".to_string()))?;
 
     if !auth_header.starts_with("Bearer ") {
-        return Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ErrorResponse {
-                error: "Invalid authorization format. Expected 'Bearer <token>'".to_string(),
-            }),
+        return Err(AppError::Unauthorized(
+            "Invalid authorization format. Expected 'Bearer <token>'".to_string(),
         ));
     }
 
     Ok(auth_header[7..].to_string()) // Remover "Bearer " prefix
-}
\ No newline at end of file
+}
+
+/// Header `Authorization` completo, para reenviarlo a auth-ms sin interpretarlo
+pub fn authorization_header(headers: &HeaderMap) -> Result<&str, AppError> {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| AppError::Unauthorized("Missing authorization header".to_string()))
+}
@@ -1,2 +1,4 @@
 pub mod token;
-pub mod extract_token;
\ No newline at end of file
+pub mod extract_token;
+pub mod auth;
+pub mod auth_service;pub mod etag;
@@ -1,119 +1,472 @@
-use reqwest::Client;
-use uuid::Uuid;
+// utils/token.rs
+use jsonwebtoken::{decode, errors::ErrorKind, Algorithm, DecodingKey, Validation};
+use reqwest::{Client, StatusCode};
 use serde::Deserialize;
-use std::env;
+use chrono::{DateTime, Utc};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, RwLock},
+    time::{Duration, Instant},
+};
+use uuid::Uuid;
+
+use crate::config::{AuthServiceConfig, Config};
+
+// Claims que auth-ms incluye en sus JWT (id del usuario, id del token y de la sesión)
+#[derive(Deserialize, Debug)]
+struct Claims {
+    id: Uuid,
+    jti: Option<Uuid>,
+    sid: Option<Uuid>,
+}
+
+// Respuesta de `/api/revocations` en auth-ms
+#[derive(Deserialize, Debug)]
+struct RevocationsResponse {
+    server_time: DateTime<Utc>,
+    revocations: Vec<Revocation>,
+}
+
+#[derive(Deserialize, Debug)]
+struct Revocation {
+    id: Uuid,
+    expires_at: DateTime<Utc>,
+}
 
 // Estructura para parsear la respuesta del servicio de autenticación
 #[derive(Deserialize, Debug)]
 struct AuthResponse {
     id: String,
 }
-pub async fn extract_user_id(token: &str) -> Result<Uuid, String> {
-    println!("[DEBUG] Starting extract_user_id function");
-    println!("[DEBUG] Received token: '{}'", token);
-    
-    // Validar que el token no esté vacío
-    if token.trim().is_empty() {
-        println!("[ERROR] Token is empty");
-        return Err("Empty token".to_string());
-    }
-    println!("[DEBUG] Token validation passed");
-
-    // Obtener URL del microservicio de autenticación
-    println!("[DEBUG] Reading AUTH_MICROSERVICE_URL environment variable");
-    let auth_url = env::var("AUTH_MICROSERVICE_URL")
-        .unwrap_or_else(|_| {
-            println!("[WARN] AUTH_MICROSERVICE_URL not set, using default: http://localhost:3000");
-            "http://localhost:3000".to_string()
-        });
-    
-    println!("[DEBUG] Auth URL: {}", auth_url);
-    let auth_endpoint = format!("{}/api/me", auth_url);
-    println!("[DEBUG] Auth endpoint: {}", auth_endpoint);
-    
-    // Crear cliente HTTP
-    println!("[DEBUG] Creating HTTP client");
-    let client = Client::new();
-    
-    // Hacer request al microservicio de autenticación
-    println!("[DEBUG] Preparing HTTP GET request to auth endpoint");
-    println!("[DEBUG] Setting Authorization header with Bearer token");
-    println!("[DEBUG] Setting timeout: 5 seconds");
-    
-    let auth_response_result = client
-        .get(&auth_endpoint)
-        .header("Authorization", format!("Bearer {}", token))
-        .timeout(std::time::Duration::from_secs(5))
-        .send()
-        .await;
-    
-    println!("[DEBUG] HTTP request completed");
-    
-    let auth_response = match auth_response_result {
-        Ok(response) => {
-            println!("[DEBUG] Successfully received response from auth service");
-            println!("[DEBUG] Response status: {}", response.status());
-            response
-        },
-        Err(e) => {
-            println!("[ERROR] Failed to contact auth service: {}", e);
-            return Err(format!("Failed to contact auth service: {}", e));
-        }
-    };
-    
-    // Verificar si la respuesta fue exitosa
-    println!("[DEBUG] Checking if response status is successful");
-    if !auth_response.status().is_success() {
-        println!("[ERROR] Auth service returned non-success status: {}", auth_response.status());
-        return Err(format!("Invalid token. Auth service status: {}", auth_response.status()));
-    }
-    println!("[DEBUG] Response status is successful");
-    
-    // Obtener el cuerpo de la respuesta
-    println!("[DEBUG] Reading response body as text");
-    let response_text = auth_response.text().await.map_err(|e| {
-        println!("[ERROR] Failed to read auth response body: {}", e);
-        format!("Failed to read auth response: {}", e)
-    })?;
-    
-    println!("[DEBUG] Response body received: {}", response_text);
-    
-    // Parsear la respuesta JSON
-    println!("[DEBUG] Attempting to parse JSON response");
-    let auth_data: AuthResponse = match serde_json::from_str(&response_text) {
-        Ok(data) => {
-            println!("[DEBUG] Successfully parsed JSON response");
-            data
-        },
-        Err(e) => {
-            println!("[ERROR] Failed to parse JSON response: {}", e);
-            println!("[ERROR] Raw response: {}", response_text);
-            return Err(format!("Failed to parse auth response: {}", e));
-        }
-    };
-    
-    println!("[DEBUG] Parsed auth data - ID: '{}'", auth_data.id);
-    
-    // Validar que el ID no esté vacío
-    println!("[DEBUG] Validating user ID is not empty");
-    if auth_data.id.trim().is_empty() {
-        println!("[ERROR] Empty user ID received from auth service");
-        return Err("Empty user ID received from auth service".to_string());
-    }
-    println!("[DEBUG] User ID validation passed");
-    
-    // Convertir a UUID
-    println!("[DEBUG] Converting user ID string to UUID");
-    match Uuid::parse_str(&auth_data.id) {
-        Ok(uuid) => {
-            println!("[DEBUG] Successfully converted to UUID: {}", uuid);
-            println!("[INFO] User ID extraction completed successfully");
-            Ok(uuid)
-        },
-        Err(e) => {
-            println!("[ERROR] Failed to parse UUID: {}", e);
-            println!("[ERROR] Invalid ID string: '{}'", auth_data.id);
-            Err(format!("Invalid UUID format: {}", e))
-        }
-    }
-}
\ No newline at end of file
+
+// Respuesta de `/api/tokens/introspect` en auth-ms
+#[derive(Deserialize, Debug)]
+struct AccessTokenIntrospection {
+    id: Uuid,
+    user_id: Uuid,
+    scopes: Vec<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// Titular de un token válido
+#[derive(Debug, Clone)]
+pub struct Principal {
+    pub user_id: Uuid,
+    /// `None` para sesiones (JWT), que tienen acceso completo; los tokens de acceso
+    /// personal solo pueden usar las rutas cubiertas por sus scopes
+    pub scopes: Option<Vec<String>>,
+}
+
+/// Prefijo de los tokens de acceso personal emitidos por auth-ms
+const ACCESS_TOKEN_PREFIX: &str = "pat_";
+
+/// Verificador de tokens, configurado una sola vez a partir de la configuración
+struct TokenVerifier {
+    decoding_key: Option<DecodingKey>,
+    validation: Validation,
+    introspection: Option<Introspection>,
+    access_tokens: AccessTokens,
+    // jti de tokens, sid de sesiones e id de tokens de acceso personal revocados,
+    // con el momento en que dejan de importar
+    revoked: RwLock<HashMap<Uuid, DateTime<Utc>>>,
+    revocation_sync: Option<RevocationSync>,
+}
+
+/// Respaldo opcional: consulta `/api/me` de auth-ms y cachea el resultado por token
+struct Introspection {
+    endpoint: String,
+    client: Client,
+    ttl: Duration,
+    cache: Mutex<HashMap<String, (Uuid, Instant)>>,
+}
+
+/// Validación de tokens de acceso personal contra auth-ms, con caché por token.
+/// Las revocaciones llegan por `RevocationSync`, así que la caché no retrasa un revoke.
+struct AccessTokens {
+    endpoint: String,
+    client: Client,
+    ttl: Duration,
+    internal_token: Option<String>,
+    cache: Mutex<HashMap<String, (AccessTokenIntrospection, Instant)>>,
+}
+
+/// Sincronización periódica de revocaciones (logout) desde auth-ms
+struct RevocationSync {
+    endpoint: String,
+    client: Client,
+    interval: Duration,
+    internal_token: Option<String>,
+}
+
+static VERIFIER: LazyLock<TokenVerifier> = LazyLock::new(TokenVerifier::from_config);
+
+/// Fuerza la lectura de la configuración al arrancar para detectar errores temprano
+/// y lanza la sincronización de revocaciones en segundo plano
+pub fn init() {
+    if VERIFIER.revocation_sync.is_some() {
+        tokio::spawn(VERIFIER.sync_revocations());
+    }
+}
+
+/// Valida el token de la petición y retorna su titular. Los JWT se validan localmente
+/// (firma, expiración, issuer, audience y revocación); los tokens de acceso personal
+/// (`pat_...`) se validan contra auth-ms.
+pub async fn verify_token(token: &str) -> Result<Principal, String> {
+    if token.starts_with(ACCESS_TOKEN_PREFIX) {
+        return VERIFIER.verify_access_token(token).await;
+    }
+
+    VERIFIER.verify(token).await.map(|user_id| Principal {
+        user_id,
+        scopes: None,
+    })
+}
+
+impl TokenVerifier {
+    fn from_config() -> Self {
+        let Config { jwt, auth_service, .. } = Config::get();
+
+        let decoding_key = Some(&jwt.secret)
+            .filter(|secret| !secret.is_empty())
+            .map(|secret| DecodingKey::from_secret(secret.as_bytes()));
+
+        let mut validation = Validation::new(Algorithm::HS256);
+        validation.set_issuer(&[&jwt.issuer]);
+        validation.set_audience(&[&jwt.audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+
+        let introspection = auth_service
+            .introspection_fallback
+            .then(|| Introspection::from_config(auth_service));
+
+        if decoding_key.is_none() && introspection.is_none() {
+            eprintln!("⚠️ JWT_SECRET no está definido y AUTH_INTROSPECTION_FALLBACK está desactivado: todos los tokens serán rechazados");
+        }
+
+        Self {
+            decoding_key,
+            validation,
+            introspection,
+            access_tokens: AccessTokens::from_config(auth_service),
+            revoked: RwLock::new(HashMap::new()),
+            revocation_sync: RevocationSync::from_config(auth_service),
+        }
+    }
+
+    async fn verify(&self, token: &str) -> Result<Uuid, String> {
+        if token.trim().is_empty() {
+            return Err("Empty token".to_string());
+        }
+
+        if let Some(ref key) = self.decoding_key {
+            match decode::<Claims>(token, key, &self.validation) {
+                Ok(data) if self.is_revoked(&data.claims) => {
+                    return Err("Token has been revoked".to_string());
+                }
+                Ok(data) => return Ok(data.claims.id),
+                // Solo se recurre a auth-ms cuando la firma no coincide con nuestra clave;
+                // un token expirado o con issuer/audience incorrectos nunca es válido
+                Err(e) if !is_key_mismatch(e.kind()) || self.introspection.is_none() => {
+                    return Err(format!("Invalid token: {}", e));
+                }
+                Err(_) => {}
+            }
+        }
+
+        let Some(ref introspection) = self.introspection else {
+            return Err("No token verification method configured".to_string());
+        };
+
+        let user_id = introspection.introspect(token).await?;
+
+        // auth-ms ya validó el token, así que sus claims sirven para comprobar la revocación
+        // en cada petición; si no, la caché mantendría válida una sesión cerrada
+        if unverified_claims(token).is_some_and(|claims| self.is_revoked(&claims)) {
+            return Err("Token has been revoked".to_string());
+        }
+        Ok(user_id)
+    }
+
+    async fn verify_access_token(&self, token: &str) -> Result<Principal, String> {
+        let access_token = self.access_tokens.introspect(token).await?;
+
+        if self.revoked.read().unwrap().contains_key(&access_token.id) {
+            return Err("Token has been revoked".to_string());
+        }
+
+        Ok(Principal {
+            user_id: access_token.user_id,
+            scopes: Some(access_token.scopes),
+        })
+    }
+
+    fn is_revoked(&self, claims: &Claims) -> bool {
+        let revoked = self.revoked.read().unwrap();
+        [claims.jti, claims.sid]
+            .iter()
+            .flatten()
+            .any(|id| revoked.contains_key(id))
+    }
+
+    /// Consulta auth-ms cada `interval` y mantiene la lista de revocaciones vigentes.
+    /// Si auth-ms no responde se conserva la última lista conocida.
+    async fn sync_revocations(&self) {
+        let Some(ref sync) = self.revocation_sync else {
+            return;
+        };
+
+        let mut since: Option<DateTime<Utc>> = None;
+
+        loop {
+            match sync.fetch(since).await {
+                Ok(response) => {
+                    let now = Utc::now();
+                    let mut revoked = self.revoked.write().unwrap();
+                    revoked.retain(|_, expires_at| *expires_at > now);
+                    for revocation in response.revocations {
+                        revoked.insert(revocation.id, revocation.expires_at);
+                    }
+                    // Pequeño solapamiento para no perder revocaciones por desfase de relojes
+                    since = Some(response.server_time - chrono::Duration::seconds(5));
+                }
+                Err(e) => eprintln!("Error syncing token revocations: {}", e),
+            }
+
+            tokio::time::sleep(sync.interval).await;
+        }
+    }
+}
+
+fn is_key_mismatch(kind: &ErrorKind) -> bool {
+    matches!(kind, ErrorKind::InvalidSignature | ErrorKind::InvalidAlgorithm)
+}
+
+/// Claims de un JWT sin verificar la firma ni la expiración. Solo para tokens que auth-ms
+/// ya validó; `None` si el token no es un JWT.
+fn unverified_claims(token: &str) -> Option<Claims> {
+    let mut validation = Validation::default();
+    validation.insecure_disable_signature_validation();
+    validation.validate_exp = false;
+    validation.validate_aud = false;
+    validation.required_spec_claims.clear();
+
+    decode::<Claims>(token, &DecodingKey::from_secret(&[]), &validation)
+        .ok()
+        .map(|data| data.claims)
+}
+
+impl Introspection {
+    fn from_config(config: &AuthServiceConfig) -> Self {
+        Self {
+            endpoint: format!("{}/api/me", config.url),
+            client: Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .expect("No se pudo crear el cliente HTTP"),
+            ttl: Duration::from_secs(config.introspection_cache_secs),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn introspect(&self, token: &str) -> Result<Uuid, String> {
+        if let Some(user_id) = self.cached(token) {
+            return Ok(user_id);
+        }
+
+        let response = self
+            .client
+            .get(&self.endpoint)
+            .bearer_auth(token)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to contact auth service: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Invalid token. Auth service status: {}", response.status()));
+        }
+
+        let auth_data: AuthResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse auth response: {}", e))?;
+
+        let user_id = Uuid::parse_str(&auth_data.id)
+            .map_err(|e| format!("Invalid UUID format: {}", e))?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (_, cached_at)| cached_at.elapsed() < self.ttl);
+        cache.insert(token.to_string(), (user_id, Instant::now()));
+
+        Ok(user_id)
+    }
+
+    fn cached(&self, token: &str) -> Option<Uuid> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(token)
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            .map(|(user_id, _)| *user_id)
+    }
+}
+
+impl AccessTokens {
+    fn from_config(config: &AuthServiceConfig) -> Self {
+        Self {
+            endpoint: format!("{}/api/tokens/introspect", config.url),
+            client: Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .expect("No se pudo crear el cliente HTTP"),
+            ttl: Duration::from_secs(config.access_token_cache_secs),
+            internal_token: config.internal_token(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn introspect(&self, token: &str) -> Result<AccessTokenIntrospection, String> {
+        if let Some(access_token) = self.cached(token) {
+            return Ok(access_token);
+        }
+
+        let mut request = self
+            .client
+            .post(&self.endpoint)
+            .json(&serde_json::json!({ "token": token }));
+
+        if let Some(ref internal_token) = self.internal_token {
+            request = request.header("X-Internal-Token", internal_token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to contact auth service: {}", e))?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Err("Invalid or expired access token".to_string());
+        }
+
+        if !response.status().is_success() {
+            return Err(format!("Auth service status: {}", response.status()));
+        }
+
+        let access_token: AccessTokenIntrospection = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse access token introspection: {}", e))?;
+
+        let mut cache = self.cache.lock().unwrap();
+        cache.retain(|_, (_, cached_at)| cached_at.elapsed() < self.ttl);
+        cache.insert(
+            token.to_string(),
+            (
+                AccessTokenIntrospection {
+                    id: access_token.id,
+                    user_id: access_token.user_id,
+                    scopes: access_token.scopes.clone(),
+                    expires_at: access_token.expires_at,
+                },
+                Instant::now(),
+            ),
+        );
+
+        Ok(access_token)
+    }
+
+    fn cached(&self, token: &str) -> Option<AccessTokenIntrospection> {
+        let cache = self.cache.lock().unwrap();
+        cache
+            .get(token)
+            .filter(|(_, cached_at)| cached_at.elapsed() < self.ttl)
+            // Un token que expira mientras está en caché deja de ser válido
+            .filter(|(access_token, _)| access_token.expires_at.is_none_or(|expires_at| expires_at > Utc::now()))
+            .map(|(access_token, _)| AccessTokenIntrospection {
+                id: access_token.id,
+                user_id: access_token.user_id,
+                scopes: access_token.scopes.clone(),
+                expires_at: access_token.expires_at,
+            })
+    }
+}
+
+impl RevocationSync {
+    fn from_config(config: &AuthServiceConfig) -> Option<Self> {
+        // 0 desactiva la sincronización (los tokens revocados siguen válidos hasta expirar)
+        if config.revocation_sync_secs == 0 {
+            return None;
+        }
+
+        Some(Self {
+            endpoint: format!("{}/api/revocations", config.url),
+            client: Client::builder()
+                .timeout(Duration::from_secs(5))
+                .build()
+                .expect("No se pudo crear el cliente HTTP"),
+            interval: Duration::from_secs(config.revocation_sync_secs),
+            internal_token: config.internal_token(),
+        })
+    }
+
+    async fn fetch(&self, since: Option<DateTime<Utc>>) -> Result<RevocationsResponse, String> {
+        let mut request = self.client.get(&self.endpoint);
+
+        if let Some(since) = since {
+            request = request.query(&[("since", since.to_rfc3339())]);
+        }
+
+        if let Some(ref token) = self.internal_token {
+            request = request.header("X-Internal-Token", token);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to contact auth service: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Auth service status: {}", response.status()));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse revocations: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    fn token(claims: serde_json::Value) -> String {
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(b"otra-clave")).unwrap()
+    }
+
+    #[test]
+    fn unverified_claims_reads_tokens_signed_with_another_key() {
+        let (id, jti, sid) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let token = token(serde_json::json!({ "id": id, "jti": jti, "sid": sid, "exp": 1, "aud": "otro" }));
+
+        let claims = unverified_claims(&token).unwrap();
+
+        assert_eq!(claims.id, id);
+        assert_eq!(claims.jti, Some(jti));
+        assert_eq!(claims.sid, Some(sid));
+    }
+
+    #[test]
+    fn unverified_claims_accepts_tokens_without_session_claims() {
+        let id = Uuid::new_v4();
+
+        let claims = unverified_claims(&token(serde_json::json!({ "id": id }))).unwrap();
+
+        assert_eq!(claims.id, id);
+        assert_eq!((claims.jti, claims.sid), (None, None));
+    }
+
+    #[test]
+    fn unverified_claims_ignores_opaque_tokens() {
+        assert!(unverified_claims("opaque-token").is_none());
+    }
+}
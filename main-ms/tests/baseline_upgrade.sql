@@ -0,0 +1,82 @@
+-- Verifica que las migraciones actualizan una base creada con el antiguo init.sql
+-- (tests/fixtures/init.sql) sin perder datos y que 0001_baseline.sql se puede aplicar dos
+-- veces. El init.sql antiguo borra las tablas, así que se ejecuta sobre una base vacía:
+--
+--   createdb main_upgrade_test
+--   psql -v ON_ERROR_STOP=1 -d main_upgrade_test -f tests/baseline_upgrade.sql
+--   dropdb main_upgrade_test
+--
+-- Todo ocurre en una transacción que se revierte al final.
+\set QUIET on
+\set user_id '00000000-0000-4000-8000-00000000000a'
+\set project_id '00000000-0000-4000-8000-0000000000a1'
+\set task_id '00000000-0000-4000-8000-0000000000a2'
+\set tag_id '00000000-0000-4000-8000-0000000000a3'
+
+BEGIN;
+-- Las dos pasadas avisan de cada objeto que ya existe
+SET LOCAL client_min_messages = warning;
+
+-- Base del antiguo init.sql, con datos anteriores a la actualización. Se descarta lo que
+-- imprimen los scripts.
+\o /dev/null
+\ir fixtures/init.sql
+INSERT INTO projects (id, user_id, name) VALUES (:'project_id', :'user_id', 'Proyecto antiguo');
+INSERT INTO tasks (id, user_id, project_id, title, status, priority)
+VALUES (:'task_id', :'user_id', :'project_id', 'Tarea antigua', 'doing', 'high');
+INSERT INTO tags (id, user_id, normalized_name, display_name) VALUES (:'tag_id', :'user_id', 'antigua', 'Antigua');
+INSERT INTO task_tags (task_id, tag_id) VALUES (:'task_id', :'tag_id');
+
+\ir ../migrations/0001_baseline.sql
+-- Idempotente: aplicarla de nuevo no cambia nada
+\ir ../migrations/0001_baseline.sql
+\ir ../migrations/0002_referential_integrity.sql
+\ir ../migrations/0003_urgent_priority.sql
+\ir ../migrations/0004_task_patch.sql
+\ir ../migrations/0005_row_versions.sql
+\ir ../migrations/0006_subtasks.sql
+\ir ../migrations/0007_task_dependencies.sql
+\ir ../migrations/0008_recurrence.sql
+\ir ../migrations/0009_reminders.sql
+\ir ../migrations/0010_notifications.sql
+\ir ../migrations/0011_change_events.sql
+\ir ../migrations/0012_sync.sql
+\ir ../migrations/0013_task_dependency_owner.sql
+\ir ../migrations/0014_change_event_project.sql
+\ir ../migrations/0015_project_cascade_count.sql
+\o
+
+SELECT set_config('app.current_user_id', :'user_id', true),
+       set_config('test.user_id', :'user_id', true),
+       set_config('test.task_id', :'task_id', true) \gset
+
+SET LOCAL ROLE todo_app_user;
+
+DO $$
+DECLARE
+    user_id UUID := current_setting('test.user_id')::UUID;
+    task_id UUID := current_setting('test.task_id')::UUID;
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM get_task(user_id, task_id) t
+        WHERE t.title = 'Tarea antigua' AND t.status = 'doing' AND t.priority = 'high'
+    ) THEN
+        RAISE EXCEPTION 'Existing tasks were lost or changed by the upgrade';
+    END IF;
+
+    IF (SELECT count(*) FROM projects) <> 1
+       OR (SELECT count(*) FROM tags) <> 1
+       OR (SELECT count(*) FROM task_tags) <> 1 THEN
+        RAISE EXCEPTION 'Existing projects, tags or task tags were lost by the upgrade';
+    END IF;
+
+    -- Las funciones nuevas funcionan sobre los datos anteriores
+    IF NOT EXISTS (SELECT 1 FROM get_sync_snapshot(user_id) WHERE entity_id = task_id) THEN
+        RAISE EXCEPTION 'Existing tasks are missing from the sync snapshot';
+    END IF;
+END;
+$$;
+
+ROLLBACK;
+
+\echo 'Baseline upgrade checks passed'
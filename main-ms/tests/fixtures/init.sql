@@ -1,3 +1,4 @@
+-- Copia del antiguo init.sql (anterior a las migraciones), usada por tests/baseline_upgrade.sql
 -- Add migration script here
 -- Add migration script here
 -- Crear el rol de usuario para la aplicación
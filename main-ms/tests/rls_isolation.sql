@@ -0,0 +1,222 @@
+-- Verifica que Row Level Security aísla a los usuarios cuando main-ms se conecta como
+-- todo_app_user. Se ejecuta con un rol que pueda asumir todo_app_user, después de
+-- aplicar las migraciones (`main-ms migrate`):
+--
+--   psql -v ON_ERROR_STOP=1 -d main_db -f tests/rls_isolation.sql
+--
+-- Todo ocurre en una transacción que se revierte al final, así que no deja datos.
+-- Cualquier fallo aborta el script con un error que describe la fuga.
+\set user_a '00000000-0000-4000-8000-00000000000a'
+\set user_b '00000000-0000-4000-8000-00000000000b'
+
+BEGIN;
+SET LOCAL ROLE todo_app_user;
+
+-- Datos del usuario A
+SELECT set_config('app.current_user_id', :'user_a', true) \gset
+SELECT create_project(:'user_a', 'Proyecto de A') AS project_a \gset
+SELECT create_task(:'user_a', 'Tarea de A', :'project_a') AS task_a \gset
+SELECT create_task(:'user_a', 'Otra tarea de A') AS task_a2 \gset
+SELECT add_task_dependency(:'user_a', :'task_a', :'task_a2') \gset
+SELECT create_tag(:'user_a', 'tag-a') AS tag_a \gset
+SELECT add_tag_to_task(:'user_a', :'task_a', :'tag_a') \gset
+SELECT create_user_profile(:'user_a', 'Usuario A') \gset
+SELECT create_task_reminder(:'user_a', :'task_a', NULL, 30, ARRAY['in_app']) AS reminder_a \gset
+SELECT create_notification(:'user_a', 'reminder', 'Aviso de A', NULL, :'task_a') AS notification_a \gset
+SELECT set_notification_preference(:'user_a', 'task_overdue', false) \gset
+
+SELECT set_config('test.user_a', :'user_a', true),
+       set_config('test.task_a', :'task_a', true),
+       set_config('test.task_a2', :'task_a2', true),
+       set_config('test.tag_a', :'tag_a', true),
+       set_config('test.project_a', :'project_a', true),
+       set_config('test.reminder_a', :'reminder_a', true),
+       set_config('test.notification_a', :'notification_a', true) \gset
+
+-- El usuario B no ve ni modifica nada de A, aunque pase el id de A a las funciones
+SELECT set_config('app.current_user_id', :'user_b', true) \gset
+SELECT create_tag(:'user_b', 'tag-b') AS tag_b \gset
+SELECT set_config('test.tag_b', :'tag_b', true) \gset
+
+DO $$
+DECLARE
+    user_a UUID := current_setting('test.user_a')::UUID;
+    task_a UUID := current_setting('test.task_a')::UUID;
+    task_a2 UUID := current_setting('test.task_a2')::UUID;
+    tag_a UUID := current_setting('test.tag_a')::UUID;
+    tag_b UUID := current_setting('test.tag_b')::UUID;
+    project_a UUID := current_setting('test.project_a')::UUID;
+    reminder_a UUID := current_setting('test.reminder_a')::UUID;
+    notification_a UUID := current_setting('test.notification_a')::UUID;
+BEGIN
+    IF EXISTS (SELECT 1 FROM tasks) OR EXISTS (SELECT 1 FROM projects)
+       OR EXISTS (SELECT 1 FROM user_profiles) OR EXISTS (SELECT 1 FROM task_tags)
+       OR EXISTS (SELECT 1 FROM task_reminders) OR EXISTS (SELECT 1 FROM notifications)
+       OR EXISTS (SELECT 1 FROM notification_preferences) OR EXISTS (SELECT 1 FROM task_overdue_notices)
+       OR EXISTS (SELECT 1 FROM change_events WHERE user_id = user_a)
+       OR EXISTS (SELECT 1 FROM tags WHERE id = tag_a) THEN
+        RAISE EXCEPTION 'RLS leak: user B can read rows owned by user A';
+    END IF;
+
+    IF EXISTS (SELECT 1 FROM get_task(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM get_user_tasks_paginated(user_a))
+       OR (SELECT count FROM get_user_tasks_count(user_a)) <> 0
+       OR EXISTS (SELECT 1 FROM get_user_projects(user_a))
+       OR EXISTS (SELECT 1 FROM get_user_tags(user_a))
+       OR EXISTS (SELECT 1 FROM get_user_profile(user_a))
+       OR EXISTS (SELECT 1 FROM get_tasks_by_tag(user_a, tag_a))
+       OR EXISTS (SELECT 1 FROM get_subtasks(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM get_task_descendants(user_a, ARRAY[task_a]::UUID[]))
+       OR EXISTS (SELECT 1 FROM get_subtask_progress(user_a, ARRAY[task_a]::UUID[]))
+       OR EXISTS (SELECT 1 FROM get_task_blockers(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM get_blocked_tasks(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM task_dependencies)
+       OR EXISTS (SELECT 1 FROM get_task_reminders(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM get_task_reminder(user_a, reminder_a))
+       OR EXISTS (SELECT 1 FROM get_user_reminders(user_a))
+       OR (SELECT count FROM get_user_reminders_count(user_a)) <> 0
+       OR EXISTS (SELECT 1 FROM get_user_notifications(user_a))
+       OR (SELECT total_count FROM get_user_notifications_count(user_a)) <> 0
+       OR EXISTS (SELECT 1 FROM get_notification_preferences(user_a))
+       OR EXISTS (SELECT 1 FROM get_change_events(user_a, 0))
+       OR get_last_change_event_id(user_a) <> 0
+       OR EXISTS (SELECT 1 FROM get_sync_snapshot(user_a))
+       OR EXISTS (SELECT 1 FROM get_sync_changes(user_a, 0, 100)) THEN
+        RAISE EXCEPTION 'RLS leak: functions return user A rows to user B';
+    END IF;
+
+    -- Las actualizaciones condicionadas devuelven una fila si la tarea o el proyecto existen
+    IF EXISTS (SELECT 1 FROM update_task(user_a, task_a, NULL, 'hijacked', NULL, 'todo', 'med', NULL))
+       OR EXISTS (SELECT 1 FROM patch_task(user_a, task_a, '{"title": "hijacked"}'))
+       OR EXISTS (SELECT 1 FROM move_task(user_a, task_a, NULL))
+       OR EXISTS (SELECT 1 FROM update_project(user_a, project_a, 'hijacked'))
+       OR update_tag(user_a, tag_a, 'hijacked')
+       OR EXISTS (SELECT 1 FROM delete_task(user_a, task_a))
+       OR EXISTS (SELECT 1 FROM delete_task(user_a, task_a, ARRAY[1]))
+       OR EXISTS (SELECT 1 FROM delete_project(user_a, project_a, 'cascade'))
+       OR EXISTS (SELECT 1 FROM delete_project(user_a, project_a, 'cascade', ARRAY[1]))
+       OR delete_tag(user_a, tag_a)
+       OR remove_tag_from_task(user_a, task_a, tag_a)
+       OR remove_task_dependency(user_a, task_a, task_a2)
+       OR create_next_occurrence(user_a, task_a, now(), 'FREQ=DAILY') IS NOT NULL
+       OR delete_task_reminder(user_a, reminder_a)
+       OR record_reminder_delivery(user_a, reminder_a, 'fired', '{}')
+       OR mark_notification_read(user_a, notification_a)
+       OR mark_all_notifications_read(user_a) <> 0
+       OR delete_notification(user_a, notification_a)
+       OR add_tag_to_task(user_a, task_a, tag_b) THEN
+        RAISE EXCEPTION 'RLS leak: user B can modify rows owned by user A';
+    END IF;
+
+    BEGIN
+        INSERT INTO tasks (user_id, title) VALUES (user_a, 'Tarea inyectada');
+        RAISE EXCEPTION 'RLS leak: user B can insert rows for user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    BEGIN
+        INSERT INTO change_events (user_id, entity, action, entity_id) VALUES (user_a, 'task', 'deleted', task_a);
+        RAISE EXCEPTION 'RLS leak: user B can write change events for user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    BEGIN
+        PERFORM set_notification_preference(user_a, 'reminder', false);
+        RAISE EXCEPTION 'RLS leak: user B can change the notification preferences of user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    -- El dueño de una dependencia es siempre el usuario de la transacción, y las claves
+    -- foráneas compuestas impiden relacionar tareas de B con tareas de A
+    BEGIN
+        PERFORM add_task_dependency(user_a, task_a, task_a2);
+        RAISE EXCEPTION 'RLS leak: user B can add dependencies on behalf of user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    BEGIN
+        INSERT INTO task_dependencies (task_id, blocked_by_task_id, user_id) VALUES (task_a2, task_a, user_a);
+        RAISE EXCEPTION 'RLS leak: user B can insert dependencies owned by user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+
+    BEGIN
+        PERFORM add_task_dependency(current_setting('app.current_user_id')::UUID, task_a2, task_a);
+        RAISE EXCEPTION 'RLS leak: user B can add dependencies between tasks owned by user A';
+    EXCEPTION WHEN foreign_key_violation THEN
+        NULL;
+    END;
+
+    BEGIN
+        PERFORM create_task(current_setting('app.current_user_id')::UUID, 'Subtarea inyectada',
+                            NULL, NULL, 'todo', 'med', NULL, task_a);
+        RAISE EXCEPTION 'RLS leak: user B can add subtasks to tasks owned by user A';
+    EXCEPTION WHEN foreign_key_violation THEN
+        NULL;
+    END;
+
+    BEGIN
+        PERFORM create_task_reminder(current_setting('app.current_user_id')::UUID, task_a, NULL, 5, ARRAY['in_app']);
+        RAISE EXCEPTION 'RLS leak: user B can add reminders to tasks owned by user A';
+    EXCEPTION WHEN foreign_key_violation THEN
+        NULL;
+    END;
+
+    BEGIN
+        INSERT INTO task_tags (task_id, tag_id) VALUES (task_a, tag_b);
+        RAISE EXCEPTION 'RLS leak: user B can tag tasks owned by user A';
+    EXCEPTION WHEN insufficient_privilege THEN
+        NULL;
+    END;
+END;
+$$;
+
+-- Sin usuario fijado no se ve nada
+SELECT set_config('app.current_user_id', '', true) \gset
+
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM tasks) OR EXISTS (SELECT 1 FROM tags) THEN
+        RAISE EXCEPTION 'RLS leak: rows visible without app.current_user_id';
+    END IF;
+END;
+$$;
+
+-- El usuario A sigue viendo sus datos intactos
+SELECT set_config('app.current_user_id', :'user_a', true) \gset
+
+DO $$
+DECLARE
+    task_a UUID := current_setting('test.task_a')::UUID;
+BEGIN
+    IF NOT EXISTS (SELECT 1 FROM get_task(current_setting('test.user_a')::UUID, task_a) WHERE title = 'Tarea de A')
+       OR (SELECT count(*) FROM task_tags WHERE task_id = task_a) <> 1
+       OR (SELECT count(*) FROM task_dependencies WHERE task_id = task_a) <> 1
+       OR (SELECT count(*) FROM task_reminders WHERE task_id = task_a) <> 1
+       OR (SELECT count(*) FROM notifications WHERE read_at IS NULL) <> 1
+       OR (SELECT count(*) FROM notification_preferences WHERE NOT enabled) <> 1
+       OR NOT EXISTS (SELECT 1 FROM get_change_events(current_setting('test.user_a')::UUID, 0) WHERE entity = 'task_tag')
+       OR NOT EXISTS (SELECT 1 FROM get_sync_snapshot(current_setting('test.user_a')::UUID) WHERE entity_id = task_a)
+       OR NOT EXISTS (SELECT 1 FROM get_sync_changes(current_setting('test.user_a')::UUID, 0, 100) WHERE entity_id = task_a) THEN
+        RAISE EXCEPTION 'User A cannot see their own rows';
+    END IF;
+END;
+$$;
+
+ROLLBACK;
+
+-- El usuario fijado con SET LOCAL no sobrevive a la transacción
+DO $$
+BEGIN
+    IF NULLIF(current_setting('app.current_user_id', true), '') IS NOT NULL THEN
+        RAISE EXCEPTION 'app.current_user_id leaked outside the transaction';
+    END IF;
+END;
+$$;
+
+\echo 'RLS isolation checks passed'